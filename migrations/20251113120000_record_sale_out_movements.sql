@@ -0,0 +1,95 @@
+-- Migration: Record sale_out stock movements for every sale item
+-- Sales now write a 'sale_out' movement per sale line (reference_type 'sale').
+-- Stock leaves the warehouse when it is loaded onto a truck (truck_load_out), so
+-- sale_out is an audit entry of stock leaving the truck and does not change the
+-- warehouse balance of the batch.
+
+BEGIN;
+
+-- The truck loads migration created update_truck_load_sold, and the sale item
+-- trigger fix added update_truck_load_quantity without dropping it. Both fire
+-- AFTER INSERT on sale_items, so quantity_sold was incremented twice per sale.
+DROP TRIGGER IF EXISTS update_truck_load_sold ON sale_items;
+DROP FUNCTION IF EXISTS update_truck_load_item_sold();
+
+-- Recompute quantity_sold from the sale lines actually recorded
+UPDATE truck_load_items tli
+SET quantity_sold = COALESCE((
+    SELECT SUM(si.quantity)
+    FROM sale_items si
+    JOIN sales s ON s.id = si.sale_id
+    WHERE s.truck_load_id = tli.truck_load_id
+      AND si.batch_id = tli.batch_id
+), 0);
+
+-- Warehouse balance: sale_out is informational (already deducted by truck_load_out)
+CREATE OR REPLACE VIEW batch_stock_balance AS
+SELECT
+    b.id as batch_id,
+    b.product_id,
+    p.name as product_name,
+    b.quantity as initial_quantity,
+    b.remaining_quantity,
+    COALESCE(SUM(
+        CASE
+            WHEN sm.movement_type IN ('delivery_in', 'truck_return_in', 'adjustment')
+            THEN sm.quantity
+            WHEN sm.movement_type = 'sale_out'
+            THEN 0
+            ELSE -sm.quantity
+        END
+    ), 0) as calculated_balance,
+    -- Verify integrity
+    (b.remaining_quantity = COALESCE(SUM(
+        CASE
+            WHEN sm.movement_type IN ('delivery_in', 'truck_return_in', 'adjustment')
+            THEN sm.quantity
+            WHEN sm.movement_type = 'sale_out'
+            THEN 0
+            ELSE -sm.quantity
+        END
+    ), 0)) as balance_matches
+FROM batches b
+JOIN products p ON b.product_id = p.id
+LEFT JOIN stock_movements sm ON sm.batch_id = b.id
+GROUP BY b.id, b.product_id, p.name, b.quantity, b.remaining_quantity;
+
+-- Backfill sale_out movements for historical sales.
+-- Idempotent: (sale, batch) pairs that already have a sale_out movement are skipped,
+-- so this statement can be re-run safely after importing old sales.
+INSERT INTO stock_movements (
+    batch_id,
+    product_id,
+    movement_type,
+    quantity,
+    reference_type,
+    reference_id,
+    notes,
+    created_by,
+    movement_date
+)
+SELECT
+    si.batch_id,
+    b.product_id,
+    'sale_out',
+    SUM(si.quantity),
+    'sale',
+    s.id,
+    'Sold to shop - Batch: ' || b.batch_number || ' (backfilled)',
+    s.user_id,
+    s.sale_date
+FROM sale_items si
+JOIN sales s ON s.id = si.sale_id
+JOIN batches b ON b.id = si.batch_id
+WHERE NOT EXISTS (
+    SELECT 1 FROM stock_movements sm
+    WHERE sm.reference_type = 'sale'
+      AND sm.reference_id = s.id
+      AND sm.batch_id = si.batch_id
+      AND sm.movement_type = 'sale_out'
+)
+GROUP BY si.batch_id, b.product_id, b.batch_number, s.id, s.user_id, s.sale_date;
+
+COMMENT ON VIEW batch_stock_balance IS 'Warehouse balance verification for each batch (sale_out is informational, stock leaves via truck_load_out)';
+
+COMMIT;
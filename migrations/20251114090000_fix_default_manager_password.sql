@@ -0,0 +1,14 @@
+-- Migration: Give the seeded default manager a usable password hash
+-- Registration now requires an authenticated manager, so the seeded 'manager'
+-- account is the only way to bootstrap a fresh database. The hash written by the
+-- initial schema is not a valid bcrypt string and every login attempt failed.
+-- Password stays "manager123" - change it via PUT /users/me/password after first login.
+
+BEGIN;
+
+UPDATE users
+SET password_hash = '$2a$12$7ciJGHVvTZvoNjAEGA45iusJU7lPsJWZFR1a027CMRVLCw0ewjD8S'
+WHERE username = 'manager'
+  AND password_hash = '$2b$12$LQv3c1yqBWVHxkd0g8f7QuYlC5nB.8qkQ8p8Nc6b5a6d5e4f3g2h1i';
+
+COMMIT;
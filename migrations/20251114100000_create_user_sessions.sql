@@ -0,0 +1,26 @@
+-- Migration: Refresh-token sessions
+-- Each login creates a session holding the SHA-256 hash of its current refresh token
+-- (the raw token is never stored). Refreshing rotates the token in place; the previous
+-- hash is kept so a replayed old token can be detected and the session revoked.
+-- Access tokens carry the session id, and require_auth rejects them once the session
+-- is revoked or the user is deactivated.
+
+BEGIN;
+
+CREATE TABLE user_sessions (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    refresh_token_hash TEXT NOT NULL UNIQUE,
+    previous_token_hash TEXT,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_refreshed_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_user_sessions_user_id ON user_sessions(user_id);
+CREATE INDEX idx_user_sessions_previous_token_hash ON user_sessions(previous_token_hash);
+
+COMMENT ON TABLE user_sessions IS 'Login sessions with hashed rotating refresh tokens; revoked_at set on logout, deactivation or password reset';
+
+COMMIT;
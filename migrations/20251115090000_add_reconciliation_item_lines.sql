@@ -0,0 +1,41 @@
+-- Migration: Per-product returns and discards for truck verification
+-- verify_truck_return used to keep only the summed quantities. Each returned or
+-- discarded product line is now stored against the truck load item (batch) it was
+-- taken from, with the discard reason, so discrepancies can be traced per product.
+
+BEGIN;
+
+-- A truck is verified with whatever actually came back; units that are neither
+-- sold, returned nor discarded are a discrepancy, not an invalid row. The old
+-- equality checks also rejected the unverified rows created by start_reconciliation.
+ALTER TABLE reconciliation_items DROP CONSTRAINT valid_stock_balance;
+ALTER TABLE reconciliation_items ADD CONSTRAINT valid_stock_balance CHECK (
+    items_sold + items_returned + items_discarded <= items_loaded
+);
+
+ALTER TABLE daily_reconciliations DROP CONSTRAINT valid_item_counts;
+ALTER TABLE daily_reconciliations ADD CONSTRAINT valid_item_counts CHECK (
+    total_items_sold + total_items_returned + total_items_discarded <= total_items_loaded
+);
+
+CREATE TABLE reconciliation_item_lines (
+    id SERIAL PRIMARY KEY,
+    reconciliation_item_id INTEGER NOT NULL REFERENCES reconciliation_items(id) ON DELETE CASCADE,
+    truck_load_item_id BIGINT NOT NULL REFERENCES truck_load_items(id) ON DELETE RESTRICT,
+    batch_id BIGINT NOT NULL REFERENCES batches(id),
+    product_id BIGINT NOT NULL REFERENCES products(id),
+    line_type VARCHAR(20) NOT NULL CHECK (line_type IN ('returned', 'discarded')),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    discard_reason VARCHAR(20) CHECK (discard_reason IN ('damaged', 'expired', 'wasted')),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CONSTRAINT discard_has_reason CHECK (
+        (line_type = 'discarded') = (discard_reason IS NOT NULL)
+    )
+);
+
+CREATE INDEX idx_reconciliation_item_lines_item ON reconciliation_item_lines(reconciliation_item_id);
+CREATE INDEX idx_reconciliation_item_lines_product ON reconciliation_item_lines(product_id);
+
+COMMENT ON TABLE reconciliation_item_lines IS 'Verified returned/discarded quantities per truck load batch for a reconciliation item';
+
+COMMIT;
@@ -0,0 +1,90 @@
+-- Migration: Truck discards written off at reconciliation
+-- finalize_reconciliation now restocks only the verified returned quantities and logs
+-- discarded quantities as 'expired_out' / 'adjustment' movements with reference_type
+-- 'reconciliation'. That stock already left the warehouse balance with its
+-- truck_load_out movement, so like sale_out these write-offs are informational and
+-- must not be subtracted (or added) a second time.
+-- The discarded units are recorded on the truck load item as quantity_discarded so
+-- they are no longer available to sell on the load.
+
+BEGIN;
+
+ALTER TABLE truck_load_items
+    ADD COLUMN quantity_discarded INTEGER NOT NULL DEFAULT 0 CHECK (quantity_discarded >= 0);
+
+-- Discards already written off at reconciliation
+UPDATE truck_load_items tli
+SET quantity_discarded = LEAST(d.quantity, tli.quantity_loaded - tli.quantity_sold - tli.quantity_returned)
+FROM (
+    SELECT l.truck_load_item_id, SUM(l.quantity)::INTEGER as quantity
+    FROM reconciliation_item_lines l
+    JOIN truck_load_items i ON l.truck_load_item_id = i.id
+    JOIN truck_loads tl ON i.truck_load_id = tl.id
+    WHERE l.line_type = 'discarded' AND tl.status = 'reconciled'
+    GROUP BY l.truck_load_item_id
+) d
+WHERE tli.id = d.truck_load_item_id
+AND tli.quantity_loaded - tli.quantity_sold - tli.quantity_returned > 0;
+
+ALTER TABLE truck_load_items DROP CONSTRAINT truck_load_items_check1;
+ALTER TABLE truck_load_items ADD CONSTRAINT truck_load_items_check1
+    CHECK (quantity_sold + quantity_returned + quantity_discarded <= quantity_loaded);
+
+-- Available to sell = loaded - sold - returned - discarded
+CREATE OR REPLACE FUNCTION check_truck_load_item_quantity()
+RETURNS TRIGGER AS $$
+DECLARE
+    v_truck_load_id INTEGER;
+    v_available_quantity INTEGER;
+BEGIN
+    SELECT truck_load_id INTO v_truck_load_id
+    FROM sales
+    WHERE id = NEW.sale_id;
+
+    SELECT (quantity_loaded - quantity_sold - quantity_returned - quantity_discarded) INTO v_available_quantity
+    FROM truck_load_items
+    WHERE truck_load_id = v_truck_load_id
+    AND batch_id = NEW.batch_id;
+
+    IF v_available_quantity IS NULL OR NEW.quantity > v_available_quantity THEN
+        RAISE EXCEPTION 'Cannot sell more than available quantity in truck load';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE VIEW batch_stock_balance AS
+SELECT
+    b.id as batch_id,
+    b.product_id,
+    p.name as product_name,
+    b.quantity as initial_quantity,
+    b.remaining_quantity,
+    COALESCE(SUM(
+        CASE
+            WHEN sm.movement_type = 'sale_out'
+              OR (sm.reference_type = 'reconciliation' AND sm.movement_type IN ('expired_out', 'adjustment'))
+            THEN 0
+            WHEN sm.movement_type IN ('delivery_in', 'truck_return_in', 'adjustment')
+            THEN sm.quantity
+            ELSE -sm.quantity
+        END
+    ), 0) as calculated_balance,
+    -- Verify integrity
+    (b.remaining_quantity = COALESCE(SUM(
+        CASE
+            WHEN sm.movement_type = 'sale_out'
+              OR (sm.reference_type = 'reconciliation' AND sm.movement_type IN ('expired_out', 'adjustment'))
+            THEN 0
+            WHEN sm.movement_type IN ('delivery_in', 'truck_return_in', 'adjustment')
+            THEN sm.quantity
+            ELSE -sm.quantity
+        END
+    ), 0)) as balance_matches
+FROM batches b
+JOIN products p ON b.product_id = p.id
+LEFT JOIN stock_movements sm ON sm.batch_id = b.id
+GROUP BY b.id, b.product_id, p.name, b.quantity, b.remaining_quantity;
+
+COMMIT;
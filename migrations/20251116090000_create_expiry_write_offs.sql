@@ -0,0 +1,57 @@
+-- Migration: Automatic expiry write-offs
+-- A sweep (hourly background task, or POST /expiry-sweeps) finds batches past their
+-- expiry_date that still have warehouse stock, zeroes remaining_quantity and logs an
+-- 'expired_out' movement per batch. Each sweep that writes something off is stored
+-- with its lines, valued per FIFO receipt layer of the batch.
+
+-- Movements from a sweep reference the expiry_write_offs row
+ALTER TYPE reference_type ADD VALUE IF NOT EXISTS 'expiry_write_off';
+
+BEGIN;
+
+-- Owner of automated stock movements. The 'system' role is not a login role:
+-- login, token checks and user management all refuse it, and the password hash
+-- is unusable. Jobs find the principal by its role, of which there is exactly one.
+ALTER TABLE users DROP CONSTRAINT users_role_check;
+ALTER TABLE users ADD CONSTRAINT users_role_check CHECK (role IN ('manager', 'driver', 'system'));
+
+CREATE UNIQUE INDEX users_single_system_role ON users (role) WHERE role = 'system';
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM users WHERE username = 'system') THEN
+        RAISE EXCEPTION 'A user named ''system'' already exists; rename that account before applying this migration';
+    END IF;
+END
+$$;
+
+INSERT INTO users (username, password_hash, role, is_active)
+VALUES ('system', '!', 'system', FALSE);
+
+CREATE TABLE expiry_write_offs (
+    id BIGSERIAL PRIMARY KEY,
+    triggered_by VARCHAR(20) NOT NULL CHECK (triggered_by IN ('scheduler', 'manual')),
+    requested_by BIGINT REFERENCES users(id) ON DELETE SET NULL, -- manager for manual sweeps
+    as_of DATE NOT NULL,                                          -- batches expiring before this date
+    batches_written_off INTEGER NOT NULL CHECK (batches_written_off > 0),
+    units_written_off INTEGER NOT NULL CHECK (units_written_off > 0),
+    total_value NUMERIC(12, 2) NOT NULL CHECK (total_value >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE expiry_write_off_items (
+    id BIGSERIAL PRIMARY KEY,
+    write_off_id BIGINT NOT NULL REFERENCES expiry_write_offs(id) ON DELETE CASCADE,
+    batch_id BIGINT NOT NULL REFERENCES batches(id),
+    product_id BIGINT NOT NULL REFERENCES products(id),
+    expiry_date DATE NOT NULL,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    unit_cost NUMERIC(10, 2) NOT NULL,  -- average cost of the FIFO receipt layers written off
+    value NUMERIC(12, 2) NOT NULL
+);
+
+CREATE INDEX idx_expiry_write_offs_created_at ON expiry_write_offs(created_at DESC);
+CREATE INDEX idx_expiry_write_off_items_write_off ON expiry_write_off_items(write_off_id);
+CREATE INDEX idx_expiry_write_off_items_batch ON expiry_write_off_items(batch_id);
+
+COMMIT;
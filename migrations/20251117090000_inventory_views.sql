@@ -0,0 +1,65 @@
+-- Migration: Inventory on hand and point-in-time batch balances
+-- batch_units_on_trucks: units of each batch still out on truck loads that are not
+-- reconciled yet (loaded - sold - returned - discarded), shared by the inventory and
+-- expiry reports.
+-- batch_balance_as_of(date): warehouse balance of every batch at the end of a day,
+-- replayed from stock_movements with the same rules as batch_stock_balance.
+-- batch_truck_units_as_of(date): units of each batch out on trucks at the end of a
+-- day, replayed from the same ledger: loaded out, less sold, returned and written
+-- off on the truck at reconciliation.
+
+BEGIN;
+
+CREATE VIEW batch_units_on_trucks AS
+SELECT
+    tli.batch_id,
+    SUM(tli.quantity_loaded - tli.quantity_sold - tli.quantity_returned - tli.quantity_discarded)::INTEGER as units_on_trucks
+FROM truck_load_items tli
+JOIN truck_loads tl ON tli.truck_load_id = tl.id
+WHERE tl.status <> 'reconciled'
+GROUP BY tli.batch_id
+HAVING SUM(tli.quantity_loaded - tli.quantity_sold - tli.quantity_returned - tli.quantity_discarded) > 0;
+
+CREATE FUNCTION batch_balance_as_of(p_as_of DATE)
+RETURNS TABLE (batch_id BIGINT, product_id BIGINT, balance INTEGER) AS $$
+    SELECT
+        b.id,
+        b.product_id,
+        COALESCE(SUM(
+            CASE
+                WHEN sm.movement_type = 'sale_out'
+                  OR (sm.reference_type = 'reconciliation' AND sm.movement_type IN ('expired_out', 'adjustment'))
+                THEN 0
+                WHEN sm.movement_type IN ('delivery_in', 'truck_return_in', 'adjustment')
+                THEN sm.quantity
+                ELSE -sm.quantity
+            END
+        ), 0)::INTEGER
+    FROM batches b
+    LEFT JOIN stock_movements sm ON sm.batch_id = b.id AND sm.movement_date <= p_as_of
+    GROUP BY b.id, b.product_id;
+$$ LANGUAGE sql STABLE;
+
+CREATE FUNCTION batch_truck_units_as_of(p_as_of DATE)
+RETURNS TABLE (batch_id BIGINT, units INTEGER) AS $$
+    SELECT batch_id, units
+    FROM (
+        SELECT
+            sm.batch_id,
+            SUM(
+                CASE
+                    WHEN sm.movement_type = 'truck_load_out' THEN sm.quantity
+                    WHEN sm.movement_type IN ('sale_out', 'truck_return_in') THEN -sm.quantity
+                    WHEN sm.reference_type = 'reconciliation' AND sm.movement_type IN ('expired_out', 'adjustment')
+                    THEN -sm.quantity
+                    ELSE 0
+                END
+            )::INTEGER as units
+        FROM stock_movements sm
+        WHERE sm.movement_date <= p_as_of
+        GROUP BY sm.batch_id
+    ) t
+    WHERE units > 0;
+$$ LANGUAGE sql STABLE;
+
+COMMIT;
@@ -0,0 +1,53 @@
+-- Migration: One definition of how a stock movement changes a batch's warehouse balance
+-- batch_stock_balance, batch_balance_as_of and the running balance of a batch's
+-- movement history each spelled out the same CASE. stock_movement_effect is now the
+-- single rule: sale_out and reconciliation write-offs are informational (the stock
+-- already left on truck_load_out), deliveries, returns and adjustments add, the rest
+-- subtracts.
+
+BEGIN;
+
+CREATE FUNCTION stock_movement_effect(
+    p_movement_type stock_movement_type,
+    p_reference_type reference_type,
+    p_quantity NUMERIC
+) RETURNS NUMERIC AS $$
+    SELECT CASE
+        WHEN p_movement_type = 'sale_out'
+          OR (p_reference_type = 'reconciliation' AND p_movement_type IN ('expired_out', 'adjustment'))
+        THEN 0
+        WHEN p_movement_type IN ('delivery_in', 'truck_return_in', 'adjustment')
+        THEN p_quantity
+        ELSE -p_quantity
+    END;
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION batch_balance_as_of(p_as_of DATE)
+RETURNS TABLE (batch_id BIGINT, product_id BIGINT, balance INTEGER) AS $$
+    SELECT
+        b.id,
+        b.product_id,
+        COALESCE(SUM(stock_movement_effect(sm.movement_type, sm.reference_type, sm.quantity)), 0)::INTEGER
+    FROM batches b
+    LEFT JOIN stock_movements sm ON sm.batch_id = b.id AND sm.movement_date <= p_as_of
+    GROUP BY b.id, b.product_id;
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE VIEW batch_stock_balance AS
+SELECT
+    b.id as batch_id,
+    b.product_id,
+    p.name as product_name,
+    b.quantity as initial_quantity,
+    b.remaining_quantity,
+    COALESCE(SUM(stock_movement_effect(sm.movement_type, sm.reference_type, sm.quantity)), 0) as calculated_balance,
+    -- Verify integrity
+    (b.remaining_quantity = COALESCE(SUM(
+        stock_movement_effect(sm.movement_type, sm.reference_type, sm.quantity)
+    ), 0)) as balance_matches
+FROM batches b
+JOIN products p ON b.product_id = p.id
+LEFT JOIN stock_movements sm ON sm.batch_id = b.id
+GROUP BY b.id, b.product_id, p.name, b.quantity, b.remaining_quantity;
+
+COMMIT;
@@ -0,0 +1,19 @@
+-- Migration: Signed adjustment movements
+-- An 'adjustment' can decrease a batch as well as increase it. The quantity check
+-- rejected every negative quantity, so decreasing adjustments failed on insert and
+-- corrective entries (integrity repair, stocktakes) could only add stock. Other
+-- movement types keep a positive quantity; stock_movement_effect already adds an
+-- adjustment's quantity with its sign.
+
+-- Integrity repairs reference the batch they correct under their own type, so they
+-- are not mistaken for manual adjustments
+ALTER TYPE reference_type ADD VALUE IF NOT EXISTS 'integrity_repair';
+
+BEGIN;
+
+ALTER TABLE stock_movements DROP CONSTRAINT stock_movements_quantity_check;
+ALTER TABLE stock_movements ADD CONSTRAINT valid_quantity CHECK (
+    quantity > 0 OR (movement_type = 'adjustment' AND quantity <> 0)
+);
+
+COMMIT;
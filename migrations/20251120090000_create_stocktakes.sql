@@ -0,0 +1,48 @@
+-- Migration: Stocktake (cycle count) sessions
+-- A manager opens a count for a set of products, users record the counted quantity
+-- per batch together with the batch's remaining_quantity at that moment, and posting
+-- writes every variance against that count-time balance as one 'adjustment'
+-- movement per batch referencing the stocktake. Posted or cancelled sessions are
+-- frozen.
+
+-- Adjustments from a stocktake reference the stocktakes row
+ALTER TYPE reference_type ADD VALUE IF NOT EXISTS 'stocktake';
+
+BEGIN;
+
+CREATE TABLE stocktakes (
+    id BIGSERIAL PRIMARY KEY,
+    status VARCHAR(20) NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'posted', 'cancelled')),
+    notes TEXT,
+    opened_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    opened_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    closed_by BIGINT REFERENCES users(id) ON DELETE SET NULL, -- posted or cancelled by
+    closed_at TIMESTAMPTZ
+);
+
+-- Products included in the count
+CREATE TABLE stocktake_products (
+    stocktake_id BIGINT NOT NULL REFERENCES stocktakes(id) ON DELETE CASCADE,
+    product_id BIGINT NOT NULL REFERENCES products(id),
+    PRIMARY KEY (stocktake_id, product_id)
+);
+
+-- Latest count per batch with the balance it was taken against; variance is filled
+-- in when posted
+CREATE TABLE stocktake_counts (
+    id BIGSERIAL PRIMARY KEY,
+    stocktake_id BIGINT NOT NULL REFERENCES stocktakes(id) ON DELETE CASCADE,
+    batch_id BIGINT NOT NULL REFERENCES batches(id),
+    product_id BIGINT NOT NULL REFERENCES products(id),
+    counted_quantity INTEGER NOT NULL CHECK (counted_quantity >= 0),
+    counted_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    counted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    system_quantity INTEGER NOT NULL, -- remaining_quantity when the count was recorded
+    variance INTEGER,                 -- counted_quantity - system_quantity, set when posted
+    UNIQUE (stocktake_id, batch_id)
+);
+
+CREATE INDEX idx_stocktakes_status ON stocktakes(status);
+CREATE INDEX idx_stocktake_counts_stocktake ON stocktake_counts(stocktake_id);
+
+COMMIT;
@@ -0,0 +1,14 @@
+-- Migration: Reorder settings per product
+-- Used by GET /reports/reorder to suggest quantities for the next delivery.
+--   reorder_point:  reorder when stock (warehouse + trucks) falls to this level
+--   safety_stock:   units kept on top of the expected demand during the lead time
+--   lead_time_days: days between placing an order and the delivery arriving
+
+BEGIN;
+
+ALTER TABLE products
+    ADD COLUMN reorder_point INTEGER NOT NULL DEFAULT 0 CHECK (reorder_point >= 0),
+    ADD COLUMN safety_stock INTEGER NOT NULL DEFAULT 0 CHECK (safety_stock >= 0),
+    ADD COLUMN lead_time_days INTEGER NOT NULL DEFAULT 1 CHECK (lead_time_days >= 0);
+
+COMMIT;
@@ -0,0 +1,39 @@
+-- Migration: Purchase orders
+-- A delivery only records what arrived. Purchase orders record what was ordered
+-- (one line per product with the expected quantity and price). A delivery can be
+-- linked to the order it fills, so short/over deliveries and price differences
+-- against delivery_items.unit_price can be reported per order.
+
+BEGIN;
+
+CREATE TABLE purchase_orders (
+    id BIGSERIAL PRIMARY KEY,
+    po_number VARCHAR(100) UNIQUE NOT NULL,
+    order_date DATE NOT NULL,
+    expected_date DATE,
+    status VARCHAR(20) NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'closed', 'cancelled')),
+    notes TEXT,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    closed_at TIMESTAMPTZ,
+    CONSTRAINT valid_expected_date CHECK (expected_date IS NULL OR expected_date >= order_date)
+);
+
+CREATE TABLE purchase_order_lines (
+    id BIGSERIAL PRIMARY KEY,
+    purchase_order_id BIGINT NOT NULL REFERENCES purchase_orders(id) ON DELETE CASCADE,
+    product_id BIGINT NOT NULL REFERENCES products(id),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    unit_price NUMERIC(10, 2) NOT NULL CHECK (unit_price >= 0), -- agreed price per unit
+    UNIQUE (purchase_order_id, product_id)
+);
+
+-- Deliveries without an order stay valid
+ALTER TABLE deliveries
+    ADD COLUMN purchase_order_id BIGINT REFERENCES purchase_orders(id) ON DELETE RESTRICT;
+
+CREATE INDEX idx_purchase_orders_order_date ON purchase_orders(order_date DESC);
+CREATE INDEX idx_purchase_order_lines_order ON purchase_order_lines(purchase_order_id);
+CREATE INDEX idx_deliveries_purchase_order ON deliveries(purchase_order_id);
+
+COMMIT;
@@ -0,0 +1,56 @@
+-- Migration: Suppliers
+-- Stock and costs used to come from a single supplier (CreamyLand) that was only
+-- named in schema comments. Suppliers are now rows: every delivery and purchase
+-- order belongs to one, each product has a primary supplier, and supplier_products
+-- holds each supplier's cost price per product. Existing data is assigned to a
+-- seeded CreamyLand supplier, priced at the product's latest delivery price.
+
+BEGIN;
+
+CREATE TABLE suppliers (
+    id BIGSERIAL PRIMARY KEY,
+    name VARCHAR(100) UNIQUE NOT NULL,
+    contact_name VARCHAR(100),
+    phone VARCHAR(30),
+    email VARCHAR(100),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE, -- inactive suppliers cannot receive new orders or deliveries
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Per-supplier cost price; the default unit_price for that supplier's delivery lines
+CREATE TABLE supplier_products (
+    supplier_id BIGINT NOT NULL REFERENCES suppliers(id) ON DELETE CASCADE,
+    product_id BIGINT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    cost_price NUMERIC(10, 2) NOT NULL CHECK (cost_price >= 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (supplier_id, product_id)
+);
+
+INSERT INTO suppliers (name) VALUES ('CreamyLand');
+
+ALTER TABLE products ADD COLUMN supplier_id BIGINT REFERENCES suppliers(id) ON DELETE RESTRICT;
+ALTER TABLE deliveries ADD COLUMN supplier_id BIGINT REFERENCES suppliers(id) ON DELETE RESTRICT;
+ALTER TABLE purchase_orders ADD COLUMN supplier_id BIGINT REFERENCES suppliers(id) ON DELETE RESTRICT;
+
+UPDATE products SET supplier_id = (SELECT id FROM suppliers WHERE name = 'CreamyLand');
+UPDATE deliveries SET supplier_id = (SELECT id FROM suppliers WHERE name = 'CreamyLand');
+UPDATE purchase_orders SET supplier_id = (SELECT id FROM suppliers WHERE name = 'CreamyLand');
+
+INSERT INTO supplier_products (supplier_id, product_id, cost_price)
+SELECT DISTINCT ON (di.product_id) d.supplier_id, di.product_id, di.unit_price
+FROM delivery_items di
+JOIN deliveries d ON di.delivery_id = d.id
+ORDER BY di.product_id, d.delivery_date DESC, di.id DESC;
+
+-- A product's primary supplier can be left unset; deliveries and orders always have one
+ALTER TABLE deliveries ALTER COLUMN supplier_id SET NOT NULL;
+ALTER TABLE purchase_orders ALTER COLUMN supplier_id SET NOT NULL;
+
+CREATE INDEX idx_products_supplier ON products(supplier_id);
+CREATE INDEX idx_deliveries_supplier ON deliveries(supplier_id);
+CREATE INDEX idx_purchase_orders_supplier ON purchase_orders(supplier_id);
+CREATE INDEX idx_supplier_products_product ON supplier_products(product_id);
+
+COMMENT ON COLUMN delivery_items.unit_price IS 'Cost price charged by the delivery''s supplier';
+
+COMMIT;
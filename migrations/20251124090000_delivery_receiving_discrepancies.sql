@@ -0,0 +1,69 @@
+-- Migration: Receiving discrepancies and damaged-on-arrival
+-- A delivery line used to assume every unit on the delivery note arrived sellable.
+-- Each line now records the quantity on the note, the quantity that arrived and how
+-- much of it was damaged (with a reason). delivery_items.quantity keeps meaning the
+-- good units that became batch stock. Damaged and missing units are claimed back
+-- from the supplier: one claim per delivery, one claim line per product and type.
+
+-- Quantity corrections to a recorded delivery line reference the delivery under
+-- their own type, apart from the receipts themselves
+ALTER TYPE reference_type ADD VALUE IF NOT EXISTS 'delivery_correction';
+
+BEGIN;
+
+ALTER TABLE delivery_items
+    ADD COLUMN note_quantity INTEGER,
+    ADD COLUMN received_quantity INTEGER,
+    ADD COLUMN damaged_quantity INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN damage_reason VARCHAR(200);
+
+UPDATE delivery_items SET note_quantity = quantity, received_quantity = quantity;
+
+ALTER TABLE delivery_items
+    ALTER COLUMN note_quantity SET NOT NULL,
+    ALTER COLUMN received_quantity SET NOT NULL;
+
+-- A line can arrive entirely damaged (no batch stock) or not at all
+ALTER TABLE delivery_items DROP CONSTRAINT delivery_items_quantity_check;
+ALTER TABLE delivery_items
+    ADD CONSTRAINT valid_received_quantities CHECK (
+        quantity >= 0
+        AND damaged_quantity >= 0
+        AND note_quantity >= 0
+        AND received_quantity = quantity + damaged_quantity
+        AND (note_quantity > 0 OR received_quantity > 0)
+    ),
+    ADD CONSTRAINT damage_has_reason CHECK ((damaged_quantity > 0) = (damage_reason IS NOT NULL));
+
+CREATE TABLE supplier_claims (
+    id BIGSERIAL PRIMARY KEY,
+    delivery_id BIGINT NOT NULL UNIQUE REFERENCES deliveries(id) ON DELETE CASCADE,
+    supplier_id BIGINT NOT NULL REFERENCES suppliers(id),
+    status VARCHAR(20) NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'settled', 'rejected')),
+    total_value NUMERIC(12, 2) NOT NULL CHECK (total_value >= 0),
+    notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    resolved_at TIMESTAMPTZ
+);
+
+CREATE TABLE supplier_claim_lines (
+    id BIGSERIAL PRIMARY KEY,
+    claim_id BIGINT NOT NULL REFERENCES supplier_claims(id) ON DELETE CASCADE,
+    delivery_item_id BIGINT NOT NULL REFERENCES delivery_items(id) ON DELETE CASCADE,
+    product_id BIGINT NOT NULL REFERENCES products(id),
+    claim_type VARCHAR(20) NOT NULL CHECK (claim_type IN ('damaged', 'short')),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    unit_price NUMERIC(10, 2) NOT NULL,  -- delivery_items.unit_price of the line
+    value NUMERIC(12, 2) NOT NULL,
+    reason VARCHAR(200),                 -- damage_reason for damaged units
+    UNIQUE (delivery_item_id, claim_type)
+);
+
+CREATE INDEX idx_supplier_claims_supplier ON supplier_claims(supplier_id);
+CREATE INDEX idx_supplier_claims_status ON supplier_claims(status);
+CREATE INDEX idx_supplier_claim_lines_claim ON supplier_claim_lines(claim_id);
+
+COMMENT ON COLUMN delivery_items.quantity IS 'Good units received into batch stock (received_quantity - damaged_quantity)';
+
+COMMIT;
@@ -0,0 +1,39 @@
+-- Migration: Per-delivery receipt rows for batches
+-- A delivery can top up a batch another delivery created (same product and batch
+-- number); the batch keeps pointing at the delivery that created it. batch_receipts
+-- (dropped when stock_movements arrived) comes back as the record of how many good
+-- units each delivery line put into each batch, net of corrections.
+--
+-- Backfilled from the ledger: receipts and corrections referencing a delivery,
+-- matched to that delivery's line for the batch's product. Top-ups recorded before
+-- they were logged as movements left no trace and cannot be attributed.
+
+BEGIN;
+
+CREATE TABLE batch_receipts (
+    id BIGSERIAL PRIMARY KEY,
+    batch_id BIGINT NOT NULL REFERENCES batches(id) ON DELETE RESTRICT,
+    delivery_id BIGINT NOT NULL REFERENCES deliveries(id) ON DELETE RESTRICT,
+    delivery_item_id BIGINT NOT NULL REFERENCES delivery_items(id) ON DELETE RESTRICT,
+    quantity INTEGER NOT NULL CHECK (quantity >= 0),  -- 0 once corrected away
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (delivery_item_id, batch_id)
+);
+
+CREATE INDEX idx_batch_receipts_delivery ON batch_receipts(delivery_id);
+CREATE INDEX idx_batch_receipts_batch ON batch_receipts(batch_id);
+
+INSERT INTO batch_receipts (batch_id, delivery_id, delivery_item_id, quantity, received_at)
+SELECT
+    b.id,
+    di.delivery_id,
+    di.id,
+    SUM(stock_movement_effect(sm.movement_type, sm.reference_type, sm.quantity))::INTEGER,
+    MIN(sm.created_at)
+FROM stock_movements sm
+JOIN batches b ON b.id = sm.batch_id
+JOIN delivery_items di ON di.delivery_id = sm.reference_id AND di.product_id = b.product_id
+WHERE sm.reference_type IN ('delivery', 'delivery_correction')
+GROUP BY b.id, di.delivery_id, di.id;
+
+COMMIT;
@@ -0,0 +1,36 @@
+-- Migration: Delivery reversals
+-- Deleting a delivery erased its batches and movements, which is only safe while
+-- none of its stock has moved. A reversal instead takes received units back out of
+-- the warehouse with 'adjustment' movements referencing the delivery_reversals row
+-- and records what was taken back per receipt, leaving the delivery and its history
+-- in place.
+-- Units already on trucks, sold or written off stay where they are; a
+-- warehouse-only reversal takes back the rest.
+
+-- Reversal adjustments reference the delivery_reversals row
+ALTER TYPE reference_type ADD VALUE IF NOT EXISTS 'delivery_reversal';
+
+BEGIN;
+
+CREATE TABLE delivery_reversals (
+    id BIGSERIAL PRIMARY KEY,
+    delivery_id BIGINT NOT NULL REFERENCES deliveries(id) ON DELETE RESTRICT,
+    warehouse_only BOOLEAN NOT NULL,  -- requested as a partial reversal
+    notes TEXT,
+    reversed_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    reversed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE delivery_reversal_lines (
+    id BIGSERIAL PRIMARY KEY,
+    reversal_id BIGINT NOT NULL REFERENCES delivery_reversals(id) ON DELETE CASCADE,
+    delivery_item_id BIGINT NOT NULL REFERENCES delivery_items(id) ON DELETE RESTRICT,
+    batch_id BIGINT NOT NULL REFERENCES batches(id) ON DELETE RESTRICT,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    UNIQUE (reversal_id, delivery_item_id, batch_id)
+);
+
+CREATE INDEX idx_delivery_reversals_delivery ON delivery_reversals(delivery_id);
+CREATE INDEX idx_delivery_reversal_lines_receipt ON delivery_reversal_lines(delivery_item_id, batch_id);
+
+COMMIT;
@@ -0,0 +1,54 @@
+-- Migration: Effective-dated product prices and commissions
+-- update_product overwrote current_wholesale_price and commission_per_unit in place,
+-- so a backdated sale, or a reconciliation started later, used today's figures.
+-- Each price change is now a row effective from a date; product_price_on gives the
+-- figures in force on a day. The products columns keep today's figures.
+--
+-- Existing products start with one row holding their current figures, effective
+-- from their first sale or creation, whichever is earlier; earlier figures were
+-- not recorded.
+
+BEGIN;
+
+CREATE TABLE product_price_history (
+    id BIGSERIAL PRIMARY KEY,
+    product_id BIGINT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    effective_from DATE NOT NULL,
+    wholesale_price NUMERIC(10, 2) NOT NULL CHECK (wholesale_price >= 0),
+    commission_per_unit NUMERIC(10, 2) NOT NULL CHECK (commission_per_unit >= 0),
+    changed_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (product_id, effective_from)
+);
+
+INSERT INTO product_price_history (product_id, effective_from, wholesale_price, commission_per_unit)
+SELECT
+    p.id,
+    COALESCE(
+        LEAST(
+            p.created_at::DATE,
+            (SELECT MIN(s.sale_date)
+             FROM sale_items si
+             JOIN sales s ON si.sale_id = s.id
+             JOIN batches b ON si.batch_id = b.id
+             WHERE b.product_id = p.id)
+        ),
+        CURRENT_DATE
+    ),
+    p.current_wholesale_price,
+    p.commission_per_unit
+FROM products p;
+
+-- Figures in force on p_date; before the first recorded change, the first figures
+CREATE FUNCTION product_price_on(p_product_id BIGINT, p_date DATE)
+RETURNS TABLE (wholesale_price NUMERIC, commission_per_unit NUMERIC) AS $$
+    SELECT h.wholesale_price, h.commission_per_unit
+    FROM product_price_history h
+    WHERE h.product_id = p_product_id
+    ORDER BY (h.effective_from <= p_date) DESC,
+             CASE WHEN h.effective_from <= p_date THEN h.effective_from END DESC,
+             h.effective_from ASC
+    LIMIT 1;
+$$ LANGUAGE sql STABLE;
+
+COMMIT;
@@ -0,0 +1,51 @@
+-- Migration: Date a delivery's stock movements on the delivery date
+-- The receipt logged by trigger_log_batch_delivery was dated with the day the batch
+-- row was inserted, while top-ups and line corrections used the day they were made.
+-- Point-in-time balances and valuation then put a late-recorded delivery, or a
+-- correction to an old one, on a different day from the goods it describes.
+-- Receipts, top-ups and corrections now all carry deliveries.delivery_date. A later
+-- change of that date leaves them in place and posts a reversing pair of
+-- corrections instead. Reversals stay on the day they were posted: the stock
+-- physically leaves then.
+
+BEGIN;
+
+CREATE OR REPLACE FUNCTION log_batch_delivery()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO stock_movements (
+        batch_id,
+        product_id,
+        movement_type,
+        quantity,
+        reference_type,
+        reference_id,
+        notes,
+        created_by,
+        movement_date
+    ) VALUES (
+        NEW.id,
+        NEW.product_id,
+        'delivery_in',
+        NEW.quantity,
+        'delivery',
+        NEW.delivery_id,
+        'Initial delivery receipt - Batch: ' || NEW.batch_number,
+        NULL,  -- System generated
+        COALESCE((SELECT delivery_date FROM deliveries WHERE id = NEW.delivery_id), NEW.created_at::DATE)
+    );
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Receipts and corrections already recorded; reversal adjustments keep their date
+UPDATE stock_movements sm
+SET movement_date = d.delivery_date
+FROM deliveries d
+WHERE sm.reference_id = d.id
+  AND sm.movement_date <> d.delivery_date
+  AND ((sm.reference_type = 'delivery' AND sm.movement_type = 'delivery_in')
+       OR sm.reference_type = 'delivery_correction');
+
+COMMIT;
@@ -0,0 +1,14 @@
+-- Migration: Keep the batches of a removed delivery line
+-- Removing a delivery line takes its stock back with 'delivery_correction'
+-- adjustments instead of deleting the batches it created, so their ledger stays.
+-- Those batches outlive the line and keep only their delivery.
+
+BEGIN;
+
+ALTER TABLE batches ALTER COLUMN delivery_item_id DROP NOT NULL;
+ALTER TABLE batches DROP CONSTRAINT batches_delivery_item_id_fkey;
+ALTER TABLE batches
+    ADD CONSTRAINT batches_delivery_item_id_fkey
+    FOREIGN KEY (delivery_item_id) REFERENCES delivery_items(id) ON DELETE SET NULL;
+
+COMMIT;
@@ -3,6 +3,9 @@ use jsonwebtoken::{encode, decode, Header, Validation, EncodingKey, DecodingKey,
 use serde::{Serialize, Deserialize};
 use crate::error::AppError;
 
+// Access tokens are short-lived; clients renew them with the refresh token (see auth::session)
+pub const ACCESS_TOKEN_TTL_SECONDS: i64 = 15 * 60;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: i64,
@@ -10,17 +13,19 @@ pub struct Claims {
     pub exp: usize,
     pub iat: usize,
     pub username: String,
+    pub sid: i64, // user_sessions.id the token was issued for
 }
 
-pub fn sign_token(user_id: i64, role: &str, username: &str, secret: &str) -> Result<String, AppError> {
+pub fn sign_token(user_id: i64, role: &str, username: &str, session_id: i64, secret: &str) -> Result<String, AppError> {
     let now = Utc::now();
-    let exp = now + Duration::hours(8);
+    let exp = now + Duration::seconds(ACCESS_TOKEN_TTL_SECONDS);
     let claims = Claims {
         sub: user_id,
         role: role.to_string(),
         iat: now.timestamp() as usize,
         exp: exp.timestamp() as usize,
         username: username.to_string(),
+        sid: session_id,
     };
     encode(&Header::new(Algorithm::HS256), &claims, &EncodingKey::from_secret(secret.as_bytes()))
         .map_err(|e| AppError::internal(format!("Token signing failed: {e}")))
@@ -1 +1,3 @@
-pub mod jwt;
\ No newline at end of file
+pub mod jwt;
+pub mod role;
+pub mod session;
@@ -0,0 +1,49 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+
+// Mirrors the CHECK constraint on users.role, minus the non-login 'system' role
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Manager,
+    Driver,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Manager => "manager",
+            Role::Driver => "driver",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "manager" => Ok(Role::Manager),
+            "driver" => Ok(Role::Driver),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Type-level role used by the `RequireRole<R>` extractor.
+pub trait RoleMarker {
+    const ROLE: Role;
+}
+
+pub struct Manager;
+
+impl RoleMarker for Manager {
+    const ROLE: Role = Role::Manager;
+}
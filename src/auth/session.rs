@@ -0,0 +1,137 @@
+// Refresh-token sessions (user_sessions table).
+// The raw refresh token is only ever returned to the client; the DB keeps its SHA-256 hash.
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use crate::error::AppError;
+
+pub const REFRESH_TOKEN_TTL_DAYS: i64 = 30;
+
+pub struct IssuedSession {
+    pub session_id: i64,
+    pub refresh_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+fn generate_refresh_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+fn hash_refresh_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+pub async fn create_session(db_pool: &PgPool, user_id: i64) -> Result<IssuedSession, AppError> {
+    let refresh_token = generate_refresh_token();
+    let expires_at = Utc::now() + Duration::days(REFRESH_TOKEN_TTL_DAYS);
+
+    let session_id = sqlx::query_scalar!(
+        r#"INSERT INTO user_sessions (user_id, refresh_token_hash, expires_at)
+        VALUES ($1, $2, $3)
+        RETURNING id"#,
+        user_id,
+        hash_refresh_token(&refresh_token),
+        expires_at
+    )
+    .fetch_one(db_pool)
+    .await?;
+
+    Ok(IssuedSession { session_id, refresh_token, expires_at })
+}
+
+/// Exchange a refresh token for a new one on the same session.
+/// Returns the session owner's id alongside the newly issued token.
+pub async fn rotate_session(db_pool: &PgPool, refresh_token: &str) -> Result<(i64, IssuedSession), AppError> {
+    let token_hash = hash_refresh_token(refresh_token);
+    let mut tx = db_pool.begin().await?;
+
+    let session = sqlx::query!(
+        r#"SELECT id, user_id, expires_at, revoked_at
+        FROM user_sessions
+        WHERE refresh_token_hash = $1
+        FOR UPDATE"#,
+        token_hash
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let session = match session {
+        Some(s) => s,
+        None => {
+            tx.rollback().await?;
+            // A token that was already rotated away is being replayed: assume it leaked
+            let reused = sqlx::query_scalar!(
+                r#"UPDATE user_sessions SET revoked_at = COALESCE(revoked_at, NOW())
+                WHERE previous_token_hash = $1
+                RETURNING id"#,
+                token_hash
+            )
+            .fetch_optional(db_pool)
+            .await?;
+
+            return Err(match reused {
+                Some(_) => AppError::unauthorized("Refresh token reuse detected; session revoked"),
+                None => AppError::unauthorized("Invalid refresh token"),
+            });
+        }
+    };
+
+    if session.revoked_at.is_some() {
+        return Err(AppError::unauthorized("Session has been revoked"));
+    }
+    if session.expires_at <= Utc::now() {
+        return Err(AppError::unauthorized("Refresh token expired"));
+    }
+
+    let new_token = generate_refresh_token();
+    let expires_at = Utc::now() + Duration::days(REFRESH_TOKEN_TTL_DAYS);
+
+    sqlx::query!(
+        r#"UPDATE user_sessions
+        SET previous_token_hash = refresh_token_hash,
+            refresh_token_hash = $1,
+            expires_at = $2,
+            last_refreshed_at = NOW()
+        WHERE id = $3"#,
+        hash_refresh_token(&new_token),
+        expires_at,
+        session.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((
+        session.user_id,
+        IssuedSession { session_id: session.id, refresh_token: new_token, expires_at },
+    ))
+}
+
+pub async fn revoke_session(db_pool: &PgPool, session_id: i64) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"UPDATE user_sessions SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL"#,
+        session_id
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(())
+}
+
+/// Revoke every open session of a user, optionally keeping the caller's own session.
+pub async fn revoke_user_sessions(db_pool: &PgPool, user_id: i64, keep_session_id: Option<i64>) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"UPDATE user_sessions SET revoked_at = NOW()
+        WHERE user_id = $1
+          AND revoked_at IS NULL
+          AND ($2::BIGINT IS NULL OR id <> $2)"#,
+        user_id,
+        keep_session_id
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(())
+}
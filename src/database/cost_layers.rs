@@ -0,0 +1,107 @@
+// FIFO cost layers of held stock, shared by the valuation, the expiring stock report
+// and the expiry sweep so the same units carry the same cost everywhere.
+// Each batch receipt (batch_receipts) is a layer at its delivery line's cost. Sales
+// and loads consume the oldest receipt first, so the units held belong to the newest
+// receipts, and within those the truck units are the older ones.
+use std::collections::HashMap;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use crate::error::AppError;
+
+/// Units of a batch to spread over its receipts.
+pub struct HeldUnits {
+    pub batch_id: i64,
+    pub warehouse_units: i32,
+    pub truck_units: i32,
+}
+
+/// Part of a batch's held units that came with one receipt.
+pub struct CostLayer {
+    pub delivery_id: i64,
+    pub supplier_id: i64,
+    pub delivery_date: NaiveDate,
+    pub unit_cost: Decimal,
+    pub warehouse_units: i32,
+    pub truck_units: i32,
+}
+
+impl CostLayer {
+    pub fn units(&self) -> i32 {
+        self.warehouse_units + self.truck_units
+    }
+}
+
+/// Cost layers of each batch at the end of `as_of`, oldest receipt first. Receipts are
+/// net of corrections and of reversals made by `as_of`; a batch without a receipt by
+/// then has no layers.
+pub async fn cost_layers(
+    conn: &mut sqlx::PgConnection,
+    as_of: NaiveDate,
+    held: &[HeldUnits],
+) -> Result<HashMap<i64, Vec<CostLayer>>, AppError> {
+    let batch_ids: Vec<i64> = held.iter().map(|h| h.batch_id).collect();
+    let receipts = sqlx::query!(
+        r#"SELECT br.batch_id, br.delivery_id, d.supplier_id, d.delivery_date, di.unit_price,
+                  (br.quantity - COALESCE((
+                      SELECT SUM(rl.quantity) FROM delivery_reversal_lines rl
+                      JOIN delivery_reversals r ON r.id = rl.reversal_id
+                      WHERE rl.delivery_item_id = br.delivery_item_id AND rl.batch_id = br.batch_id
+                        AND r.reversed_at::DATE <= $1
+                  ), 0))::INT as "received!"
+           FROM batch_receipts br
+           JOIN deliveries d ON br.delivery_id = d.id
+           JOIN delivery_items di ON br.delivery_item_id = di.id
+           WHERE br.batch_id = ANY($2) AND d.delivery_date <= $1
+           ORDER BY br.batch_id, d.delivery_date, br.received_at, br.id"#,
+        as_of,
+        &batch_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut by_batch: HashMap<i64, Vec<_>> = HashMap::new();
+    for receipt in receipts {
+        by_batch.entry(receipt.batch_id).or_default().push(receipt);
+    }
+
+    let mut layers = HashMap::new();
+    for units in held {
+        let Some(receipts) = by_batch.remove(&units.batch_id) else {
+            continue;
+        };
+
+        // Held units fill the newest receipts first: warehouse units, then truck
+        // units. Anything beyond the receipts (e.g. stocktake gains) goes to the
+        // oldest layer.
+        let mut warehouse_left = units.warehouse_units.max(0);
+        let mut truck_left = units.truck_units.max(0);
+        let mut split = vec![(0, 0); receipts.len()];
+        for (receipt, (warehouse, truck)) in receipts.iter().zip(split.iter_mut()).rev() {
+            let mut room = receipt.received.max(0);
+            *warehouse = warehouse_left.min(room);
+            warehouse_left -= *warehouse;
+            room -= *warehouse;
+            *truck = truck_left.min(room);
+            truck_left -= *truck;
+        }
+        split[0].0 += warehouse_left;
+        split[0].1 += truck_left;
+
+        let batch_layers = receipts
+            .into_iter()
+            .zip(split)
+            .filter(|(_, (warehouse, truck))| warehouse + truck > 0)
+            .map(|(receipt, (warehouse_units, truck_units))| CostLayer {
+                delivery_id: receipt.delivery_id,
+                supplier_id: receipt.supplier_id,
+                delivery_date: receipt.delivery_date,
+                unit_cost: receipt.unit_price,
+                warehouse_units,
+                truck_units,
+            })
+            .collect();
+        layers.insert(units.batch_id, batch_layers);
+    }
+
+    Ok(layers)
+}
@@ -0,0 +1,211 @@
+// Shared WHERE-clause builder for list endpoints, on top of sqlx::QueryBuilder.
+// Column names and SQL fragments are &'static str so only code can add SQL text;
+// every request value goes through push_bind as a query parameter.
+// Conditions are kept so the same filter can build both the COUNT(*) and the page query.
+use sqlx::postgres::PgRow;
+use sqlx::{Encode, FromRow, PgPool, Postgres, QueryBuilder, Type};
+use crate::dtos::pagination::{PageParams, Paginated};
+use crate::error::AppError;
+
+pub const DEFAULT_PAGE_LIMIT: i64 = 50;
+pub const MAX_PAGE_LIMIT: i64 = 200;
+
+type Condition<'a> = Box<dyn Fn(&mut QueryBuilder<'a, Postgres>) + Send + Sync + 'a>;
+
+pub struct ListFilter<'a> {
+    select: &'static str,
+    conditions: Vec<Condition<'a>>,
+}
+
+impl<'a> ListFilter<'a> {
+    /// `select` is the query up to (not including) WHERE.
+    pub fn new(select: &'static str) -> Self {
+        Self { select, conditions: Vec::new() }
+    }
+
+    fn compare<T>(&mut self, column: &'static str, op: &'static str, value: Option<T>) -> &mut Self
+    where
+        T: 'a + Encode<'a, Postgres> + Type<Postgres> + Clone + Send + Sync,
+    {
+        if let Some(value) = value {
+            self.conditions.push(Box::new(move |qb| {
+                qb.push(column).push(op).push_bind(value.clone());
+            }));
+        }
+        self
+    }
+
+    /// `column = value`, skipped when the value is None.
+    pub fn eq<T>(&mut self, column: &'static str, value: Option<T>) -> &mut Self
+    where
+        T: 'a + Encode<'a, Postgres> + Type<Postgres> + Clone + Send + Sync,
+    {
+        self.compare(column, " = ", value)
+    }
+
+    /// `column >= value`, skipped when the value is None.
+    pub fn gte<T>(&mut self, column: &'static str, value: Option<T>) -> &mut Self
+    where
+        T: 'a + Encode<'a, Postgres> + Type<Postgres> + Clone + Send + Sync,
+    {
+        self.compare(column, " >= ", value)
+    }
+
+    /// `column <= value`, skipped when the value is None.
+    pub fn lte<T>(&mut self, column: &'static str, value: Option<T>) -> &mut Self
+    where
+        T: 'a + Encode<'a, Postgres> + Type<Postgres> + Clone + Send + Sync,
+    {
+        self.compare(column, " <= ", value)
+    }
+
+    /// Fixed condition chosen by the handler (e.g. a status mapped to a predicate).
+    pub fn condition(&mut self, sql: &'static str) -> &mut Self {
+        self.conditions.push(Box::new(move |qb| {
+            qb.push(sql);
+        }));
+        self
+    }
+
+    fn filtered(&self, builder: &mut QueryBuilder<'a, Postgres>) {
+        builder.push(self.select);
+        for (i, condition) in self.conditions.iter().enumerate() {
+            builder.push(if i == 0 { " WHERE " } else { " AND " });
+            condition(builder);
+        }
+    }
+
+    /// `SELECT COUNT(*)` over the filtered rows; `group_by` is "" when the query has none.
+    pub fn count(&self, group_by: &'static str) -> QueryBuilder<'a, Postgres> {
+        let mut builder = QueryBuilder::new("SELECT COUNT(*) FROM (");
+        self.filtered(&mut builder);
+        builder.push(" ").push(group_by).push(") AS filtered");
+        builder
+    }
+
+    /// One page of the filtered rows in the requested order.
+    pub fn page(&self, group_by: &'static str, sort: &Sort, page: &Page) -> QueryBuilder<'a, Postgres> {
+        let mut builder = QueryBuilder::new("");
+        self.filtered(&mut builder);
+        let direction = if sort.descending { " DESC" } else { " ASC" };
+        builder
+            .push(" ")
+            .push(group_by)
+            .push(" ORDER BY ")
+            .push(sort.column)
+            .push(direction)
+            .push(", ")
+            .push(sort.tiebreak)
+            .push(direction)
+            .push(" LIMIT ")
+            .push_bind(page.limit)
+            .push(" OFFSET ")
+            .push_bind(page.offset);
+        builder
+    }
+}
+
+/// Reject filter values outside a column's CHECK / enum domain with a 400
+/// instead of silently returning an empty list.
+pub fn one_of<'v>(field: &str, value: Option<&'v str>, allowed: &[&str]) -> Result<Option<&'v str>, AppError> {
+    match value {
+        Some(v) if !allowed.contains(&v) => Err(AppError::validation(format!(
+            "Invalid {field}. Use: {}",
+            allowed.join(", ")
+        ))),
+        other => Ok(other),
+    }
+}
+
+pub struct Sort {
+    column: &'static str,
+    tiebreak: &'static str,
+    descending: bool,
+}
+
+impl Sort {
+    /// Resolve `?sort=field` / `?sort=-field` (descending) against the endpoint's whitelist of
+    /// (public name, SQL column). `default` is used when no sort is given; `tiebreak` (a unique
+    /// column) keeps page boundaries stable between requests.
+    pub fn parse(
+        requested: Option<&str>,
+        allowed: &[(&str, &'static str)],
+        default: &str,
+        tiebreak: &'static str,
+    ) -> Result<Self, AppError> {
+        let requested = requested.filter(|s| !s.is_empty()).unwrap_or(default);
+        let (name, descending) = match requested.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (requested, false),
+        };
+        let column = allowed
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, column)| *column)
+            .ok_or_else(|| {
+                let fields: Vec<&str> = allowed.iter().map(|(field, _)| *field).collect();
+                AppError::validation(format!("Invalid sort field. Use: {}", fields.join(", ")))
+            })?;
+        Ok(Sort { column, tiebreak, descending })
+    }
+}
+
+pub struct Page {
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl Page {
+    /// `cursor` is the opaque `next_cursor` from the previous page (an offset).
+    pub fn from_params(params: &PageParams) -> Result<Self, AppError> {
+        let limit = params.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+        if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+            return Err(AppError::validation(format!("limit must be between 1 and {MAX_PAGE_LIMIT}")));
+        }
+        let offset = match params.cursor.as_deref() {
+            Some(cursor) => cursor
+                .parse::<i64>()
+                .ok()
+                .filter(|offset| *offset >= 0)
+                .ok_or_else(|| AppError::validation("Invalid cursor"))?,
+            None => 0,
+        };
+        Ok(Page { limit, offset })
+    }
+
+    pub fn next_cursor(&self, total: i64) -> Option<String> {
+        let next = self.offset + self.limit;
+        (next < total).then(|| next.to_string())
+    }
+
+    /// Wrap one page of items in the response envelope.
+    pub fn envelope<T>(&self, items: Vec<T>, total: i64) -> Paginated<T> {
+        Paginated {
+            items,
+            total,
+            limit: self.limit,
+            next_cursor: self.next_cursor(total),
+        }
+    }
+}
+
+pub async fn count_rows(db_pool: &PgPool, filter: &ListFilter<'_>, group_by: &'static str) -> Result<i64, AppError> {
+    let total = filter.count(group_by).build_query_scalar::<i64>().fetch_one(db_pool).await?;
+    Ok(total)
+}
+
+/// Run the page query and the matching count; rows map to `T` (a tuple or FromRow struct).
+pub async fn fetch_page<T>(
+    db_pool: &PgPool,
+    filter: &ListFilter<'_>,
+    group_by: &'static str,
+    sort: &Sort,
+    page: &Page,
+) -> Result<(Vec<T>, i64), AppError>
+where
+    T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+{
+    let rows = filter.page(group_by, sort, page).build_query_as::<T>().fetch_all(db_pool).await?;
+    let total = count_rows(db_pool, filter, group_by).await?;
+    Ok((rows, total))
+}
@@ -1,3 +1,6 @@
+pub mod cost_layers;
+pub mod filter;
+
 use sqlx::{postgres::PgPoolOptions, PgPool};
 
 pub async fn create_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
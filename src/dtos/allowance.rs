@@ -1,12 +1,14 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
 
 // Request DTOs
 
 #[derive(Deserialize)]
 pub struct CreateTransportAllowanceRequest {
     pub allowance_date: NaiveDate,
-    pub total_allowance: f64,
+    #[serde(with = "crate::money")]
+    pub total_allowance: Decimal,
     pub notes: Option<String>,
 }
 
@@ -18,14 +20,16 @@ pub struct AllocateToTrucksRequest {
 #[derive(Deserialize)]
 pub struct TruckAllocationRequest {
     pub truck_id: i64,
-    pub amount: f64,
+    #[serde(with = "crate::money")]
+    pub amount: Decimal,
     pub distance_covered: Option<f64>,
     pub notes: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct UpdateTruckAllocationRequest {
-    pub amount: f64,
+    #[serde(with = "crate::money")]
+    pub amount: Decimal,
     pub distance_covered: Option<f64>,
     pub notes: Option<String>,
 }
@@ -36,9 +40,12 @@ pub struct UpdateTruckAllocationRequest {
 pub struct TransportAllowanceResponse {
     pub id: i64,
     pub allowance_date: NaiveDate,
-    pub total_allowance: f64,
-    pub allocated_amount: f64,
-    pub remaining_amount: f64,
+    #[serde(with = "crate::money")]
+    pub total_allowance: Decimal,
+    #[serde(with = "crate::money")]
+    pub allocated_amount: Decimal,
+    #[serde(with = "crate::money")]
+    pub remaining_amount: Decimal,
     pub status: String,
     pub notes: Option<String>,
     pub created_by_username: String,
@@ -53,8 +60,10 @@ pub struct TruckAllocationResponse {
     pub truck_id: i64,
     pub truck_number: String,
     pub driver_username: Option<String>,
-    pub max_limit: f64,
-    pub amount: f64,
+    #[serde(with = "crate::money")]
+    pub max_limit: Decimal,
+    #[serde(with = "crate::money")]
+    pub amount: Decimal,
     pub distance_covered: Option<f64>,
     pub notes: Option<String>,
     pub created_at: DateTime<Utc>,
@@ -64,10 +73,20 @@ pub struct TruckAllocationResponse {
 pub struct AllowanceSummary {
     pub id: i64,
     pub allowance_date: NaiveDate,
-    pub total_allowance: f64,
-    pub allocated_amount: f64,
-    pub remaining_amount: f64,
+    #[serde(with = "crate::money")]
+    pub total_allowance: Decimal,
+    #[serde(with = "crate::money")]
+    pub allocated_amount: Decimal,
+    #[serde(with = "crate::money")]
+    pub remaining_amount: Decimal,
     pub status: String,
     pub truck_count: i32,
     pub created_by_username: String,
 }
+
+#[derive(Deserialize)]
+pub struct AllowanceListQuery {
+    pub status: Option<String>, // "pending", "allocated", "finalized"
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
@@ -1,19 +1,26 @@
 use serde::{Deserialize, Serialize};
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
 
 #[derive(Deserialize)]
 pub struct CreateDeliveryRequest {
     pub delivery_date: NaiveDate,
     pub received_by: Option<i64>,
     pub delivery_note_number: String,
+    pub supplier_id: Option<i64>, // defaults to the purchase order's supplier
+    pub purchase_order_id: Option<i64>, // open purchase order this delivery fills
     pub items: Vec<NewDeliveryItem>,
 }
 
 #[derive(Deserialize)]
 pub struct NewDeliveryItem {
     pub product_id: i64,
-    pub unit_price: f64,
-    pub batches: Vec<NewDeliveryBatch>,
+    #[serde(default, deserialize_with = "crate::money::option::deserialize")]
+    pub unit_price: Option<Decimal>, // defaults to the supplier's cost price
+    pub note_quantity: Option<i32>, // on the delivery note; defaults to the received quantity
+    pub damaged_quantity: Option<i32>, // arrived damaged, not put into stock
+    pub damage_reason: Option<String>, // required with damaged_quantity
+    pub batches: Vec<NewDeliveryBatch>, // good units only; may be empty when nothing usable arrived
 }
 
 #[derive(Deserialize)]
@@ -29,39 +36,107 @@ pub struct DeliveryResponse {
     pub delivery_date: NaiveDate,
     pub received_by: Option<i64>,
     pub delivery_note_number: String,
+    pub supplier_id: i64,
+    pub purchase_order_id: Option<i64>,
     pub items: Vec<DeliveryItemResponse>,
+    pub discrepancy: DeliveryDiscrepancy,
+    pub reversals: Vec<DeliveryReversalSummary>,
 }
 
 #[derive(Serialize)]
 pub struct DeliveryItemResponse {
     pub id: i64,
     pub product_id: i64,
-    pub quantity: i32,
-    pub unit_price: f64,
+    pub quantity: i32, // good units put into batch stock
+    pub note_quantity: i32,
+    pub received_quantity: i32,
+    pub damaged_quantity: i32,
+    pub damage_reason: Option<String>,
+    #[serde(with = "crate::money")]
+    pub unit_price: Decimal,
     pub batches: Vec<DeliveryBatchResponse>,
 }
 
+/// Delivery note against what arrived, over all lines.
+#[derive(Serialize)]
+pub struct DeliveryDiscrepancy {
+    pub note_quantity: i32,
+    pub received_quantity: i32,
+    pub good_quantity: i32,
+    pub damaged_quantity: i32,
+    pub short_quantity: i32, // on the note but not received
+    pub over_quantity: i32,  // received beyond the note
+    pub lines_with_discrepancy: i32,
+    pub claim: Option<DeliveryClaimSummary>,
+}
+
+#[derive(Serialize)]
+pub struct DeliveryClaimSummary {
+    pub id: i64,
+    pub status: String,
+    #[serde(with = "crate::money")]
+    pub total_value: Decimal,
+}
+
 #[derive(Serialize)]
 pub struct DeliveryBatchResponse {
     pub id: i64,
     pub batch_number: String,
     pub quantity: i32,
+    pub reversed_quantity: i32, // taken back out by reversals
     pub remaining_quantity: i32,
     pub expiry_date: NaiveDate,
 }
 
+#[derive(Serialize)]
+pub struct DeliveryReversalSummary {
+    pub id: i64,
+    pub warehouse_only: bool,
+    pub quantity: i32,
+    pub notes: Option<String>,
+    pub reversed_by: Option<i64>,
+    pub reversed_at: DateTime<Utc>,
+}
+
 #[derive(Serialize)]
 pub struct DeliverySummary {
     pub id: i64,
     pub delivery_date: NaiveDate,
     pub delivery_note_number: String,
     pub received_by: Option<i64>,
+    pub supplier_id: i64,
+    pub purchase_order_id: Option<i64>,
     pub total_items: i64,
 }
 
+#[derive(Deserialize)]
+pub struct DeliveryListQuery {
+    pub supplier_id: Option<i64>,
+    pub purchase_order_id: Option<i64>,
+}
+
 #[derive(Deserialize)]
 pub struct UpdateDeliveryRequest {
     pub delivery_date: Option<NaiveDate>,
     pub received_by: Option<Option<i64>>, // Some(Some(id)) set, Some(None) clear, None ignore
     pub delivery_note_number: Option<String>,
+    pub purchase_order_id: Option<i64>, // link to an open purchase order of the same supplier
+}
+
+// Line-level corrections after the delivery was recorded
+
+#[derive(Deserialize)]
+pub struct UpdateDeliveryItemRequest {
+    #[serde(default, deserialize_with = "crate::money::option::deserialize")]
+    pub unit_price: Option<Decimal>,
+    pub note_quantity: Option<i32>,
+    pub damaged_quantity: Option<i32>,
+    pub damage_reason: Option<String>, // required while damaged_quantity > 0
+}
+
+#[derive(Deserialize)]
+pub struct CorrectDeliveryBatchRequest {
+    pub quantity: Option<i32>, // good units this line put into the batch
+    pub expiry_date: Option<NaiveDate>, // only on batches this delivery created
+    pub batch_number: Option<String>,   // only on batches this delivery created
 }
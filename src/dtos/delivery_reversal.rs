@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+// Request DTOs
+
+#[derive(Deserialize)]
+pub struct CreateDeliveryReversalRequest {
+    #[serde(default)]
+    pub warehouse_only: bool, // take back only the units still in the warehouse
+    pub notes: Option<String>,
+}
+
+// Response DTOs
+
+/// What reversing the delivery now would take back, per batch receipt.
+#[derive(Serialize)]
+pub struct DeliveryReversalPreview {
+    pub delivery_id: i64,
+    pub fully_reversible: bool, // every outstanding unit is still in the warehouse
+    pub reversible_quantity: i32,
+    pub lines: Vec<ReversalPreviewLine>,
+}
+
+#[derive(Serialize)]
+pub struct ReversalPreviewLine {
+    pub delivery_item_id: i64,
+    pub product_id: i64,
+    pub batch_id: i64,
+    pub batch_number: String,
+    pub received_quantity: i32,
+    pub reversed_quantity: i32, // by earlier reversals
+    pub outstanding_quantity: i32,
+    pub reversible_quantity: i32,
+    // Where the batch's stock is now (all deliveries' units in the batch)
+    pub in_warehouse: i32,
+    pub on_trucks: i32,
+    pub sold: i32,
+    pub adjusted_out: i32, // expired, stocktake, manual and other deliveries' correction adjustments
+}
+
+#[derive(Serialize)]
+pub struct DeliveryReversalResponse {
+    pub id: i64,
+    pub delivery_id: i64,
+    pub warehouse_only: bool,
+    pub notes: Option<String>,
+    pub reversed_by: Option<i64>,
+    pub reversed_at: DateTime<Utc>,
+    pub quantity: i32,
+    pub lines: Vec<DeliveryReversalLine>,
+}
+
+#[derive(Serialize)]
+pub struct DeliveryReversalLine {
+    pub delivery_item_id: i64,
+    pub product_id: i64,
+    pub batch_id: i64,
+    pub batch_number: String,
+    pub quantity: i32,
+}
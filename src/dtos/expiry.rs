@@ -0,0 +1,46 @@
+use serde::Serialize;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+
+// Response DTOs
+
+#[derive(Serialize)]
+pub struct ExpiryWriteOffReport {
+    pub id: Option<i64>, // None when nothing had expired (the sweep is not stored)
+    pub triggered_by: String, // "scheduler" or "manual"
+    pub requested_by: Option<i64>,
+    pub as_of: NaiveDate,
+    pub batches_written_off: i32,
+    pub units_written_off: i32,
+    #[serde(with = "crate::money")]
+    pub total_value: Decimal,
+    pub created_at: Option<DateTime<Utc>>,
+    pub items: Vec<ExpiryWriteOffItem>,
+}
+
+#[derive(Serialize)]
+pub struct ExpiryWriteOffItem {
+    pub batch_id: i64,
+    pub batch_number: String,
+    pub product_id: i64,
+    pub product_name: String,
+    pub expiry_date: NaiveDate,
+    pub quantity: i32,
+    #[serde(with = "crate::money")]
+    pub unit_cost: Decimal,
+    #[serde(with = "crate::money")]
+    pub value: Decimal,
+}
+
+#[derive(Serialize)]
+pub struct ExpiryWriteOffSummary {
+    pub id: i64,
+    pub triggered_by: String,
+    pub requested_by_username: Option<String>,
+    pub as_of: NaiveDate,
+    pub batches_written_off: i32,
+    pub units_written_off: i32,
+    #[serde(with = "crate::money")]
+    pub total_value: Decimal,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+// Request DTOs
+
+#[derive(Deserialize)]
+pub struct RepairQuery {
+    pub dry_run: Option<bool>, // default true: report the corrections without posting them
+}
+
+// Response DTOs
+
+#[derive(Serialize)]
+pub struct IntegrityReport {
+    pub checked_at: DateTime<Utc>,
+    pub dry_run: bool,
+    pub batches_checked: i64,
+    pub truck_load_items_checked: i64,
+    pub reconciliations_checked: i64,
+    pub adjustments_posted: i32,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+#[derive(Serialize)]
+pub struct IntegrityIssue {
+    pub check: String,  // "batch_ledger", "truck_load_overdrawn", "truck_load_unaccounted", "truck_load_sold", "reconciliation_totals", "reconciliation_lines"
+    pub entity: String, // "batch", "truck_load_item", "daily_reconciliation", "reconciliation_item"
+    pub entity_id: i64,
+    pub expected: i64,
+    pub actual: i64,
+    pub message: String,
+    pub correction: Option<i32>, // adjustment quantity that fixes the ledger, when repairable
+    pub repaired: bool,
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+// Request DTOs
+
+#[derive(Deserialize)]
+pub struct ValuationQuery {
+    pub as_of: Option<NaiveDate>, // default today
+    pub supplier_id: Option<i64>, // only stock received from this supplier
+}
+
+// Response DTOs
+
+#[derive(Serialize)]
+pub struct OnHandProduct {
+    pub product_id: i64,
+    pub product_name: String,
+    pub warehouse_units: i64,
+    pub truck_units: i64, // out on truck loads that are not reconciled yet
+    pub total_units: i64,
+    pub earliest_expiry: Option<NaiveDate>,
+    pub batches: Vec<OnHandBatch>,
+}
+
+#[derive(Serialize)]
+pub struct OnHandBatch {
+    pub batch_id: i64,
+    pub batch_number: String,
+    pub expiry_date: NaiveDate,
+    pub warehouse_units: i32,
+    pub truck_units: i32,
+}
+
+#[derive(Serialize)]
+pub struct InventoryValuation {
+    pub as_of: NaiveDate,
+    pub supplier_id: Option<i64>,
+    pub warehouse_units: i32,
+    pub truck_units: i32, // out on truck loads that were not reconciled by as_of
+    pub total_units: i32,
+    #[serde(with = "crate::money")]
+    pub total_value: Decimal,
+    pub products: Vec<ProductValuation>,
+}
+
+#[derive(Serialize)]
+pub struct ProductValuation {
+    pub product_id: i64,
+    pub product_name: String,
+    pub warehouse_units: i32,
+    pub truck_units: i32,
+    pub units: i32,
+    #[serde(with = "crate::money")]
+    pub value: Decimal,
+    pub layers: Vec<ValuationLayer>, // oldest receipt first
+}
+
+#[derive(Serialize)]
+pub struct ValuationLayer {
+    pub batch_id: i64,
+    pub batch_number: String,
+    pub delivery_id: i64, // delivery of this receipt into the batch
+    pub supplier_id: i64,
+    pub delivery_date: NaiveDate,
+    pub expiry_date: NaiveDate,
+    pub warehouse_units: i32,
+    pub truck_units: i32,
+    pub units: i32,
+    #[serde(with = "crate::money")]
+    pub unit_cost: Decimal, // delivery_items.unit_price of the receipt
+    #[serde(with = "crate::money")]
+    pub value: Decimal,
+}
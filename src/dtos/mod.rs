@@ -8,3 +8,13 @@ pub mod sale;
 pub mod allowance;
 pub mod reconciliation;
 pub mod batch;
+pub mod pagination;
+pub mod expiry;
+pub mod report;
+pub mod inventory;
+pub mod integrity;
+pub mod stocktake;
+pub mod purchase_order;
+pub mod supplier;
+pub mod supplier_claim;
+pub mod delivery_reversal;
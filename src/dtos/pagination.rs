@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+// Shared by every paginated list endpoint, next to the endpoint's own filter struct:
+// ?limit=50&cursor=<next_cursor>&sort=-sale_date
+#[derive(Deserialize)]
+pub struct PageParams {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub sort: Option<String>, // whitelisted field, "-" prefix for descending
+}
+
+#[derive(Serialize)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub limit: i64,
+    pub next_cursor: Option<String>, // None on the last page
+}
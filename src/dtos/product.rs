@@ -1,26 +1,49 @@
 // src/dtos/product.rs
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize)]
 pub struct CreateProductRequest {
     pub name: String,
-    pub current_wholesale_price: f64,
-    pub commission_per_unit: f64,
+    #[serde(with = "crate::money")]
+    pub current_wholesale_price: Decimal,
+    #[serde(with = "crate::money")]
+    pub commission_per_unit: Decimal,
+    // Reorder settings; default 0, 0 and 1 day
+    pub reorder_point: Option<i32>,
+    pub safety_stock: Option<i32>,
+    pub lead_time_days: Option<i32>,
+    pub supplier_id: Option<i64>, // primary supplier
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateProductRequest {
     pub name: Option<String>,
-    pub current_wholesale_price: Option<f64>,
-    pub commission_per_unit: Option<f64>,
+    #[serde(default, deserialize_with = "crate::money::option::deserialize")]
+    pub current_wholesale_price: Option<Decimal>,
+    #[serde(default, deserialize_with = "crate::money::option::deserialize")]
+    pub commission_per_unit: Option<Decimal>,
+    pub reorder_point: Option<i32>,
+    pub safety_stock: Option<i32>,
+    pub lead_time_days: Option<i32>,
+    pub supplier_id: Option<i64>,
+    // Day a price/commission change takes effect; defaults to today, cannot be in the future
+    pub effective_from: Option<NaiveDate>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct ProductResponse {
     pub id: i64,
     pub name: String,
-    pub current_wholesale_price: f64,
-    pub commission_per_unit: f64,
+    #[serde(with = "crate::money")]
+    pub current_wholesale_price: Decimal,
+    #[serde(with = "crate::money")]
+    pub commission_per_unit: Decimal,
+    pub reorder_point: i32,
+    pub safety_stock: i32,
+    pub lead_time_days: i32,
+    pub supplier_id: Option<i64>,
     pub created_at: Option<String>,
 }
 
@@ -32,7 +55,36 @@ impl From<crate::models::product::Product> for ProductResponse {
             name: product.name,
             current_wholesale_price: product.current_wholesale_price,
             commission_per_unit: product.commission_per_unit,
+            reorder_point: product.reorder_point,
+            safety_stock: product.safety_stock,
+            lead_time_days: product.lead_time_days,
+            supplier_id: product.supplier_id,
             created_at: product.created_at.map(|dt| dt.to_rfc3339()),
         }
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProductPriceResponse {
+    pub effective_from: NaiveDate,
+    pub effective_to: Option<NaiveDate>, // last day before the next change
+    #[serde(with = "crate::money")]
+    pub wholesale_price: Decimal,
+    #[serde(with = "crate::money")]
+    pub commission_per_unit: Decimal,
+    pub changed_by: Option<i64>,
+    pub created_at: String,
+}
+
+impl From<crate::models::product::ProductPrice> for ProductPriceResponse {
+    fn from(price: crate::models::product::ProductPrice) -> Self {
+        Self {
+            effective_from: price.effective_from,
+            effective_to: price.effective_to,
+            wholesale_price: price.wholesale_price,
+            commission_per_unit: price.commission_per_unit,
+            changed_by: price.changed_by,
+            created_at: price.created_at.to_rfc3339(),
+        }
+    }
+}
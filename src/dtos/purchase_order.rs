@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+
+// Request DTOs
+
+#[derive(Deserialize)]
+pub struct CreatePurchaseOrderRequest {
+    pub po_number: String,
+    pub supplier_id: i64,
+    pub order_date: NaiveDate,
+    pub expected_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+    pub lines: Vec<NewPurchaseOrderLine>,
+}
+
+#[derive(Deserialize)]
+pub struct NewPurchaseOrderLine {
+    pub product_id: i64,
+    pub quantity: i32,
+    #[serde(with = "crate::money")]
+    pub unit_price: Decimal,
+}
+
+#[derive(Deserialize)]
+pub struct PurchaseOrderListQuery {
+    pub status: Option<String>, // "open", "closed", "cancelled"
+    pub supplier_id: Option<i64>,
+    pub from: Option<NaiveDate>, // order_date range
+    pub to: Option<NaiveDate>,
+}
+
+// Response DTOs
+
+#[derive(Serialize)]
+pub struct PurchaseOrderResponse {
+    pub id: i64,
+    pub po_number: String,
+    pub supplier_id: i64,
+    pub supplier_name: String,
+    pub order_date: NaiveDate,
+    pub expected_date: Option<NaiveDate>,
+    pub status: String,
+    pub notes: Option<String>,
+    pub created_by_username: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::money")]
+    pub total_value: Decimal,
+    pub lines: Vec<PurchaseOrderLineResponse>,
+    pub delivery_ids: Vec<i64>,
+}
+
+#[derive(Serialize)]
+pub struct PurchaseOrderLineResponse {
+    pub id: i64,
+    pub product_id: i64,
+    pub product_name: String,
+    pub quantity: i32,
+    #[serde(with = "crate::money")]
+    pub unit_price: Decimal,
+    #[serde(with = "crate::money")]
+    pub line_value: Decimal,
+}
+
+#[derive(Serialize)]
+pub struct PurchaseOrderListItem {
+    pub id: i64,
+    pub po_number: String,
+    pub supplier_id: i64,
+    pub supplier_name: String,
+    pub order_date: NaiveDate,
+    pub expected_date: Option<NaiveDate>,
+    pub status: String,
+    pub line_count: i64,
+    #[serde(with = "crate::money")]
+    pub total_value: Decimal,
+    pub delivery_count: i64,
+}
+
+/// Ordered vs received quantity vs invoiced (delivery) price, per product.
+#[derive(Serialize)]
+pub struct PurchaseOrderComparison {
+    pub purchase_order_id: i64,
+    pub po_number: String,
+    pub status: String,
+    pub summary: ComparisonSummary,
+    pub lines: Vec<ComparisonLine>,
+}
+
+#[derive(Serialize)]
+pub struct ComparisonSummary {
+    pub ordered_quantity: i32,
+    pub received_quantity: i32,
+    pub short_lines: i32,
+    pub over_lines: i32,
+    pub unordered_lines: i32,
+    pub price_mismatch_lines: i32,
+    #[serde(with = "crate::money")]
+    pub ordered_value: Decimal,
+    #[serde(with = "crate::money")]
+    pub received_value: Decimal, // at delivery_items.unit_price
+    #[serde(with = "crate::money")]
+    pub price_variance: Decimal, // received units at delivery price minus at order price
+}
+
+#[derive(Serialize)]
+pub struct ComparisonLine {
+    pub product_id: i64,
+    pub product_name: String,
+    pub ordered_quantity: i32,
+    pub received_quantity: i32,
+    pub quantity_variance: i32, // received - ordered
+    pub quantity_status: String, // "matched", "short", "over", "not_ordered"
+    #[serde(with = "crate::money::option")]
+    pub ordered_unit_price: Option<Decimal>,
+    #[serde(with = "crate::money")]
+    pub ordered_value: Decimal,
+    #[serde(with = "crate::money")]
+    pub received_value: Decimal,
+    #[serde(with = "crate::money")]
+    pub price_variance: Decimal,
+    pub deliveries: Vec<ComparisonDelivery>,
+}
+
+#[derive(Serialize)]
+pub struct ComparisonDelivery {
+    pub delivery_id: i64,
+    pub delivery_note_number: String,
+    pub delivery_date: NaiveDate,
+    pub quantity: i32,          // net of reversals
+    pub reversed_quantity: i32, // sent back by delivery reversals
+    #[serde(with = "crate::money")]
+    pub unit_price: Decimal,
+    #[serde(with = "crate::money::option")]
+    pub unit_price_difference: Option<Decimal>, // delivery price - order price
+}
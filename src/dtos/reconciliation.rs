@@ -1,4 +1,5 @@
 use chrono::NaiveDate;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 // ==================== Enums ====================
@@ -43,6 +44,13 @@ pub struct DiscardedItem {
     pub reason: String, // "damaged", "expired", "wasted"
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ReconciliationListQuery {
+    pub status: Option<String>, // "in_progress", "completed", "finalized"
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ReconciliationResponse {
     pub id: i64,
@@ -60,12 +68,18 @@ pub struct ReconciliationResponse {
     pub total_items_discarded: f64,
 
     // Financial summary
-    pub total_sales_amount: f64,
-    pub total_commission_earned: f64,
-    pub total_allowance_allocated: f64,
-    pub total_payments_collected: f64,
-    pub pending_payments: f64,
-    pub net_profit: f64,
+    #[serde(with = "crate::money")]
+    pub total_sales_amount: Decimal,
+    #[serde(with = "crate::money")]
+    pub total_commission_earned: Decimal,
+    #[serde(with = "crate::money")]
+    pub total_allowance_allocated: Decimal,
+    #[serde(with = "crate::money")]
+    pub total_payments_collected: Decimal,
+    #[serde(with = "crate::money")]
+    pub pending_payments: Decimal,
+    #[serde(with = "crate::money")]
+    pub net_profit: Decimal,
 
     // Metadata
     pub started_by: Option<i64>,
@@ -101,14 +115,43 @@ pub struct TruckVerificationItem {
     pub discrepancy_notes: Option<String>,
 
     // Financial
-    pub sales_amount: f64,
-    pub commission_earned: f64,
-    pub allowance_received: f64,
-    pub payments_collected: f64,
-    pub pending_payments: f64,
+    #[serde(with = "crate::money")]
+    pub sales_amount: Decimal,
+    #[serde(with = "crate::money")]
+    pub commission_earned: Decimal,
+    #[serde(with = "crate::money")]
+    pub allowance_received: Decimal,
+    #[serde(with = "crate::money")]
+    pub payments_collected: Decimal,
+    #[serde(with = "crate::money")]
+    pub pending_payments: Decimal,
 
     pub verified_by: Option<i64>,
     pub verified_at: Option<chrono::NaiveDateTime>,
+
+    // Per-product breakdown of the truck load and the verified lines
+    pub products: Vec<ProductVerification>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProductVerification {
+    pub product_id: i64,
+    pub product_name: String,
+    pub quantity_loaded: i64,
+    pub quantity_sold: i64,
+    pub quantity_returned: i64,
+    pub quantity_discarded: i64,
+    pub quantity_unaccounted: i64, // left on the truck but neither returned nor discarded
+    pub batches: Vec<VerificationBatchLine>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VerificationBatchLine {
+    pub batch_id: i64,
+    pub batch_number: String,
+    pub line_type: String, // "returned" or "discarded"
+    pub quantity: i32,
+    pub discard_reason: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -118,7 +161,8 @@ pub struct ReconciliationSummary {
     pub status: String,
     pub trucks_out: i32,
     pub trucks_verified: i32,
-    pub net_profit: f64,
+    #[serde(with = "crate::money")]
+    pub net_profit: Decimal,
     pub profit_status: String, // "profit" or "loss"
     pub started_at: chrono::NaiveDateTime,
     pub finalized_at: Option<chrono::NaiveDateTime>,
@@ -126,6 +170,25 @@ pub struct ReconciliationSummary {
 
 // ==================== Stock Movement DTOs ====================
 
+#[derive(Debug, Deserialize)]
+pub struct ProductMovementQuery {
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub movement_type: Option<StockMovementType>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StockBalanceQuery {
+    pub date: Option<NaiveDate>, // end of this day; default today
+    pub product_id: Option<i64>,
+    pub batch_id: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BalanceCheckQuery {
+    pub mismatches_only: Option<bool>, // default true
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateStockAdjustmentRequest {
     pub batch_id: i64,
@@ -177,6 +240,44 @@ pub struct StockMovementDetail {
     pub running_balance: f64,
 }
 
+#[derive(Debug, Serialize)]
+pub struct StockBalanceAsOf {
+    pub date: NaiveDate,
+    pub product_id: Option<i64>,
+    pub batch_id: Option<i64>,
+    pub total_units: i64,
+    pub batches: Vec<BatchBalanceAsOf>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchBalanceAsOf {
+    pub batch_id: i64,
+    pub batch_number: String,
+    pub product_id: i64,
+    pub product_name: String,
+    pub expiry_date: NaiveDate,
+    pub balance: i32, // warehouse units at the end of the day, from the ledger
+}
+
+#[derive(Debug, Serialize)]
+pub struct LedgerBalanceCheck {
+    pub batches_checked: i64,
+    pub mismatches: i64,
+    pub batches: Vec<BatchBalanceCheck>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchBalanceCheck {
+    pub batch_id: i64,
+    pub batch_number: String,
+    pub product_id: i64,
+    pub product_name: String,
+    pub ledger_balance: i32,    // every movement of the batch, whatever its date
+    pub remaining_quantity: i32,
+    pub difference: i32,        // remaining_quantity - ledger_balance
+    pub matches: bool,
+}
+
 #[derive(Debug, Serialize)]
 pub struct DailyStockSummary {
     pub movement_date: NaiveDate,
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+// Request DTOs
+
+#[derive(Deserialize)]
+pub struct ExpiringReportQuery {
+    pub within_days: Option<i32>, // default 7
+}
+
+#[derive(Deserialize)]
+pub struct ReorderReportQuery {
+    pub window_days: Option<i32>, // sales history used for the daily average, default 28
+    pub cover_days: Option<i32>,  // days of demand the order should cover after it arrives, default 1
+}
+
+// Response DTOs
+
+#[derive(Serialize)]
+pub struct ExpiringStockReport {
+    pub as_of: NaiveDate,
+    pub within_days: i32,
+    pub total_units: i32,
+    #[serde(with = "crate::money")]
+    pub total_value: Decimal,
+    pub products: Vec<ExpiringProduct>,
+}
+
+#[derive(Serialize)]
+pub struct ExpiringProduct {
+    pub product_id: i64,
+    pub product_name: String,
+    pub earliest_expiry: NaiveDate,
+    pub warehouse_units: i32,
+    pub truck_units: i32, // still out on trucks that are not reconciled yet
+    #[serde(with = "crate::money")]
+    pub value: Decimal,
+    pub batches: Vec<ExpiringBatch>,
+}
+
+#[derive(Serialize)]
+pub struct ExpiringBatch {
+    pub batch_id: i64,
+    pub batch_number: String,
+    pub expiry_date: NaiveDate,
+    pub days_left: i32,
+    pub warehouse_units: i32,
+    pub truck_units: i32,
+    #[serde(with = "crate::money")]
+    pub unit_cost: Decimal, // average cost of its FIFO receipt layers
+    #[serde(with = "crate::money")]
+    pub value: Decimal,
+}
+
+#[derive(Serialize)]
+pub struct ReorderReport {
+    pub as_of: NaiveDate,
+    pub window_days: i32,
+    pub cover_days: i32,
+    pub products: Vec<ReorderSuggestion>,
+}
+
+#[derive(Serialize)]
+pub struct ReorderSuggestion {
+    pub product_id: i64,
+    pub product_name: String,
+    pub warehouse_units: i64,
+    pub truck_units: i64,
+    pub on_hand: i64,           // warehouse + trucks
+    pub units_sold: i64,        // in the window
+    pub avg_daily_sales: f64,
+    pub lead_time_days: i32,
+    pub safety_stock: i32,
+    pub reorder_point: i32,     // as configured on the product
+    pub effective_reorder_point: i64, // max(reorder_point, lead time demand + safety stock)
+    pub needs_reorder: bool,
+    pub suggested_order_quantity: i64,
+}
@@ -1,12 +1,14 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc, NaiveDate};
+use rust_decimal::Decimal;
 
 #[derive(Deserialize)]
 pub struct CreateSaleRequest {
     pub shop_id: i64,
     pub truck_load_id: i64,
     pub sale_date: NaiveDate,
-    pub amount_paid: Option<f64>,
+    #[serde(default, deserialize_with = "crate::money::option::deserialize")]
+    pub amount_paid: Option<Decimal>,
     pub items: Vec<SaleItemRequest>,
 }
 
@@ -14,12 +16,14 @@ pub struct CreateSaleRequest {
 pub struct SaleItemRequest {
     pub product_id: i64,
     pub quantity: i32,
-    pub unit_price: Option<f64>, // Optional - uses current_wholesale_price if not provided
+    #[serde(default, deserialize_with = "crate::money::option::deserialize")]
+    pub unit_price: Option<Decimal>, // Optional - uses the wholesale price on sale_date if not provided
 }
 
 #[derive(Deserialize)]
 pub struct UpdatePaymentRequest {
-    pub additional_payment: f64,
+    #[serde(with = "crate::money")]
+    pub additional_payment: Decimal,
 }
 
 #[derive(Serialize)]
@@ -32,8 +36,10 @@ pub struct SaleResponse {
     pub driver_id: i64,
     pub driver_username: String,
     pub truck_load_id: i64,
-    pub total_amount: f64,
-    pub amount_paid: f64,
+    #[serde(with = "crate::money")]
+    pub total_amount: Decimal,
+    #[serde(with = "crate::money")]
+    pub amount_paid: Decimal,
     pub payment_status: String,
     pub sale_date: NaiveDate,
     pub created_at: DateTime<Utc>,
@@ -49,16 +55,21 @@ pub struct SaleItemResponse {
     pub batch_id: i64,
     pub batch_number: String,
     pub quantity: i32,
-    pub unit_price: f64,
-    pub commission_earned: f64,
-    pub line_total: f64,
+    #[serde(with = "crate::money")]
+    pub unit_price: Decimal,
+    #[serde(with = "crate::money")]
+    pub commission_earned: Decimal,
+    #[serde(with = "crate::money")]
+    pub line_total: Decimal,
 }
 
 #[derive(Serialize)]
 pub struct SaleSummary {
     pub total_items: i32,
-    pub total_commission: f64,
-    pub balance_due: f64,
+    #[serde(with = "crate::money")]
+    pub total_commission: Decimal,
+    #[serde(with = "crate::money")]
+    pub balance_due: Decimal,
 }
 
 #[derive(Serialize)]
@@ -67,9 +78,19 @@ pub struct SaleListItem {
     pub shop_name: String,
     pub truck_number: String,
     pub driver_username: String,
-    pub total_amount: f64,
-    pub amount_paid: f64,
+    #[serde(with = "crate::money")]
+    pub total_amount: Decimal,
+    #[serde(with = "crate::money")]
+    pub amount_paid: Decimal,
     pub payment_status: String,
     pub sale_date: NaiveDate,
     pub total_items: i32,
 }
+
+#[derive(Deserialize)]
+pub struct SaleListQuery {
+    pub driver_id: Option<i64>,
+    pub shop_id: Option<i64>,
+    pub sale_date: Option<NaiveDate>,
+    pub payment_status: Option<String>, // "paid", "pending"
+}
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+
+// Request DTOs
+
+#[derive(Deserialize)]
+pub struct CreateStocktakeRequest {
+    pub product_ids: Vec<i64>,
+    pub notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RecordCountsRequest {
+    pub counts: Vec<StocktakeCountRequest>,
+}
+
+#[derive(Deserialize)]
+pub struct StocktakeCountRequest {
+    pub batch_id: i64,
+    pub counted_quantity: i32, // replaces any earlier count of the batch in this session
+}
+
+#[derive(Deserialize)]
+pub struct StocktakeListQuery {
+    pub status: Option<String>, // "open", "posted", "cancelled"
+}
+
+// Response DTOs
+
+#[derive(Serialize)]
+pub struct StocktakeResponse {
+    pub id: i64,
+    pub status: String,
+    pub notes: Option<String>,
+    pub opened_by_username: Option<String>,
+    pub opened_at: DateTime<Utc>,
+    pub closed_by_username: Option<String>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub product_ids: Vec<i64>,
+    pub summary: StocktakeSummary,
+    pub lines: Vec<StocktakeLine>,
+}
+
+#[derive(Serialize)]
+pub struct StocktakeSummary {
+    pub batches: i32,
+    pub counted_batches: i32,
+    pub batches_with_variance: i32,
+    pub net_variance: i32,
+}
+
+#[derive(Serialize)]
+pub struct StocktakeLine {
+    pub batch_id: i64,
+    pub batch_number: String,
+    pub product_id: i64,
+    pub product_name: String,
+    pub expiry_date: NaiveDate,
+    pub system_quantity: Option<i32>, // remaining_quantity when counted; live while uncounted
+    pub counted_quantity: Option<i32>,
+    pub variance: Option<i32>, // counted - system
+    pub counted_by_username: Option<String>,
+    pub counted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct StocktakeListItem {
+    pub id: i64,
+    pub status: String,
+    pub opened_by_username: Option<String>,
+    pub opened_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub product_count: i64,
+    pub counted_batches: i64,
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+// Request DTOs
+
+#[derive(Deserialize)]
+pub struct CreateSupplierRequest {
+    pub name: String,
+    pub contact_name: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateSupplierRequest {
+    pub name: Option<String>,
+    pub contact_name: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct SupplierListQuery {
+    pub is_active: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct SetSupplierPriceRequest {
+    #[serde(with = "crate::money")]
+    pub cost_price: Decimal,
+}
+
+// Response DTOs
+
+#[derive(Serialize)]
+pub struct SupplierResponse {
+    pub id: i64,
+    pub name: String,
+    pub contact_name: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub products: Vec<SupplierProductPrice>,
+}
+
+#[derive(Serialize)]
+pub struct SupplierProductPrice {
+    pub product_id: i64,
+    pub product_name: String,
+    #[serde(with = "crate::money")]
+    pub cost_price: Decimal,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct SupplierSummary {
+    pub id: i64,
+    pub name: String,
+    pub contact_name: Option<String>,
+    pub phone: Option<String>,
+    pub is_active: bool,
+    pub product_count: i64,
+}
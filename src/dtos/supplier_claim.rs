@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+
+// Request DTOs
+
+#[derive(Deserialize)]
+pub struct SupplierClaimListQuery {
+    pub status: Option<String>, // "open", "settled", "rejected"
+    pub supplier_id: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct ResolveClaimRequest {
+    pub status: String, // "settled" or "rejected"
+    pub notes: Option<String>,
+}
+
+// Response DTOs
+
+#[derive(Serialize)]
+pub struct SupplierClaimResponse {
+    pub id: i64,
+    pub delivery_id: i64,
+    pub delivery_note_number: String,
+    pub delivery_date: NaiveDate,
+    pub supplier_id: i64,
+    pub supplier_name: String,
+    pub status: String,
+    #[serde(with = "crate::money")]
+    pub total_value: Decimal,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub resolved_by_username: Option<String>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub lines: Vec<SupplierClaimLine>,
+}
+
+#[derive(Serialize)]
+pub struct SupplierClaimLine {
+    pub id: i64,
+    pub delivery_item_id: i64,
+    pub product_id: i64,
+    pub product_name: String,
+    pub claim_type: String, // "damaged" or "short"
+    pub quantity: i32,
+    #[serde(with = "crate::money")]
+    pub unit_price: Decimal,
+    #[serde(with = "crate::money")]
+    pub value: Decimal,
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SupplierClaimListItem {
+    pub id: i64,
+    pub delivery_id: i64,
+    pub delivery_note_number: String,
+    pub supplier_id: i64,
+    pub supplier_name: String,
+    pub status: String,
+    #[serde(with = "crate::money")]
+    pub total_value: Decimal,
+    pub created_at: DateTime<Utc>,
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 
 #[derive(Deserialize)]
 pub struct CreateTruckRequest {
@@ -16,7 +17,8 @@ pub struct UpdateTruckRequest {
 
 #[derive(Deserialize)]
 pub struct UpdateTruckMaxLimitRequest {
-    pub max_allowance_limit: f64,
+    #[serde(with = "crate::money")]
+    pub max_allowance_limit: Decimal,
 }
 
 #[derive(Serialize)]
@@ -26,7 +28,8 @@ pub struct TruckResponse {
     pub driver_id: Option<i64>,
     pub driver_username: Option<String>,
     pub is_active: bool,
-    pub max_allowance_limit: f64,
+    #[serde(with = "crate::money")]
+    pub max_allowance_limit: Decimal,
     pub created_at: DateTime<Utc>,
 }
 
@@ -7,6 +7,9 @@ pub struct CreateTruckLoadRequest {
     pub load_date: NaiveDate,
     pub loaded_by: i64,
     pub notes: Option<String>,
+    // Days of shelf life a batch must have left on load_date to be auto-selected;
+    // defaults to MIN_SHELF_LIFE_DAYS
+    pub min_shelf_life_days: Option<i32>,
     pub items: Vec<TruckLoadItemRequest>,
 }
 
@@ -42,6 +45,19 @@ pub struct TruckLoadResponse {
     pub created_at: DateTime<Utc>,
     pub items: Vec<TruckLoadItemResponse>,
     pub summary: TruckLoadSummary,
+    // Batches passed over by FIFO product selection (only on create)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_batches: Vec<SkippedBatch>,
+}
+
+#[derive(Serialize)]
+pub struct SkippedBatch {
+    pub batch_id: i64,
+    pub batch_number: String,
+    pub product_id: i64,
+    pub expiry_date: NaiveDate,
+    pub remaining_quantity: i32,
+    pub reason: String, // "expired", "short_shelf_life"
 }
 
 #[derive(Serialize)]
@@ -80,3 +96,10 @@ pub struct TruckLoadListItem {
     pub total_returned: i32,
     pub total_lost_damaged: i32,
 }
+
+#[derive(Deserialize)]
+pub struct TruckLoadListQuery {
+    pub truck_id: Option<i64>,
+    pub load_date: Option<NaiveDate>,
+    pub status: Option<String>, // "loaded", "in_transit", "returned", "reconciled"
+}
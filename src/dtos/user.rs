@@ -28,6 +28,13 @@ pub struct LoginResponse {
     pub access_token: String,
     pub token_type: &'static str,
     pub expires_in_seconds: usize,
+    pub refresh_token: String,
+    pub refresh_expires_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
 }
 
 #[derive(Serialize)]
@@ -35,4 +42,27 @@ pub struct MeResponse {
     pub id: i64,
     pub role: String,
     pub username: String,
-}
\ No newline at end of file
+}
+
+#[derive(Deserialize)]
+pub struct UserListQuery {
+    pub role: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateUserRoleRequest {
+    pub role: String,
+}
+
+// Manager-initiated reset; the user's current password is not required
+#[derive(Deserialize)]
+pub struct ResetPasswordRequest {
+    pub new_password: String,
+}
+
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
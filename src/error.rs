@@ -11,6 +11,7 @@ pub enum AppError {
     Conflict(String),
     NotFound(String),
     Forbidden(String),
+    Unauthorized(String),
     Db(sqlx::Error),
     Internal(String),
 }
@@ -20,6 +21,7 @@ impl AppError {
     pub fn conflict(msg: impl Into<String>) -> Self { Self::Conflict(msg.into()) }
     pub fn not_found(msg: impl Into<String>) -> Self { Self::NotFound(msg.into()) }
     pub fn forbidden(msg: impl Into<String>) -> Self { Self::Forbidden(msg.into()) }
+    pub fn unauthorized(msg: impl Into<String>) -> Self { Self::Unauthorized(msg.into()) }
     pub fn db(e: sqlx::Error) -> Self { Self::Db(e) }
     pub fn internal(msg: impl Into<String>) -> Self { Self::Internal(msg.into()) }
 }
@@ -37,6 +39,7 @@ impl IntoResponse for AppError {
             AppError::Conflict(m) => (StatusCode::CONFLICT, m, "conflict"),
             AppError::NotFound(m) => (StatusCode::NOT_FOUND, m, "not_found"),
             AppError::Forbidden(m) => (StatusCode::FORBIDDEN, m, "forbidden"),
+            AppError::Unauthorized(m) => (StatusCode::UNAUTHORIZED, m, "unauthorized"),
             AppError::Db(e) => {
                 // Fallback for unmapped DB errors
                 (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {e}"), "db_error")
@@ -63,8 +66,8 @@ impl From<sqlx::Error> for AppError {
                 match (code, constraint) {
                     (Some("23514"), Some("delivery_items_unit_price_check")) =>
                         AppError::Validation("unit_price must be greater than or equal to 0".into()), // check_violation
-                    (Some("23514"), Some("delivery_items_quantity_check")) =>
-                        AppError::Validation("quantity must be greater than 0".into()),
+                    (Some("23514"), Some("valid_received_quantities")) =>
+                        AppError::Validation("Delivery line quantities must not be negative, received = good + damaged, and a line needs a note or received quantity".into()),
                     (Some("23514"), Some("batches_remaining_quantity_check")) =>
                         AppError::Validation("remaining_quantity must be between 0 and quantity".into()),
                     (Some("23514"), Some("batches_check")) =>
@@ -89,7 +92,7 @@ impl From<sqlx::Error> for AppError {
                     (Some("23514"), Some("truck_load_items_check")) =>
                         AppError::Validation("Truck load constraint: quantity_sold cannot exceed quantity_loaded".into()),
                     (Some("23514"), Some("truck_load_items_check1")) =>
-                        AppError::Validation("Truck load constraint: quantity_sold + quantity_returned cannot exceed quantity_loaded".into()),
+                        AppError::Validation("Truck load constraint: quantity_sold + quantity_returned + quantity_discarded cannot exceed quantity_loaded".into()),
                     // Reconciliation constraints
                     (Some("23514"), Some("valid_stock_balance")) =>
                         AppError::Validation("Reconciliation balance error: items_loaded must equal (items_sold + items_returned + items_discarded) when verified".into()),
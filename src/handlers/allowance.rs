@@ -1,34 +1,36 @@
-use axum::{extract::State, Json, Extension};
+use axum::{extract::State, Json};
 use axum::http::StatusCode;
 use crate::state::AppState;
 use crate::error::AppError;
 use crate::dtos::allowance::{
     CreateTransportAllowanceRequest, AllocateToTrucksRequest,
     UpdateTruckAllocationRequest, TransportAllowanceResponse,
-    TruckAllocationResponse, AllowanceSummary,
+    TruckAllocationResponse, AllowanceSummary, AllowanceListQuery,
 };
-use crate::middleware::auth::AuthContext;
+use crate::database::filter::{self, ListFilter, Page, Sort};
+use crate::dtos::pagination::{PageParams, Paginated};
+use crate::auth::role::Manager;
+use crate::middleware::auth::RequireRole;
+use crate::money;
+use rust_decimal::Decimal;
 
 pub async fn create_allowance(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
     Json(req): Json<CreateTransportAllowanceRequest>,
 ) -> Result<(StatusCode, Json<TransportAllowanceResponse>), AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden("Only managers can create allowances"));
-    }
-
-    if req.total_allowance <= 0.0 {
+    let total_allowance = money::normalize(req.total_allowance);
+    if total_allowance <= Decimal::ZERO {
         return Err(AppError::validation("Total allowance must be greater than 0"));
     }
 
     let allowance = sqlx::query!(
         r#"INSERT INTO transport_allowances (allowance_date, total_allowance, notes, created_by)
-        VALUES ($1, $2::FLOAT8, $3, $4)
-        RETURNING id, allowance_date, (total_allowance)::FLOAT8 as "total_allowance!", 
-                  (allocated_amount)::FLOAT8 as "allocated_amount!", status, notes, created_at, updated_at"#,
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, allowance_date, total_allowance as "total_allowance!", 
+                  allocated_amount as "allocated_amount!", status, notes, created_at, updated_at"#,
         req.allowance_date,
-        req.total_allowance,
+        total_allowance,
         req.notes,
         auth.user_id
     )
@@ -65,14 +67,10 @@ pub async fn create_allowance(
 
 pub async fn allocate_to_trucks(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    _: RequireRole<Manager>,
     axum::extract::Path(id): axum::extract::Path<i64>,
     Json(req): Json<AllocateToTrucksRequest>,
 ) -> Result<Json<TransportAllowanceResponse>, AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden("Only managers can allocate allowances"));
-    }
-
     if req.allocations.is_empty() {
         return Err(AppError::validation("At least one truck allocation is required"));
     }
@@ -82,8 +80,8 @@ pub async fn allocate_to_trucks(
 
     // Get allowance and check status
     let allowance = sqlx::query!(
-        r#"SELECT id, allowance_date, (total_allowance)::FLOAT8 as "total_allowance!", 
-           (allocated_amount)::FLOAT8 as "allocated_amount!", status
+        r#"SELECT id, allowance_date, total_allowance as "total_allowance!", 
+           allocated_amount as "allocated_amount!", status
         FROM transport_allowances
         WHERE id = $1"#,
         id
@@ -97,7 +95,7 @@ pub async fn allocate_to_trucks(
     }
 
     // Calculate total new allocations
-    let total_new_allocations: f64 = req.allocations.iter().map(|a| a.amount).sum();
+    let total_new_allocations: Decimal = req.allocations.iter().map(|a| money::normalize(a.amount)).sum();
 
     // Check if total allocation exceeds total allowance
     if allowance.allocated_amount + total_new_allocations > allowance.total_allowance {
@@ -112,7 +110,7 @@ pub async fn allocate_to_trucks(
 
     // Validate each allocation
     for allocation in &req.allocations {
-        if allocation.amount <= 0.0 {
+        if money::normalize(allocation.amount) <= Decimal::ZERO {
             return Err(AppError::validation("Allocation amount must be greater than 0"));
         }
 
@@ -124,7 +122,7 @@ pub async fn allocate_to_trucks(
 
         // Check if truck exists and get max limit
         let truck = sqlx::query!(
-            r#"SELECT id, truck_number, is_active, (max_allowance_limit)::FLOAT8 as "max_allowance_limit!"
+            r#"SELECT id, truck_number, is_active, max_allowance_limit as "max_allowance_limit!"
             FROM trucks
             WHERE id = $1"#,
             allocation.truck_id
@@ -138,7 +136,7 @@ pub async fn allocate_to_trucks(
         }
 
         // Check if amount exceeds truck's max limit
-        if allocation.amount > truck.max_allowance_limit {
+        if money::normalize(allocation.amount) > truck.max_allowance_limit {
             return Err(AppError::validation(&format!(
                 "Allocation amount ({}) exceeds truck {}'s max limit ({})",
                 allocation.amount, truck.truck_number, truck.max_allowance_limit
@@ -166,10 +164,10 @@ pub async fn allocate_to_trucks(
     for allocation in &req.allocations {
         sqlx::query!(
             r#"INSERT INTO truck_allowances (transport_allowance_id, truck_id, amount, distance_covered, notes)
-            VALUES ($1, $2, $3::FLOAT8, $4::FLOAT8, $5)"#,
+            VALUES ($1, $2, $3, $4::FLOAT8, $5)"#,
             id,
             allocation.truck_id,
-            allocation.amount,
+            money::normalize(allocation.amount),
             allocation.distance_covered,
             allocation.notes
         )
@@ -194,15 +192,12 @@ pub async fn allocate_to_trucks(
 
 pub async fn update_truck_allocation(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    _: RequireRole<Manager>,
     axum::extract::Path((allowance_id, truck_id)): axum::extract::Path<(i64, i64)>,
     Json(req): Json<UpdateTruckAllocationRequest>,
 ) -> Result<Json<TransportAllowanceResponse>, AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden("Only managers can update allocations"));
-    }
-
-    if req.amount <= 0.0 {
+    let amount = money::normalize(req.amount);
+    if amount <= Decimal::ZERO {
         return Err(AppError::validation("Allocation amount must be greater than 0"));
     }
 
@@ -217,7 +212,7 @@ pub async fn update_truck_allocation(
 
     // Check allowance status
     let allowance = sqlx::query!(
-        r#"SELECT status, (total_allowance)::FLOAT8 as "total_allowance!", (allocated_amount)::FLOAT8 as "allocated_amount!"
+        r#"SELECT status, total_allowance as "total_allowance!", allocated_amount as "allocated_amount!"
         FROM transport_allowances WHERE id = $1"#,
         allowance_id
     )
@@ -231,7 +226,7 @@ pub async fn update_truck_allocation(
 
     // Get current allocation
     let current_allocation = sqlx::query!(
-        r#"SELECT (amount)::FLOAT8 as "amount!" FROM truck_allowances
+        r#"SELECT amount as "amount!" FROM truck_allowances
         WHERE transport_allowance_id = $1 AND truck_id = $2"#,
         allowance_id,
         truck_id
@@ -242,7 +237,7 @@ pub async fn update_truck_allocation(
 
     // Get truck max limit
     let truck = sqlx::query!(
-        r#"SELECT (max_allowance_limit)::FLOAT8 as "max_allowance_limit!" FROM trucks WHERE id = $1"#,
+        r#"SELECT max_allowance_limit as "max_allowance_limit!" FROM trucks WHERE id = $1"#,
         truck_id
     )
     .fetch_optional(&mut *tx)
@@ -250,15 +245,15 @@ pub async fn update_truck_allocation(
     .ok_or_else(|| AppError::not_found("Truck not found"))?;
 
     // Check if new amount exceeds max limit
-    if req.amount > truck.max_allowance_limit {
+    if amount > truck.max_allowance_limit {
         return Err(AppError::validation(&format!(
             "Allocation amount ({}) exceeds truck's max limit ({})",
-            req.amount, truck.max_allowance_limit
+            amount, truck.max_allowance_limit
         )));
     }
 
     // Calculate new total allocated (subtract old, add new)
-    let new_total_allocated = allowance.allocated_amount - current_allocation.amount + req.amount;
+    let new_total_allocated = allowance.allocated_amount - current_allocation.amount + amount;
 
     if new_total_allocated > allowance.total_allowance {
         return Err(AppError::validation(&format!(
@@ -270,11 +265,11 @@ pub async fn update_truck_allocation(
     // Update allocation
     sqlx::query!(
         r#"UPDATE truck_allowances
-        SET amount = $3::FLOAT8, distance_covered = $4::FLOAT8, notes = $5
+        SET amount = $3, distance_covered = $4::FLOAT8, notes = $5
         WHERE transport_allowance_id = $1 AND truck_id = $2"#,
         allowance_id,
         truck_id,
-        req.amount,
+        amount,
         req.distance_covered,
         req.notes
     )
@@ -290,13 +285,9 @@ pub async fn update_truck_allocation(
 
 pub async fn finalize_allowance(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    _: RequireRole<Manager>,
     axum::extract::Path(id): axum::extract::Path<i64>,
 ) -> Result<Json<TransportAllowanceResponse>, AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden("Only managers can finalize allowances"));
-    }
-
     let result = sqlx::query!(
         r#"UPDATE transport_allowances
         SET status = 'finalized'
@@ -313,6 +304,7 @@ pub async fn finalize_allowance(
 
 pub async fn get_allowance(
     State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
     axum::extract::Path(id): axum::extract::Path<i64>,
 ) -> Result<Json<TransportAllowanceResponse>, AppError> {
     fetch_allowance_by_id(&db_pool, id).await.map(Json)
@@ -320,56 +312,46 @@ pub async fn get_allowance(
 
 pub async fn list_allowances(
     State(AppState { db_pool }): State<AppState>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<Vec<AllowanceSummary>>, AppError> {
-    let status = params.get("status");
-    let start_date = params.get("start_date").and_then(|s| s.parse::<chrono::NaiveDate>().ok());
-    let end_date = params.get("end_date").and_then(|s| s.parse::<chrono::NaiveDate>().ok());
-
-    let mut query_str = String::from(
+    _: RequireRole<Manager>,
+    axum::extract::Query(params): axum::extract::Query<AllowanceListQuery>,
+    axum::extract::Query(page_params): axum::extract::Query<PageParams>,
+) -> Result<Json<Paginated<AllowanceSummary>>, AppError> {
+    let status = filter::one_of("status", params.status.as_deref(), &["pending", "allocated", "finalized"])?;
+    let sort = Sort::parse(
+        page_params.sort.as_deref(),
+        &[
+            ("allowance_date", "allowance_date"),
+            ("total_allowance", "total_allowance"),
+            ("status", "status"),
+        ],
+        "-allowance_date",
+        "id",
+    )?;
+    let page = Page::from_params(&page_params)?;
+
+    let mut filter = ListFilter::new(
         r#"SELECT 
             id, allowance_date, 
-            (total_allowance)::FLOAT8 as total_allowance,
-            (allocated_amount)::FLOAT8 as allocated_amount,
-            (total_allowance - allocated_amount)::FLOAT8 as remaining_amount,
+            total_allowance,
+            allocated_amount,
+            (total_allowance - allocated_amount) as remaining_amount,
             status,
             (truck_count)::INT as truck_count,
             created_by_username
-        FROM allowance_summary
-        WHERE 1=1"#
+        FROM allowance_summary"#
     );
 
-    if status.is_some() {
-        query_str.push_str(" AND status = $1");
-    }
-    if start_date.is_some() {
-        let param_num = if status.is_some() { 2 } else { 1 };
-        query_str.push_str(&format!(" AND allowance_date >= ${}", param_num));
-    }
-    if end_date.is_some() {
-        let param_num = if status.is_some() && start_date.is_some() { 3 }
-                       else if status.is_some() || start_date.is_some() { 2 }
-                       else { 1 };
-        query_str.push_str(&format!(" AND allowance_date <= ${}", param_num));
-    }
+    filter
+        .eq("status", status)
+        .gte("allowance_date", params.start_date)
+        .lte("allowance_date", params.end_date);
 
-    query_str.push_str(" ORDER BY allowance_date DESC");
-
-    let mut query = sqlx::query_as::<_, (i64, chrono::NaiveDate, f64, f64, f64, String, i32, String)>(&query_str);
-
-    if let Some(s) = status {
-        query = query.bind(s);
-    }
-    if let Some(d) = start_date {
-        query = query.bind(d);
-    }
-    if let Some(d) = end_date {
-        query = query.bind(d);
-    }
-
-    let allowances = query.fetch_all(&db_pool).await?;
+    let (allowances, total) = filter::fetch_page::<(i64, chrono::NaiveDate, Decimal, Decimal, Decimal, String, i32, String)>(
+        &db_pool, &filter, "", &sort, &page,
+    )
+    .await?;
 
-    Ok(Json(
+    Ok(Json(page.envelope(
         allowances
             .into_iter()
             .map(|(id, allowance_date, total_allowance, allocated_amount, remaining_amount, status, truck_count, created_by_username)| {
@@ -385,18 +367,15 @@ pub async fn list_allowances(
                 }
             })
             .collect(),
-    ))
+        total,
+    )))
 }
 
 pub async fn delete_allowance(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    _: RequireRole<Manager>,
     axum::extract::Path(id): axum::extract::Path<i64>,
 ) -> Result<StatusCode, AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden("Only managers can delete allowances"));
-    }
-
     let result = sqlx::query!(
         r#"DELETE FROM transport_allowances
         WHERE id = $1 AND status = 'pending'
@@ -422,8 +401,8 @@ async fn fetch_allowance_by_id(
     let allowance = sqlx::query!(
         r#"SELECT 
             ta.id, ta.allowance_date,
-            (ta.total_allowance)::FLOAT8 as "total_allowance!",
-            (ta.allocated_amount)::FLOAT8 as "allocated_amount!",
+            ta.total_allowance as "total_allowance!",
+            ta.allocated_amount as "allocated_amount!",
             ta.status, ta.notes, ta.created_at, ta.updated_at,
             u.username as "created_by_username!"
         FROM transport_allowances ta
@@ -439,11 +418,11 @@ async fn fetch_allowance_by_id(
     let allocations_data = sqlx::query!(
         r#"SELECT 
             tka.id, tka.truck_id,
-            (tka.amount)::FLOAT8 as "amount!",
+            tka.amount as "amount!",
             (tka.distance_covered)::FLOAT8 as distance_covered,
             tka.notes, tka.created_at,
             t.truck_number,
-            (t.max_allowance_limit)::FLOAT8 as "max_allowance_limit!",
+            t.max_allowance_limit as "max_allowance_limit!",
             u.username as "driver_username?"
         FROM truck_allowances tka
         JOIN trucks t ON tka.truck_id = t.id
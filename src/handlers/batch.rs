@@ -1,6 +1,8 @@
 use axum::{extract::{State, Path, Query}, Json};
 use serde::Deserialize;
 use sqlx::Row;
+use crate::database::filter::{self, ListFilter, Page, Sort};
+use crate::dtos::pagination::{PageParams, Paginated};
 use crate::state::AppState;
 use crate::error::AppError;
 use crate::dtos::batch::{BatchResponse, BatchListItem};
@@ -14,8 +16,21 @@ pub struct BatchQueryParams {
 pub async fn list_batches(
     State(AppState { db_pool }): State<AppState>,
     Query(params): Query<BatchQueryParams>,
-) -> Result<Json<Vec<BatchListItem>>, AppError> {
-    let mut query = String::from(
+    Query(page_params): Query<PageParams>,
+) -> Result<Json<Paginated<BatchListItem>>, AppError> {
+    let sort = Sort::parse(
+        page_params.sort.as_deref(),
+        &[
+            ("expiry_date", "b.expiry_date"),
+            ("batch_number", "b.batch_number"),
+            ("remaining_quantity", "b.remaining_quantity"),
+            ("created_at", "b.created_at"),
+        ],
+        "expiry_date",
+        "b.id",
+    )?;
+    let page = Page::from_params(&page_params)?;
+    let mut filter = ListFilter::new(
         r#"SELECT 
             b.id, b.batch_number, b.product_id, p.name as product_name,
             b.quantity as initial_quantity, b.remaining_quantity, b.expiry_date,
@@ -25,26 +40,26 @@ pub async fn list_batches(
                 ELSE 'available'
             END as status
         FROM batches b
-        JOIN products p ON b.product_id = p.id
-        WHERE 1=1"#
+        JOIN products p ON b.product_id = p.id"#
     );
 
-    if let Some(product_id) = params.product_id {
-        query.push_str(&format!(" AND b.product_id = {}", product_id));
-    }
+    filter.eq("b.product_id", params.product_id);
 
     if let Some(status) = &params.status {
         match status.as_str() {
-            "available" => query.push_str(" AND b.remaining_quantity > 0 AND b.expiry_date >= CURRENT_DATE"),
-            "empty" => query.push_str(" AND b.remaining_quantity = 0"),
-            "expired" => query.push_str(" AND b.expiry_date < CURRENT_DATE"),
+            "available" => filter.condition("b.remaining_quantity > 0 AND b.expiry_date >= CURRENT_DATE"),
+            "empty" => filter.condition("b.remaining_quantity = 0"),
+            "expired" => filter.condition("b.expiry_date < CURRENT_DATE"),
             _ => return Err(AppError::validation("Invalid status. Use: available, empty, or expired")),
-        }
+        };
     }
 
-    query.push_str(" ORDER BY b.expiry_date ASC, b.created_at ASC");
-
-    let rows = sqlx::query(&query).fetch_all(&db_pool).await?;
+    let rows = filter
+        .page("", &sort, &page)
+        .build()
+        .fetch_all(&db_pool)
+        .await?;
+    let total = filter::count_rows(&db_pool, &filter, "").await?;
 
     let batches: Vec<BatchListItem> = rows.iter().map(|row| {
         use sqlx::Row;
@@ -60,7 +75,7 @@ pub async fn list_batches(
         }
     }).collect();
 
-    Ok(Json(batches))
+    Ok(Json(page.envelope(batches, total)))
 }
 
 pub async fn get_batch(
@@ -1,220 +1,707 @@
 use crate::dtos::delivery::{
-    CreateDeliveryRequest, DeliveryBatchResponse, DeliveryItemResponse, DeliveryResponse,
-    DeliverySummary, NewDeliveryItem, UpdateDeliveryRequest,
+    CorrectDeliveryBatchRequest, CreateDeliveryRequest, DeliveryBatchResponse, DeliveryClaimSummary,
+    DeliveryDiscrepancy, DeliveryItemResponse, DeliveryListQuery, DeliveryResponse,
+    DeliveryReversalSummary, DeliverySummary, NewDeliveryBatch, NewDeliveryItem,
+    UpdateDeliveryItemRequest, UpdateDeliveryRequest,
 };
+use crate::database::filter::{self, ListFilter, Page, Sort};
+use crate::dtos::pagination::{PageParams, Paginated};
 use crate::error::AppError;
-use crate::middleware::auth::AuthContext;
+use crate::handlers::purchase_order::lock_open_purchase_order;
+use crate::handlers::supplier::{ensure_active_supplier, supplier_cost_price};
+use crate::auth::role::Manager;
+use crate::middleware::auth::RequireRole;
+use crate::money;
 use crate::state::AppState;
-use axum::extract::Extension;
 use axum::http::StatusCode;
 use axum::{extract::State, Json};
-use chrono::NaiveDate;
 
 pub async fn create_delivery(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
     Json(req): Json<CreateDeliveryRequest>,
 ) -> Result<(StatusCode, Json<DeliveryResponse>), AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden("Only managers can create deliveries"));
-    }
     if req.items.is_empty() {
         return Err(AppError::validation("Delivery must have at least one item"));
     }
-
     for item in &req.items {
-        if item.unit_price < 0.0 {
+        validate_item(item)?;
+    }
+
+    let mut tx = db_pool.begin().await?;
+
+    let order_supplier_id = match req.purchase_order_id {
+        Some(purchase_order_id) => Some(lock_open_purchase_order(&mut tx, purchase_order_id).await?),
+        None => None,
+    };
+    let supplier_id = match (req.supplier_id, order_supplier_id) {
+        (Some(supplier_id), Some(order_supplier_id)) if supplier_id != order_supplier_id => {
             return Err(AppError::validation(
-                "unit_price must be greater than or equal to 0",
+                "supplier_id does not match the purchase order's supplier",
             ));
         }
-        if item.batches.is_empty() {
+        (Some(supplier_id), _) | (None, Some(supplier_id)) => supplier_id,
+        (None, None) => return Err(AppError::validation("supplier_id is required")),
+    };
+    ensure_active_supplier(&mut tx, supplier_id).await?;
+
+    let delivery_id = sqlx::query_scalar!(
+        r#"INSERT INTO deliveries (delivery_date, received_by, delivery_note_number, supplier_id, purchase_order_id) VALUES ($1,$2,$3,$4,$5)
+        RETURNING id"#,
+        req.delivery_date,
+        req.received_by,
+        req.delivery_note_number,
+        supplier_id,
+        req.purchase_order_id
+    ).fetch_one(&mut *tx).await?;
+
+    for item in &req.items {
+        receive_item(&mut tx, delivery_id, supplier_id, item, auth.user_id).await?;
+    }
+
+    sync_supplier_claim(&mut tx, delivery_id, supplier_id).await?;
+
+    tx.commit().await?;
+
+    let delivery = fetch_delivery(&db_pool, delivery_id).await?;
+    Ok((StatusCode::CREATED, Json(delivery)))
+}
+
+pub async fn get_delivery(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> Result<Json<DeliveryResponse>, AppError> {
+    fetch_delivery(&db_pool, id).await.map(Json)
+}
+
+pub async fn list_deliveries(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    axum::extract::Query(params): axum::extract::Query<DeliveryListQuery>,
+    axum::extract::Query(page_params): axum::extract::Query<PageParams>,
+) -> Result<Json<Paginated<DeliverySummary>>, AppError> {
+    let sort = Sort::parse(
+        page_params.sort.as_deref(),
+        &[("delivery_date", "d.delivery_date"), ("delivery_note_number", "d.delivery_note_number")],
+        "-delivery_date",
+        "d.id",
+    )?;
+    let page = Page::from_params(&page_params)?;
+
+    let mut filter = ListFilter::new(
+        r#"SELECT d.id, d.delivery_date, d.delivery_note_number, d.received_by, d.supplier_id, d.purchase_order_id,
+                  COUNT(di.id)::BIGINT as total_items
+            FROM deliveries d LEFT JOIN delivery_items di ON di.delivery_id = d.id"#,
+    );
+    filter
+        .eq("d.supplier_id", params.supplier_id)
+        .eq("d.purchase_order_id", params.purchase_order_id);
+    let (rows, total) = filter::fetch_page::<(i64, chrono::NaiveDate, String, Option<i64>, i64, Option<i64>, i64)>(
+        &db_pool,
+        &filter,
+        "GROUP BY d.id, d.delivery_date, d.delivery_note_number, d.received_by, d.supplier_id, d.purchase_order_id",
+        &sort,
+        &page,
+    )
+    .await?;
+
+    Ok(Json(page.envelope(
+        rows.into_iter()
+            .map(
+                |(id, delivery_date, delivery_note_number, received_by, supplier_id, purchase_order_id, total_items)| {
+                    DeliverySummary {
+                        id,
+                        delivery_date,
+                        delivery_note_number,
+                        received_by,
+                        supplier_id,
+                        purchase_order_id,
+                        total_items,
+                    }
+                },
+            )
+            .collect(),
+        total,
+    )))
+}
+
+pub async fn update_delivery(
+    State(AppState { db_pool }): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(req): Json<UpdateDeliveryRequest>,
+) -> Result<Json<DeliveryResponse>, AppError> {
+    let mut tx = db_pool.begin().await?;
+    let supplier_id = lock_delivery(&mut tx, id).await?;
+    let current_date = sqlx::query_scalar!("SELECT delivery_date FROM deliveries WHERE id = $1", id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    if let Some(purchase_order_id) = req.purchase_order_id {
+        let order_supplier_id = lock_open_purchase_order(&mut tx, purchase_order_id).await?;
+        if supplier_id != order_supplier_id {
             return Err(AppError::validation(
-                "Each item must have at least one batch",
+                "Purchase order is for a different supplier than the delivery",
             ));
         }
-        for b in &item.batches {
-            if b.quantity <= 0 {
-                return Err(AppError::validation("Batch quantity must be > 0"));
+    }
+
+    // Moving the receipts later must not put them after stock already left their
+    // batches, or the ledger would show those batches negative in between
+    if let Some(delivery_date) = req.delivery_date {
+        let first_out = sqlx::query!(
+            r#"SELECT b.batch_number, sm.movement_date
+               FROM deliveries d
+               JOIN batch_receipts br ON br.delivery_id = d.id
+               JOIN batches b ON b.id = br.batch_id
+               JOIN stock_movements sm ON sm.batch_id = br.batch_id
+               WHERE d.id = $1
+                 AND sm.reference_type NOT IN ('delivery', 'delivery_correction')
+                 AND sm.movement_date >= d.delivery_date
+               ORDER BY sm.movement_date, sm.id
+               LIMIT 1"#,
+            id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(out) = first_out.filter(|out| out.movement_date < delivery_date) {
+            return Err(AppError::conflict(format!(
+                "Batch {} has stock movements from {}; delivery_date cannot be later than that",
+                out.batch_number, out.movement_date
+            )));
+        }
+    }
+
+    sqlx::query!(
+        r#"UPDATE deliveries SET delivery_date = COALESCE($2, delivery_date),
+                        received_by = COALESCE($3::BIGINT, received_by),
+                        delivery_note_number = COALESCE($4, delivery_note_number),
+                        purchase_order_id = COALESCE($5::BIGINT, purchase_order_id)
+                        WHERE id = $1"#,
+        id,
+        req.delivery_date,
+        req.received_by.flatten(),
+        req.delivery_note_number,
+        req.purchase_order_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Receipts and corrections stay on the old date; a reversing pair of corrections
+    // moves what the delivery put into each batch to the new one
+    if let Some(delivery_date) = req.delivery_date.filter(|date| *date != current_date) {
+        let received = sqlx::query!(
+            r#"SELECT sm.batch_id, sm.product_id, b.batch_number,
+                      SUM(stock_movement_effect(sm.movement_type, sm.reference_type, sm.quantity))::INT as "quantity!"
+               FROM stock_movements sm
+               JOIN batches b ON b.id = sm.batch_id
+               WHERE sm.reference_type IN ('delivery', 'delivery_correction')
+                 AND sm.reference_id = $1
+                 AND sm.movement_date = $2
+               GROUP BY sm.batch_id, sm.product_id, b.batch_number
+               HAVING SUM(stock_movement_effect(sm.movement_type, sm.reference_type, sm.quantity)) <> 0
+               ORDER BY sm.batch_id"#,
+            id as i32,
+            current_date
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for r in received {
+            for (quantity, movement_date) in [(-r.quantity, current_date), (r.quantity, delivery_date)] {
+                sqlx::query!(
+                    r#"INSERT INTO stock_movements
+                       (batch_id, product_id, movement_type, quantity, reference_type, reference_id, notes, created_by, movement_date)
+                       VALUES ($1, $2, 'adjustment', ($3)::FLOAT8::NUMERIC, 'delivery_correction', $4, $5, $6, $7)"#,
+                    r.batch_id,
+                    r.product_id,
+                    quantity as f64,
+                    id as i32,
+                    format!(
+                        "Delivery #{} date corrected from {} to {} - Batch: {}",
+                        id, current_date, delivery_date, r.batch_number
+                    ),
+                    auth.user_id as i32,
+                    movement_date
+                )
+                .execute(&mut *tx)
+                .await?;
             }
         }
     }
 
+    tx.commit().await?;
+
+    fetch_delivery(&db_pool, id).await.map(Json)
+}
+
+pub async fn delete_delivery(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> Result<StatusCode, AppError> {
     let mut tx = db_pool.begin().await?;
 
-    let delivery = sqlx::query!(
-        r#"INSERT INTO deliveries (delivery_date, received_by, delivery_note_number) VALUES ($1,$2,$3)
-        RETURNING id, delivery_date, received_by, delivery_note_number"#,
-        req.delivery_date,
-        req.received_by,
-        req.delivery_note_number
-    ).fetch_one(&mut *tx).await?;
+    // Deleting erases the delivery's history; a reversed delivery keeps it
+    let reversed = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM delivery_reversals WHERE delivery_id = $1) as "exists!""#,
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if reversed {
+        return Err(AppError::conflict(
+            "Delivery has been reversed and its history is kept",
+        ));
+    }
+
+    // The claim goes with the delivery; one the supplier has settled or rejected stays
+    let claim_status = sqlx::query_scalar!(
+        r#"SELECT status FROM supplier_claims WHERE delivery_id = $1 AND status <> 'open' FOR UPDATE"#,
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(status) = claim_status {
+        return Err(AppError::conflict(format!(
+            "Delivery has a supplier claim that is already {status}"
+        )));
+    }
 
-    let mut items_out: Vec<DeliveryItemResponse> = Vec::with_capacity(req.items.len());
+    // Batches this delivery created go with it, so no other delivery may hold stock in them
+    let shared = sqlx::query!(
+        r#"SELECT b.batch_number, br.delivery_id FROM batches b
+           JOIN batch_receipts br ON br.batch_id = b.id AND br.delivery_id <> b.delivery_id
+           WHERE b.delivery_id = $1
+           LIMIT 1"#,
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(shared) = shared {
+        return Err(AppError::conflict(format!(
+            "Batch {} also holds stock received by delivery {}",
+            shared.batch_number, shared.delivery_id
+        )));
+    }
 
-    for item in &req.items {
-        let total_qty: i32 = item.batches.iter().map(|b| b.quantity).sum();
-        let item_row = sqlx::query!(
-            r#"INSERT INTO delivery_items (delivery_id, product_id, quantity, unit_price)
-            VALUES ($1,$2,$3,$4::FLOAT8)
-            RETURNING id, product_id, quantity, unit_price::FLOAT8 as "unit_price!""#,
-            delivery.id,
-            item.product_id,
-            total_qty,
-            item.unit_price
-        )
-        .fetch_one(&mut *tx)
+    // Only a delivery none of whose stock has moved (truck loads, sales,
+    // adjustments) can be erased; anything else is reversed instead
+    let moved = sqlx::query_scalar!(
+        r#"SELECT b.batch_number FROM batch_receipts br
+           JOIN batches b ON b.id = br.batch_id
+           WHERE br.delivery_id = $1
+             AND (b.remaining_quantity < br.quantity
+                  OR (b.delivery_id = $1 AND EXISTS (
+                      SELECT 1 FROM stock_movements sm
+                      WHERE sm.batch_id = br.batch_id
+                        AND NOT (sm.reference_type IN ('delivery', 'delivery_correction') AND sm.reference_id = $2)
+                  )))
+           LIMIT 1"#,
+        id,
+        id as i32
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(batch_number) = moved {
+        return Err(AppError::conflict(format!(
+            "Stock of batch {batch_number} has moved since it was received; reverse the delivery instead"
+        )));
+    }
+
+    let receipts = sqlx::query!(
+        r#"DELETE FROM batch_receipts WHERE delivery_id = $1 RETURNING batch_id, quantity"#,
+        id
+    ).fetch_all(&mut *tx).await?;
+
+    // Take each receipt back out of its batch
+    for r in receipts {
+        sqlx::query!(
+            r#"UPDATE batches SET quantity = quantity - $1, remaining_quantity = remaining_quantity - $1
+               WHERE id = $2"#,
+            r.quantity,
+            r.batch_id
+        ).execute(&mut *tx).await?;
+    }
+
+    // Delete stock_movements for this delivery
+    sqlx::query!(
+        "DELETE FROM stock_movements WHERE reference_type IN ('delivery', 'delivery_correction') AND reference_id = $1",
+        id as i32
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Batches created by this delivery are now empty
+    sqlx::query!("DELETE FROM batches WHERE delivery_id = $1", id)
+        .execute(&mut *tx)
         .await
         .map_err(|e| {
-            if let Some(db) = e.as_database_error() {
-                if db.code().as_deref() == Some("23503") {
-                    return AppError::validation("Invalid product_id or received_by");
-                }
-                if db.code().as_deref() == Some("23505") {
-                    return AppError::conflict("Product already exists in delivery");
-                }
+            if e.as_database_error().and_then(|db| db.code()).as_deref() == Some("23503") {
+                return AppError::conflict(
+                    "A batch of this delivery is referenced by other records; reverse the delivery instead",
+                );
             }
             AppError::db(e)
         })?;
 
-        let mut batches_out = Vec::with_capacity(item.batches.len());
-        for b in &item.batches {
-            // Try to find existing batch by (product_id, batch_number)
-            let existing = sqlx::query!(
-                r#"SELECT id, expiry_date, quantity, remaining_quantity FROM batches 
-                WHERE product_id = $1 AND batch_number = $2"#,
-                item.product_id,
-                b.batch_number
-            ).fetch_optional(&mut *tx).await?;
-
-            let (batch_id, remaining_qty) = match existing {
-                Some(ex) => {
-                    // Validate expiry date matches
-                    if ex.expiry_date != b.expiry_date {
-                        return Err(AppError::validation(format!(
-                            "Batch {} already exists with different expiry date",
-                            b.batch_number
-                        )));
-                    }
-                    // Update batch quantities
-                    let new_qty = ex.quantity + b.quantity;
-                    let new_remaining = ex.remaining_quantity + b.quantity;
-                    sqlx::query!(
-                        r#"UPDATE batches 
-                        SET quantity = $1, 
-                        remaining_quantity = $2 
-                        WHERE id = $3"#,
-                        new_qty,
-                        new_remaining,
-                        ex.id
-                    ).execute(&mut *tx).await?;
-                    (ex.id, new_remaining)
-                }
-                None => {
-                    // Create new batch
-                    let new_batch = sqlx::query!(
-                        r#"INSERT INTO batches (product_id, delivery_id, delivery_item_id, batch_number, quantity, remaining_quantity, expiry_date)
-                        VALUES ($1,$2,$3,$4,$5,$5,$6)
-                        RETURNING id, remaining_quantity"#,
-                        item.product_id,
-                        delivery.id,
-                        item_row.id,
-                        b.batch_number,
-                        b.quantity,
-                        b.expiry_date
-                    ).fetch_one(&mut *tx).await.map_err(|e| {
-                        if let Some(db) = e.as_database_error() {
-                            if db.code().as_deref() == Some("23505") { return AppError::conflict("Batch number exists for product"); }
-                            if db.code().as_deref() == Some("23503") { return AppError::validation("Invalid delivery/product reference"); }
-                        }
-                        AppError::db(e)
-                    })?;
-                    (new_batch.id, new_batch.remaining_quantity)
-                }
-            };
-
-            // Stock movement is automatically logged by trigger_log_batch_delivery
-            // No manual insert needed here
-
-            batches_out.push(DeliveryBatchResponse {
-                id: batch_id,
-                batch_number: b.batch_number.clone(),
-                quantity: b.quantity,
-                remaining_quantity: remaining_qty,
-                expiry_date: b.expiry_date,
-            });
-        }
+    // Delete delivery_items
+    sqlx::query!("DELETE FROM delivery_items WHERE delivery_id = $1", id)
+        .execute(&mut *tx)
+        .await?;
 
-        items_out.push(DeliveryItemResponse {
-            id: item_row.id,
-            product_id: item_row.product_id,
-            quantity: item_row.quantity,
-            unit_price: item_row.unit_price,
-            batches: batches_out,
-        });
-    }
+    // Delete delivery
+    let res = sqlx::query!("DELETE FROM deliveries WHERE id = $1", id)
+        .execute(&mut *tx)
+        .await?;
 
     tx.commit().await?;
-    Ok((
-        StatusCode::CREATED,
-        Json(DeliveryResponse {
-            id: delivery.id,
-            delivery_date: delivery.delivery_date,
-            received_by: delivery.received_by,
-            delivery_note_number: delivery.delivery_note_number,
-            items: items_out,
-        }),
-    ))
+    if res.rows_affected() == 0 {
+        return Err(AppError::not_found("Delivery not found"));
+    }
+    Ok(StatusCode::NO_CONTENT)
 }
 
-pub async fn get_delivery(
+// ==================== Line Corrections ====================
+//
+// Received stock is corrected, never rewritten: quantity changes post an
+// `adjustment` movement of reference type `delivery_correction` referencing the
+// delivery, so the batch ledger keeps the original receipt and every correction.
+// A correction is refused only when the batch no longer has the units in the
+// warehouse to take back.
+
+// Add a line that was left off when the delivery was recorded
+pub async fn add_delivery_item(
     State(AppState { db_pool }): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
     axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(item): Json<NewDeliveryItem>,
+) -> Result<(StatusCode, Json<DeliveryResponse>), AppError> {
+    validate_item(&item)?;
+
+    let mut tx = db_pool.begin().await?;
+    let supplier_id = lock_delivery(&mut tx, id).await?;
+
+    receive_item(&mut tx, id, supplier_id, &item, auth.user_id).await?;
+    sync_supplier_claim(&mut tx, id, supplier_id).await?;
+
+    tx.commit().await?;
+
+    let delivery = fetch_delivery(&db_pool, id).await?;
+    Ok((StatusCode::CREATED, Json(delivery)))
+}
+
+// Correct the price, note quantity or damaged units of a line
+pub async fn update_delivery_item(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    axum::extract::Path((id, item_id)): axum::extract::Path<(i64, i64)>,
+    Json(req): Json<UpdateDeliveryItemRequest>,
+) -> Result<Json<DeliveryResponse>, AppError> {
+    if req.unit_price.is_some_and(|price| price.is_sign_negative()) {
+        return Err(AppError::validation(
+            "unit_price must be greater than or equal to 0",
+        ));
+    }
+    if req.damaged_quantity.is_some_and(|q| q < 0) || req.note_quantity.is_some_and(|q| q < 0) {
+        return Err(AppError::validation(
+            "note_quantity and damaged_quantity cannot be negative",
+        ));
+    }
+
+    let mut tx = db_pool.begin().await?;
+    let supplier_id = lock_delivery(&mut tx, id).await?;
+    lock_delivery_item(&mut tx, id, item_id).await?;
+
+    let line = sqlx::query!(
+        r#"SELECT damaged_quantity, damage_reason FROM delivery_items WHERE id = $1"#,
+        item_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let damaged_qty = req.damaged_quantity.unwrap_or(line.damaged_quantity);
+    let damage_reason = match req.damage_reason.as_deref().map(str::trim) {
+        Some(reason) if !reason.is_empty() => Some(reason.to_string()),
+        _ => line.damage_reason,
+    }
+    .filter(|_| damaged_qty > 0);
+    if damaged_qty > 0 && damage_reason.is_none() {
+        return Err(AppError::validation(
+            "damage_reason is required when damaged_quantity > 0",
+        ));
+    }
+
+    sqlx::query!(
+        r#"UPDATE delivery_items
+           SET unit_price = COALESCE($2, unit_price),
+               note_quantity = COALESCE($3, note_quantity),
+               damaged_quantity = $4,
+               damage_reason = $5,
+               received_quantity = quantity + $4
+           WHERE id = $1"#,
+        item_id,
+        req.unit_price.map(money::normalize),
+        req.note_quantity,
+        damaged_qty,
+        damage_reason
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sync_supplier_claim(&mut tx, id, supplier_id).await?;
+
+    tx.commit().await?;
+
+    fetch_delivery(&db_pool, id).await.map(Json)
+}
+
+// Remove a line recorded in error, taking its stock back out of the warehouse.
+// Batches the line created stay, emptied, with their ledger.
+pub async fn delete_delivery_item(
+    State(AppState { db_pool }): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
+    axum::extract::Path((id, item_id)): axum::extract::Path<(i64, i64)>,
+) -> Result<Json<DeliveryResponse>, AppError> {
+    let mut tx = db_pool.begin().await?;
+    let supplier_id = lock_delivery(&mut tx, id).await?;
+    lock_delivery_item(&mut tx, id, item_id).await?;
+
+    let other_lines = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) as "count!" FROM delivery_items WHERE delivery_id = $1 AND id <> $2"#,
+        id,
+        item_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if other_lines == 0 {
+        return Err(AppError::conflict(
+            "Delivery must keep at least one item; delete the delivery instead",
+        ));
+    }
+
+    let claimed = sqlx::query_scalar!(
+        r#"SELECT c.status FROM supplier_claim_lines l
+           JOIN supplier_claims c ON l.claim_id = c.id
+           WHERE l.delivery_item_id = $1 AND c.status <> 'open'
+           LIMIT 1"#,
+        item_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(status) = claimed {
+        return Err(AppError::conflict(format!(
+            "Line is on a supplier claim that is already {status}"
+        )));
+    }
+
+    // Everything the line put into a batch is taken back with a correction, whether
+    // the line created the batch or topped it up
+    let receipts = sqlx::query!(
+        r#"SELECT batch_id, quantity FROM batch_receipts WHERE delivery_item_id = $1 ORDER BY batch_id"#,
+        item_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for r in receipts.iter().filter(|r| r.quantity != 0) {
+        adjust_receipt(&mut tx, id, item_id, r.batch_id, -r.quantity, auth.user_id).await?;
+    }
+    sqlx::query!("DELETE FROM batch_receipts WHERE delivery_item_id = $1", item_id)
+        .execute(&mut *tx)
+        .await?;
+
+    // Batches the line created lose their link to it (ON DELETE SET NULL)
+    sqlx::query!("DELETE FROM delivery_items WHERE id = $1", item_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sync_supplier_claim(&mut tx, id, supplier_id).await?;
+
+    tx.commit().await?;
+
+    fetch_delivery(&db_pool, id).await.map(Json)
+}
+
+// Receive another batch on a line (new batch or a top-up of an existing one)
+pub async fn add_delivery_batch(
+    State(AppState { db_pool }): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
+    axum::extract::Path((id, item_id)): axum::extract::Path<(i64, i64)>,
+    Json(batch): Json<NewDeliveryBatch>,
+) -> Result<(StatusCode, Json<DeliveryResponse>), AppError> {
+    if batch.quantity <= 0 {
+        return Err(AppError::validation("Batch quantity must be > 0"));
+    }
+
+    let mut tx = db_pool.begin().await?;
+    let supplier_id = lock_delivery(&mut tx, id).await?;
+    let product_id = lock_delivery_item(&mut tx, id, item_id).await?;
+
+    let existing = sqlx::query_scalar!(
+        r#"SELECT id FROM batches WHERE product_id = $1 AND batch_number = $2"#,
+        product_id,
+        batch.batch_number
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(batch_id) = existing {
+        if receipt_quantity(&mut tx, item_id, batch_id).await?.is_some_and(|q| q > 0) {
+            return Err(AppError::conflict(format!(
+                "Batch {} is already received on this delivery; correct its quantity instead",
+                batch.batch_number
+            )));
+        }
+    }
+
+    receive_batch(&mut tx, id, item_id, product_id, &batch, auth.user_id).await?;
+
+    sqlx::query!(
+        r#"UPDATE delivery_items SET quantity = quantity + $2, received_quantity = received_quantity + $2 WHERE id = $1"#,
+        item_id,
+        batch.quantity
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sync_supplier_claim(&mut tx, id, supplier_id).await?;
+
+    tx.commit().await?;
+
+    let delivery = fetch_delivery(&db_pool, id).await?;
+    Ok((StatusCode::CREATED, Json(delivery)))
+}
+
+// Correct how much of a batch the line received, or the batch's expiry/number
+pub async fn correct_delivery_batch(
+    State(AppState { db_pool }): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
+    axum::extract::Path((id, item_id, batch_id)): axum::extract::Path<(i64, i64, i64)>,
+    Json(req): Json<CorrectDeliveryBatchRequest>,
 ) -> Result<Json<DeliveryResponse>, AppError> {
+    if req.quantity.is_some_and(|q| q < 0) {
+        return Err(AppError::validation("Batch quantity cannot be negative"));
+    }
+    if req.batch_number.as_deref().is_some_and(|n| n.trim().is_empty()) {
+        return Err(AppError::validation("batch_number cannot be empty"));
+    }
+
+    let mut tx = db_pool.begin().await?;
+    let supplier_id = lock_delivery(&mut tx, id).await?;
+    lock_delivery_item(&mut tx, id, item_id).await?;
+
+    let batch_delivery_id = sqlx::query_scalar!(
+        r#"SELECT delivery_id FROM batches WHERE id = $1 FOR UPDATE"#,
+        batch_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::not_found("Batch not found on this delivery line"))?;
+
+    let received = receipt_quantity(&mut tx, item_id, batch_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Batch not found on this delivery line"))?;
+
+    if let Some(quantity) = req.quantity {
+        let delta = quantity - received;
+        if delta != 0 {
+            adjust_receipt(&mut tx, id, item_id, batch_id, delta, auth.user_id).await?;
+            sqlx::query!(
+                r#"UPDATE delivery_items SET quantity = quantity + $2, received_quantity = received_quantity + $2 WHERE id = $1"#,
+                item_id,
+                delta
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    if req.expiry_date.is_some() || req.batch_number.is_some() {
+        // A topped-up batch belongs to the delivery that created it
+        if batch_delivery_id != id {
+            return Err(AppError::conflict(format!(
+                "Batch was created by delivery {batch_delivery_id}; correct its expiry or number there"
+            )));
+        }
+        sqlx::query!(
+            r#"UPDATE batches SET expiry_date = COALESCE($2, expiry_date), batch_number = COALESCE($3, batch_number) WHERE id = $1"#,
+            batch_id,
+            req.expiry_date,
+            req.batch_number.as_deref().map(str::trim)
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            if e.as_database_error().and_then(|db| db.code()).as_deref() == Some("23505") {
+                return AppError::conflict("Batch number exists for product");
+            }
+            AppError::db(e)
+        })?;
+    }
+
+    sync_supplier_claim(&mut tx, id, supplier_id).await?;
+
+    tx.commit().await?;
+
+    fetch_delivery(&db_pool, id).await.map(Json)
+}
+
+// ==================== Helper Functions ====================
+
+/// Delivery with its lines, the batches each line was received into and the
+/// receiving discrepancy summary.
+async fn fetch_delivery(db_pool: &sqlx::PgPool, id: i64) -> Result<DeliveryResponse, AppError> {
     let d = sqlx::query!(
-        r#"SELECT id, delivery_date, received_by, delivery_note_number FROM deliveries WHERE id = $1"#,
+        r#"SELECT id, delivery_date, received_by, delivery_note_number, supplier_id, purchase_order_id FROM deliveries WHERE id = $1"#,
         id
-    ).fetch_optional(&db_pool).await?.ok_or_else(|| AppError::not_found("Delivery not found"))?;
+    ).fetch_optional(db_pool).await?.ok_or_else(|| AppError::not_found("Delivery not found"))?;
 
     let items = sqlx::query!(
-        r#"SELECT id, product_id, quantity, unit_price::FLOAT8 as "unit_price!" FROM delivery_items WHERE delivery_id = $1 ORDER BY id"#,
+        r#"SELECT id, product_id, quantity, unit_price, note_quantity, received_quantity, damaged_quantity, damage_reason
+           FROM delivery_items WHERE delivery_id = $1 ORDER BY id"#,
         id
-    ).fetch_all(&db_pool).await?;
+    ).fetch_all(db_pool).await?;
 
     let mut items_out = Vec::with_capacity(items.len());
     for it in items {
-        // Fetch batches created from this delivery_item via stock_movements
+        // Batches this line received into, including top-ups of older batches
         let receipt_batches = sqlx::query!(
             r#"SELECT 
-                sm.batch_id, 
-                (sm.quantity)::INT as "receipt_qty!", 
+                br.batch_id, 
+                br.quantity as receipt_qty, 
+                COALESCE((
+                    SELECT SUM(rl.quantity) FROM delivery_reversal_lines rl
+                    WHERE rl.delivery_item_id = br.delivery_item_id AND rl.batch_id = br.batch_id
+                ), 0)::INT as "reversed_qty!",
                 b.batch_number, 
                 b.remaining_quantity, 
                 b.expiry_date
-               FROM stock_movements sm
-               JOIN batches b ON b.id = sm.batch_id
-               WHERE sm.reference_type = 'delivery' 
-                 AND sm.reference_id = $1
-                 AND sm.movement_type = 'delivery_in'
-                 AND b.product_id = $2
+               FROM batch_receipts br
+               JOIN batches b ON b.id = br.batch_id
+               WHERE br.delivery_item_id = $1
                ORDER BY b.expiry_date ASC, b.id ASC"#,
-            d.id as i32,
-            it.product_id
+            it.id
         )
-        .fetch_all(&db_pool)
+        .fetch_all(db_pool)
         .await?;
 
         items_out.push(DeliveryItemResponse {
             id: it.id,
             product_id: it.product_id,
             quantity: it.quantity,
+            note_quantity: it.note_quantity,
+            received_quantity: it.received_quantity,
+            damaged_quantity: it.damaged_quantity,
+            damage_reason: it.damage_reason,
             unit_price: it.unit_price,
             batches: receipt_batches
                 .into_iter()
                 .map(|rb| DeliveryBatchResponse {
-                    id: rb.batch_id as i64,
+                    id: rb.batch_id,
                     batch_number: rb.batch_number,
                     quantity: rb.receipt_qty,
+                    reversed_quantity: rb.reversed_qty,
                     remaining_quantity: rb.remaining_quantity,
                     expiry_date: rb.expiry_date,
                 })
@@ -222,193 +709,461 @@ pub async fn get_delivery(
         });
     }
 
-    Ok(Json(DeliveryResponse {
+    let claim = sqlx::query_as!(
+        DeliveryClaimSummary,
+        r#"SELECT id, status, total_value FROM supplier_claims WHERE delivery_id = $1"#,
+        id
+    )
+    .fetch_optional(db_pool)
+    .await?;
+
+    let reversals = sqlx::query_as!(
+        DeliveryReversalSummary,
+        r#"SELECT r.id, r.warehouse_only, COALESCE(SUM(l.quantity), 0)::INT as "quantity!",
+                  r.notes, r.reversed_by, r.reversed_at
+           FROM delivery_reversals r
+           LEFT JOIN delivery_reversal_lines l ON l.reversal_id = r.id
+           WHERE r.delivery_id = $1
+           GROUP BY r.id
+           ORDER BY r.id"#,
+        id
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    Ok(DeliveryResponse {
         id: d.id,
         delivery_date: d.delivery_date,
         received_by: d.received_by,
         delivery_note_number: d.delivery_note_number,
+        supplier_id: d.supplier_id,
+        purchase_order_id: d.purchase_order_id,
+        discrepancy: summarize_discrepancy(&items_out, claim),
         items: items_out,
-    }))
+        reversals,
+    })
 }
 
-pub async fn list_deliveries(
-    State(AppState { db_pool }): State<AppState>,
-) -> Result<Json<Vec<DeliverySummary>>, AppError> {
-    let rows = sqlx::query!(
-        r#"SELECT d.id, d.delivery_date, d.delivery_note_number, d.received_by, COUNT(di.id)::BIGINT as total_items
-            FROM deliveries d LEFT JOIN delivery_items di ON di.delivery_id = d.id
-            GROUP BY d.id, d.delivery_date, d.delivery_note_number, d.received_by
-            ORDER BY d.delivery_date DESC, d.id DESC"#
-    ).fetch_all(&db_pool).await?;
-
-    Ok(Json(
-        rows.into_iter()
-            .map(|r| DeliverySummary {
-                id: r.id,
-                delivery_date: r.delivery_date,
-                delivery_note_number: r.delivery_note_number,
-                received_by: r.received_by,
-                total_items: r.total_items.unwrap_or(0),
-            })
-            .collect(),
-    ))
+fn summarize_discrepancy(items: &[DeliveryItemResponse], claim: Option<DeliveryClaimSummary>) -> DeliveryDiscrepancy {
+    let short = |it: &DeliveryItemResponse| (it.note_quantity - it.received_quantity).max(0);
+    let over = |it: &DeliveryItemResponse| (it.received_quantity - it.note_quantity).max(0);
+    DeliveryDiscrepancy {
+        note_quantity: items.iter().map(|it| it.note_quantity).sum(),
+        received_quantity: items.iter().map(|it| it.received_quantity).sum(),
+        good_quantity: items.iter().map(|it| it.quantity).sum(),
+        damaged_quantity: items.iter().map(|it| it.damaged_quantity).sum(),
+        short_quantity: items.iter().map(short).sum(),
+        over_quantity: items.iter().map(over).sum(),
+        lines_with_discrepancy: items
+            .iter()
+            .filter(|it| it.damaged_quantity > 0 || it.note_quantity != it.received_quantity)
+            .count() as i32,
+        claim,
+    }
 }
 
-pub async fn update_delivery(
-    State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
-    axum::extract::Path(id): axum::extract::Path<i64>,
-    Json(req): Json<UpdateDeliveryRequest>,
-) -> Result<Json<DeliveryResponse>, AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden("Only managers can update deliveries"));
+fn validate_item(item: &NewDeliveryItem) -> Result<(), AppError> {
+    if item.unit_price.is_some_and(|price| price.is_sign_negative()) {
+        return Err(AppError::validation(
+            "unit_price must be greater than or equal to 0",
+        ));
     }
-    let row = sqlx::query!(
-        r#"UPDATE deliveries SET delivery_date = COALESCE($2, delivery_date),
-                        received_by = COALESCE($3::BIGINT, received_by),
-                        delivery_note_number = COALESCE($4, delivery_note_number)
-                        WHERE id = $1
-                        RETURNING id, delivery_date, received_by, delivery_note_number"#,
-        id,
-        req.delivery_date,
-        req.received_by.flatten(),
-        req.delivery_note_number
+    for b in &item.batches {
+        if b.quantity <= 0 {
+            return Err(AppError::validation("Batch quantity must be > 0"));
+        }
+    }
+    let damaged = item.damaged_quantity.unwrap_or(0);
+    if damaged < 0 || item.note_quantity.is_some_and(|q| q < 0) {
+        return Err(AppError::validation(
+            "note_quantity and damaged_quantity cannot be negative",
+        ));
+    }
+    if damaged > 0 && item.damage_reason.as_deref().is_none_or(|r| r.trim().is_empty()) {
+        return Err(AppError::validation(
+            "damage_reason is required when damaged_quantity > 0",
+        ));
+    }
+    if item.batches.is_empty() && damaged == 0 && item.note_quantity.unwrap_or(0) == 0 {
+        return Err(AppError::validation(
+            "Each item must have at least one batch, damaged units or a note quantity",
+        ));
+    }
+    Ok(())
+}
+
+/// Insert a delivery line and receive its batches. Returns the line id.
+async fn receive_item(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    delivery_id: i64,
+    supplier_id: i64,
+    item: &NewDeliveryItem,
+    user_id: i64,
+) -> Result<i64, AppError> {
+    let cost_price = supplier_cost_price(tx, supplier_id, item.product_id).await?;
+    let unit_price = item.unit_price.or(cost_price).ok_or_else(|| {
+        AppError::validation(format!(
+            "unit_price is required for product {} (no cost price for this supplier)",
+            item.product_id
+        ))
+    })?;
+    // Only good units become batch stock; damaged ones are claimed back
+    let good_qty: i32 = item.batches.iter().map(|b| b.quantity).sum();
+    let damaged_qty = item.damaged_quantity.unwrap_or(0);
+    let received_qty = good_qty + damaged_qty;
+    let item_id = sqlx::query_scalar!(
+        r#"INSERT INTO delivery_items
+           (delivery_id, product_id, quantity, unit_price, note_quantity, received_quantity, damaged_quantity, damage_reason)
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8)
+        RETURNING id"#,
+        delivery_id,
+        item.product_id,
+        good_qty,
+        money::normalize(unit_price),
+        item.note_quantity.unwrap_or(received_qty),
+        received_qty,
+        damaged_qty,
+        item.damage_reason.as_deref().map(str::trim).filter(|_| damaged_qty > 0)
     )
-    .fetch_optional(&db_pool)
-    .await?
-    .ok_or_else(|| AppError::not_found("Delivery not found"))?;
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| {
+        if let Some(db) = e.as_database_error() {
+            if db.code().as_deref() == Some("23503") {
+                return AppError::validation("Invalid product_id or received_by");
+            }
+            if db.code().as_deref() == Some("23505") {
+                return AppError::conflict("Product already exists in delivery");
+            }
+        }
+        AppError::db(e)
+    })?;
 
-    let items = sqlx::query!(
-        r#"SELECT id, product_id, quantity, unit_price::FLOAT8 as "unit_price!" FROM delivery_items WHERE delivery_id = $1 ORDER BY id"#,
-        id
-    ).fetch_all(&db_pool).await?;
+    for b in &item.batches {
+        receive_batch(tx, delivery_id, item_id, item.product_id, b, user_id).await?;
+    }
 
-    let mut items_out = Vec::with_capacity(items.len());
-    for it in items {
-        // Fetch batches created from this delivery_item via stock_movements
-        let receipt_batches = sqlx::query!(
-            r#"SELECT 
-                sm.batch_id, 
-                (sm.quantity)::INT as "receipt_qty!", 
-                b.batch_number, 
-                b.remaining_quantity, 
+    Ok(item_id)
+}
+
+/// Put good units of a line into stock: a new batch, or a top-up of the batch
+/// with the same number, recorded as the line's receipt for that batch. New
+/// batches log their movement through trigger_log_batch_delivery; top-ups are
+/// logged here since the trigger only fires on insert.
+async fn receive_batch(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    delivery_id: i64,
+    item_id: i64,
+    product_id: i64,
+    b: &NewDeliveryBatch,
+    user_id: i64,
+) -> Result<(), AppError> {
+    let existing = sqlx::query!(
+        r#"SELECT id, expiry_date FROM batches
+        WHERE product_id = $1 AND batch_number = $2
+        FOR UPDATE"#,
+        product_id,
+        b.batch_number
+    ).fetch_optional(&mut **tx).await?;
+
+    let batch_id = match existing {
+        Some(ex) => {
+            // Validate expiry date matches
+            if ex.expiry_date != b.expiry_date {
+                return Err(AppError::validation(format!(
+                    "Batch {} already exists with different expiry date",
+                    b.batch_number
+                )));
+            }
+            sqlx::query!(
+                r#"UPDATE batches
+                SET quantity = quantity + $1,
+                remaining_quantity = remaining_quantity + $1
+                WHERE id = $2"#,
+                b.quantity,
+                ex.id
+            ).execute(&mut **tx).await?;
+
+            sqlx::query!(
+                r#"INSERT INTO stock_movements
+                   (batch_id, product_id, movement_type, quantity, reference_type, reference_id, notes, created_by, movement_date)
+                   VALUES ($1, $2, 'delivery_in', ($3)::FLOAT8::NUMERIC, 'delivery', $4, $5, $6,
+                           (SELECT delivery_date FROM deliveries WHERE id = $4::INT))"#,
+                ex.id as i32,
+                product_id as i32,
+                b.quantity as f64,
+                delivery_id as i32,
+                format!("Delivery receipt (top-up) - Batch: {}", b.batch_number),
+                user_id as i32
+            )
+            .execute(&mut **tx)
+            .await?;
+            ex.id
+        }
+        None => {
+            sqlx::query_scalar!(
+                r#"INSERT INTO batches (product_id, delivery_id, delivery_item_id, batch_number, quantity, remaining_quantity, expiry_date)
+                VALUES ($1,$2,$3,$4,$5,$5,$6)
+                RETURNING id"#,
+                product_id,
+                delivery_id,
+                item_id,
+                b.batch_number,
+                b.quantity,
                 b.expiry_date
-               FROM stock_movements sm
-               JOIN batches b ON b.id = sm.batch_id
-               WHERE sm.reference_type = 'delivery' 
-                 AND sm.reference_id = $1
-                 AND sm.movement_type = 'delivery_in'
-                 AND b.product_id = $2
-               ORDER BY b.expiry_date ASC, b.id ASC"#,
-            id as i32,
-            it.product_id
-        )
-        .fetch_all(&db_pool)
-        .await?;
+            ).fetch_one(&mut **tx).await.map_err(|e| {
+                if let Some(db) = e.as_database_error() {
+                    if db.code().as_deref() == Some("23505") { return AppError::conflict("Batch number exists for product"); }
+                    if db.code().as_deref() == Some("23503") { return AppError::validation("Invalid delivery/product reference"); }
+                }
+                AppError::db(e)
+            })?
+        }
+    };
 
-        items_out.push(DeliveryItemResponse {
-            id: it.id,
-            product_id: it.product_id,
-            quantity: it.quantity,
-            unit_price: it.unit_price,
-            batches: receipt_batches
-                .into_iter()
-                .map(|rb| DeliveryBatchResponse {
-                    id: rb.batch_id as i64,
-                    batch_number: rb.batch_number,
-                    quantity: rb.receipt_qty,
-                    remaining_quantity: rb.remaining_quantity,
-                    expiry_date: rb.expiry_date,
-                })
-                .collect(),
-        });
-    }
+    sqlx::query!(
+        r#"INSERT INTO batch_receipts (batch_id, delivery_id, delivery_item_id, quantity)
+           VALUES ($1, $2, $3, $4)
+           ON CONFLICT (delivery_item_id, batch_id)
+           DO UPDATE SET quantity = batch_receipts.quantity + EXCLUDED.quantity"#,
+        batch_id,
+        delivery_id,
+        item_id,
+        b.quantity
+    )
+    .execute(&mut **tx)
+    .await?;
 
-    Ok(Json(DeliveryResponse {
-        id: row.id,
-        delivery_date: row.delivery_date,
-        received_by: row.received_by,
-        delivery_note_number: row.delivery_note_number,
-        items: items_out,
-    }))
+    Ok(())
 }
 
-pub async fn delete_delivery(
-    State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
-    axum::extract::Path(id): axum::extract::Path<i64>,
-) -> Result<StatusCode, AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden("Only managers can delete deliveries"));
+/// Good units a line put into a batch, net of corrections; None when the line
+/// never received into it.
+async fn receipt_quantity(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    item_id: i64,
+    batch_id: i64,
+) -> Result<Option<i32>, AppError> {
+    let quantity = sqlx::query_scalar!(
+        r#"SELECT quantity FROM batch_receipts WHERE delivery_item_id = $1 AND batch_id = $2 FOR UPDATE"#,
+        item_id,
+        batch_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(quantity)
+}
+
+/// Correct what a line put into a batch by `delta` units with a compensating
+/// adjustment. Units that already left the warehouse cannot be taken back.
+async fn adjust_receipt(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    delivery_id: i64,
+    item_id: i64,
+    batch_id: i64,
+    delta: i32,
+    user_id: i64,
+) -> Result<(), AppError> {
+    let batch = sqlx::query!(
+        r#"SELECT product_id, batch_number, remaining_quantity FROM batches WHERE id = $1 FOR UPDATE"#,
+        batch_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    if batch.remaining_quantity + delta < 0 {
+        return Err(AppError::conflict(format!(
+            "Batch {} has only {} units left in the warehouse; cannot take back {}",
+            batch.batch_number, batch.remaining_quantity, -delta
+        )));
     }
-    let mut tx = db_pool.begin().await?;
 
-    // Check if any batch linked to this delivery has sales
-    let locked = sqlx::query_scalar!(
-        r#"SELECT EXISTS (
-            SELECT 1 FROM sale_items si
-            JOIN batches b ON b.id = si.batch_id
-            JOIN stock_movements sm ON sm.batch_id = b.id
-            WHERE sm.reference_type = 'delivery' 
-              AND sm.reference_id = $1
-              AND sm.movement_type = 'delivery_in'
-        ) as "exists!""#,
-        id as i32
+    sqlx::query!(
+        r#"UPDATE batches SET quantity = quantity + $2, remaining_quantity = remaining_quantity + $2 WHERE id = $1"#,
+        batch_id,
+        delta
     )
-    .fetch_one(&mut *tx)
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query!(
+        r#"UPDATE batch_receipts SET quantity = quantity + $3 WHERE delivery_item_id = $1 AND batch_id = $2"#,
+        item_id,
+        batch_id,
+        delta
+    )
+    .execute(&mut **tx)
     .await?;
-    if locked {
+
+    sqlx::query!(
+        r#"INSERT INTO stock_movements
+           (batch_id, product_id, movement_type, quantity, reference_type, reference_id, notes, created_by, movement_date)
+           VALUES ($1, $2, 'adjustment', ($3)::FLOAT8::NUMERIC, 'delivery_correction', $4, $5, $6,
+                   (SELECT delivery_date FROM deliveries WHERE id = $4::INT))"#,
+        batch_id as i32,
+        batch.product_id as i32,
+        delta as f64,
+        delivery_id as i32,
+        format!("Delivery #{} correction - Batch: {}", delivery_id, batch.batch_number),
+        user_id as i32
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Lock a delivery against concurrent corrections and header edits. Returns its
+/// supplier. A reversed delivery can no longer be corrected.
+async fn lock_delivery(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: i64,
+) -> Result<i64, AppError> {
+    let delivery = sqlx::query!(
+        r#"SELECT supplier_id,
+                  EXISTS (SELECT 1 FROM delivery_reversals r WHERE r.delivery_id = d.id) as "reversed!"
+           FROM deliveries d WHERE id = $1 FOR UPDATE"#,
+        id
+    )
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or_else(|| AppError::not_found("Delivery not found"))?;
+    if delivery.reversed {
         return Err(AppError::conflict(
-            "Cannot delete delivery with sold batches",
+            "Delivery has been reversed and can no longer be corrected",
         ));
     }
+    Ok(delivery.supplier_id)
+}
 
-    // Get all stock_movements for this delivery
-    let movements = sqlx::query!(
-        r#"SELECT batch_id, (quantity)::INT as "quantity!" FROM stock_movements 
-           WHERE reference_type = 'delivery' AND reference_id = $1 AND movement_type = 'delivery_in'"#,
-        id as i32
-    ).fetch_all(&mut *tx).await?;
+/// Lock a line of the delivery. Returns its product.
+async fn lock_delivery_item(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    delivery_id: i64,
+    item_id: i64,
+) -> Result<i64, AppError> {
+    sqlx::query_scalar!(
+        r#"SELECT product_id FROM delivery_items WHERE id = $1 AND delivery_id = $2 FOR UPDATE"#,
+        item_id,
+        delivery_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or_else(|| AppError::not_found("Delivery item not found"))
+}
 
-    // Reduce batch quantities for each movement, delete batch if quantity becomes 0
-    for m in movements {
-        let updated = sqlx::query!(
-            r#"UPDATE batches SET quantity = quantity - $1, remaining_quantity = remaining_quantity - $1
-               WHERE id = $2
-               RETURNING quantity"#,
-            m.quantity,
-            m.batch_id as i64
-        ).fetch_one(&mut *tx).await?;
-
-        // If batch is now empty, delete it
-        if updated.quantity <= 0 {
-            sqlx::query!("DELETE FROM batches WHERE id = $1", m.batch_id as i64)
-                .execute(&mut *tx)
+/// Bring the supplier claim in line with the delivery's lines: damaged and
+/// missing (short) units are claimed back at the line's unit price, and there is
+/// no claim when the delivery arrived complete and undamaged. An open claim is
+/// rebuilt; a settled or rejected one may not change.
+async fn sync_supplier_claim(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    delivery_id: i64,
+    supplier_id: i64,
+) -> Result<(), AppError> {
+    let items = sqlx::query!(
+        r#"SELECT id, product_id, unit_price, note_quantity, received_quantity, damaged_quantity, damage_reason
+           FROM delivery_items WHERE delivery_id = $1 ORDER BY id"#,
+        delivery_id
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let lines: Vec<_> = items
+        .iter()
+        .flat_map(|it| {
+            [
+                (it, "damaged", it.damaged_quantity),
+                (it, "short", it.note_quantity - it.received_quantity),
+            ]
+        })
+        .filter(|(_, _, quantity)| *quantity > 0)
+        .collect();
+
+    let claim = sqlx::query!(
+        r#"SELECT id, status FROM supplier_claims WHERE delivery_id = $1 FOR UPDATE"#,
+        delivery_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let claim_id = match claim {
+        None if lines.is_empty() => return Ok(()),
+        Some(claim) if claim.status != "open" => {
+            let claimed = sqlx::query!(
+                r#"SELECT delivery_item_id, claim_type, quantity, unit_price FROM supplier_claim_lines
+                   WHERE claim_id = $1 ORDER BY delivery_item_id, claim_type"#,
+                claim.id
+            )
+            .fetch_all(&mut **tx)
+            .await?;
+            let unchanged = claimed.len() == lines.len()
+                && claimed.iter().zip(&lines).all(|(c, (it, claim_type, quantity))| {
+                    c.delivery_item_id == it.id
+                        && c.claim_type == *claim_type
+                        && c.quantity == *quantity
+                        && c.unit_price == it.unit_price
+                });
+            if !unchanged {
+                return Err(AppError::conflict(format!(
+                    "Supplier claim is already {}; the correction would change what was claimed",
+                    claim.status
+                )));
+            }
+            return Ok(());
+        }
+        Some(claim) => {
+            sqlx::query!("DELETE FROM supplier_claim_lines WHERE claim_id = $1", claim.id)
+                .execute(&mut **tx)
                 .await?;
+            if lines.is_empty() {
+                sqlx::query!("DELETE FROM supplier_claims WHERE id = $1", claim.id)
+                    .execute(&mut **tx)
+                    .await?;
+                return Ok(());
+            }
+            claim.id
         }
-    }
+        None => {
+            sqlx::query_scalar!(
+                r#"INSERT INTO supplier_claims (delivery_id, supplier_id, total_value)
+                   VALUES ($1, $2, 0)
+                   RETURNING id"#,
+                delivery_id,
+                supplier_id
+            )
+            .fetch_one(&mut **tx)
+            .await?
+        }
+    };
 
-    // Delete stock_movements for this delivery
+    let total_value = lines
+        .iter()
+        .fold(money::zero(), |sum, (it, _, quantity)| sum + money::line_amount(*quantity, it.unit_price));
     sqlx::query!(
-        "DELETE FROM stock_movements WHERE reference_type = 'delivery' AND reference_id = $1",
-        id as i32
+        "UPDATE supplier_claims SET total_value = $2 WHERE id = $1",
+        claim_id,
+        total_value
     )
-    .execute(&mut *tx)
+    .execute(&mut **tx)
     .await?;
 
-    // Delete delivery_items
-    sqlx::query!("DELETE FROM delivery_items WHERE delivery_id = $1", id)
-        .execute(&mut *tx)
-        .await?;
-
-    // Delete delivery
-    let res = sqlx::query!("DELETE FROM deliveries WHERE id = $1", id)
-        .execute(&mut *tx)
+    for (it, claim_type, quantity) in lines {
+        sqlx::query!(
+            r#"INSERT INTO supplier_claim_lines
+               (claim_id, delivery_item_id, product_id, claim_type, quantity, unit_price, value, reason)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+            claim_id,
+            it.id,
+            it.product_id,
+            claim_type,
+            quantity,
+            it.unit_price,
+            money::line_amount(quantity, it.unit_price),
+            if claim_type == "damaged" { it.damage_reason.as_deref() } else { None }
+        )
+        .execute(&mut **tx)
         .await?;
-
-    tx.commit().await?;
-    if res.rows_affected() == 0 {
-        return Err(AppError::not_found("Delivery not found"));
     }
-    Ok(StatusCode::NO_CONTENT)
+
+    Ok(())
 }
@@ -0,0 +1,237 @@
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use crate::auth::role::Manager;
+use crate::dtos::delivery_reversal::{
+    CreateDeliveryReversalRequest, DeliveryReversalLine, DeliveryReversalPreview,
+    DeliveryReversalResponse, ReversalPreviewLine,
+};
+use crate::error::AppError;
+use crate::middleware::auth::RequireRole;
+use crate::state::AppState;
+
+// What a reversal would take back now, and what keeps the rest out of reach
+pub async fn preview_delivery_reversal(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Path(id): Path<i64>,
+) -> Result<Json<DeliveryReversalPreview>, AppError> {
+    let mut tx = db_pool.begin().await?;
+    sqlx::query_scalar!(r#"SELECT id FROM deliveries WHERE id = $1"#, id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found("Delivery not found"))?;
+    let lines = reversal_lines(&mut tx, id).await?;
+    tx.commit().await?;
+
+    Ok(Json(summarize_preview(id, lines)))
+}
+
+// Take a delivery's units back out of the warehouse. A full reversal is refused
+// while any of them are on trucks, sold or written off; `warehouse_only` takes
+// back what is still there and leaves the rest.
+pub async fn reverse_delivery(
+    State(AppState { db_pool }): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
+    Path(id): Path<i64>,
+    Json(req): Json<CreateDeliveryReversalRequest>,
+) -> Result<(StatusCode, Json<DeliveryReversalResponse>), AppError> {
+    let mut tx = db_pool.begin().await?;
+    sqlx::query_scalar!(r#"SELECT id FROM deliveries WHERE id = $1 FOR UPDATE"#, id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found("Delivery not found"))?;
+
+    // An open claim still asks the supplier for the damaged and short units of what
+    // is being sent back; it has to be settled or rejected first
+    let open_claim = sqlx::query_scalar!(
+        r#"SELECT id FROM supplier_claims WHERE delivery_id = $1 AND status = 'open' FOR UPDATE"#,
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(claim_id) = open_claim {
+        return Err(AppError::conflict(format!(
+            "Supplier claim {claim_id} for this delivery is still open; settle or reject it before reversing"
+        )));
+    }
+
+    sqlx::query!(
+        r#"SELECT b.id FROM batches b
+           JOIN batch_receipts br ON br.batch_id = b.id
+           WHERE br.delivery_id = $1
+           ORDER BY b.id
+           FOR UPDATE OF b"#,
+        id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let preview = summarize_preview(id, reversal_lines(&mut tx, id).await?);
+
+    let blocked = preview
+        .lines
+        .iter()
+        .find(|l| l.reversible_quantity < l.outstanding_quantity);
+    if let (false, Some(blocked)) = (req.warehouse_only, blocked) {
+        return Err(AppError::conflict(format!(
+            "Batch {}: {} units received but only {} still in the warehouse ({} on trucks, {} sold, {} written off); \
+             reverse with warehouse_only to take back the warehouse units",
+            blocked.batch_number,
+            blocked.outstanding_quantity,
+            blocked.reversible_quantity,
+            blocked.on_trucks,
+            blocked.sold,
+            blocked.adjusted_out
+        )));
+    }
+    if preview.reversible_quantity == 0 {
+        return Err(AppError::conflict(
+            "None of the delivery's units are left in the warehouse to reverse",
+        ));
+    }
+
+    let reversal = sqlx::query!(
+        r#"INSERT INTO delivery_reversals (delivery_id, warehouse_only, notes, reversed_by)
+           VALUES ($1, $2, $3, $4)
+           RETURNING id, reversed_at"#,
+        id,
+        req.warehouse_only,
+        req.notes,
+        auth.user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let mut lines = Vec::new();
+    for l in preview.lines.into_iter().filter(|l| l.reversible_quantity > 0) {
+        sqlx::query!(
+            r#"UPDATE batches SET quantity = quantity - $2, remaining_quantity = remaining_quantity - $2 WHERE id = $1"#,
+            l.batch_id,
+            l.reversible_quantity
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"INSERT INTO delivery_reversal_lines (reversal_id, delivery_item_id, batch_id, quantity)
+               VALUES ($1, $2, $3, $4)"#,
+            reversal.id,
+            l.delivery_item_id,
+            l.batch_id,
+            l.reversible_quantity
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"INSERT INTO stock_movements
+               (batch_id, product_id, movement_type, quantity, reference_type, reference_id, notes, created_by, movement_date)
+               VALUES ($1, $2, 'adjustment', ($3)::FLOAT8::NUMERIC, 'delivery_reversal', $4, $5, $6, CURRENT_DATE)"#,
+            l.batch_id as i32,
+            l.product_id as i32,
+            -(l.reversible_quantity as f64),
+            reversal.id as i32,
+            format!("Delivery #{} reversal #{} - Batch: {}", id, reversal.id, l.batch_number),
+            auth.user_id as i32
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        lines.push(DeliveryReversalLine {
+            delivery_item_id: l.delivery_item_id,
+            product_id: l.product_id,
+            batch_id: l.batch_id,
+            batch_number: l.batch_number,
+            quantity: l.reversible_quantity,
+        });
+    }
+
+    tx.commit().await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(DeliveryReversalResponse {
+            id: reversal.id,
+            delivery_id: id,
+            warehouse_only: req.warehouse_only,
+            notes: req.notes,
+            reversed_by: Some(auth.user_id),
+            reversed_at: reversal.reversed_at,
+            quantity: lines.iter().map(|l| l.quantity).sum(),
+            lines,
+        }),
+    ))
+}
+
+// ==================== Helper Functions ====================
+
+/// Each batch receipt of the delivery with what earlier reversals took back and
+/// where the batch's stock is now. Reversible units are the outstanding receipt,
+/// capped by what is left in the warehouse.
+async fn reversal_lines(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    delivery_id: i64,
+) -> Result<Vec<ReversalPreviewLine>, AppError> {
+    let rows = sqlx::query!(
+        r#"SELECT br.delivery_item_id, b.product_id, br.batch_id, b.batch_number,
+                  br.quantity as received_quantity,
+                  COALESCE((
+                      SELECT SUM(rl.quantity) FROM delivery_reversal_lines rl
+                      WHERE rl.delivery_item_id = br.delivery_item_id AND rl.batch_id = br.batch_id
+                  ), 0)::INT as "reversed_quantity!",
+                  b.remaining_quantity as in_warehouse,
+                  COALESCE((
+                      SELECT SUM(tli.quantity_loaded - tli.quantity_sold - tli.quantity_returned - tli.quantity_discarded)
+                      FROM truck_load_items tli
+                      JOIN truck_loads tl ON tl.id = tli.truck_load_id
+                      WHERE tli.batch_id = br.batch_id AND tl.status <> 'reconciled'
+                  ), 0)::INT as "on_trucks!",
+                  COALESCE((SELECT SUM(si.quantity) FROM sale_items si WHERE si.batch_id = br.batch_id), 0)::INT as "sold!",
+                  COALESCE((
+                      SELECT -SUM(stock_movement_effect(sm.movement_type, sm.reference_type, sm.quantity))
+                      FROM stock_movements sm
+                      WHERE sm.batch_id = br.batch_id
+                        AND sm.movement_type IN ('adjustment', 'expired_out')
+                        AND sm.reference_type <> 'delivery_reversal'
+                        -- this delivery's own corrections are already net in its receipts
+                        AND NOT (sm.reference_type = 'delivery_correction' AND sm.reference_id = $2)
+                  ), 0)::INT as "adjusted_out!"
+           FROM batch_receipts br
+           JOIN batches b ON b.id = br.batch_id
+           WHERE br.delivery_id = $1
+           ORDER BY br.delivery_item_id, b.expiry_date, b.id"#,
+        delivery_id,
+        delivery_id as i32
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            let outstanding = r.received_quantity - r.reversed_quantity;
+            ReversalPreviewLine {
+                delivery_item_id: r.delivery_item_id,
+                product_id: r.product_id,
+                batch_id: r.batch_id,
+                batch_number: r.batch_number,
+                received_quantity: r.received_quantity,
+                reversed_quantity: r.reversed_quantity,
+                outstanding_quantity: outstanding,
+                reversible_quantity: outstanding.min(r.in_warehouse).max(0),
+                in_warehouse: r.in_warehouse,
+                on_trucks: r.on_trucks,
+                sold: r.sold,
+                adjusted_out: r.adjusted_out,
+            }
+        })
+        .collect())
+}
+
+fn summarize_preview(delivery_id: i64, lines: Vec<ReversalPreviewLine>) -> DeliveryReversalPreview {
+    DeliveryReversalPreview {
+        delivery_id,
+        fully_reversible: lines.iter().all(|l| l.reversible_quantity == l.outstanding_quantity),
+        reversible_quantity: lines.iter().map(|l| l.reversible_quantity).sum(),
+        lines,
+    }
+}
@@ -0,0 +1,113 @@
+use axum::{extract::{Path, Query, State}, Json};
+use crate::auth::role::Manager;
+use crate::database::filter::{self, ListFilter, Page, Sort};
+use crate::dtos::expiry::{ExpiryWriteOffItem, ExpiryWriteOffReport, ExpiryWriteOffSummary};
+use crate::dtos::pagination::{PageParams, Paginated};
+use crate::error::AppError;
+use crate::jobs::expiry_sweep::{self, SweepTrigger};
+use crate::middleware::auth::RequireRole;
+use crate::state::AppState;
+
+// Run the expiry sweep now instead of waiting for the scheduler
+pub async fn run_expiry_sweep(
+    State(AppState { db_pool }): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
+) -> Result<Json<ExpiryWriteOffReport>, AppError> {
+    expiry_sweep::run_sweep(&db_pool, SweepTrigger::Manual { user_id: auth.user_id })
+        .await
+        .map(Json)
+}
+
+pub async fn list_expiry_sweeps(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Query(page_params): Query<PageParams>,
+) -> Result<Json<Paginated<ExpiryWriteOffSummary>>, AppError> {
+    let sort = Sort::parse(
+        page_params.sort.as_deref(),
+        &[("created_at", "w.created_at"), ("total_value", "w.total_value")],
+        "-created_at",
+        "w.id",
+    )?;
+    let page = Page::from_params(&page_params)?;
+
+    let filter = ListFilter::new(
+        r#"SELECT w.id, w.triggered_by, u.username as requested_by_username, w.as_of,
+                  w.batches_written_off, w.units_written_off, w.total_value, w.created_at
+           FROM expiry_write_offs w
+           LEFT JOIN users u ON w.requested_by = u.id"#,
+    );
+    let (rows, total) = filter::fetch_page::<(
+        i64,
+        String,
+        Option<String>,
+        chrono::NaiveDate,
+        i32,
+        i32,
+        rust_decimal::Decimal,
+        chrono::DateTime<chrono::Utc>,
+    )>(&db_pool, &filter, "", &sort, &page)
+    .await?;
+
+    Ok(Json(page.envelope(
+        rows.into_iter()
+            .map(
+                |(id, triggered_by, requested_by_username, as_of, batches_written_off, units_written_off, total_value, created_at)| {
+                    ExpiryWriteOffSummary {
+                        id,
+                        triggered_by,
+                        requested_by_username,
+                        as_of,
+                        batches_written_off,
+                        units_written_off,
+                        total_value,
+                        created_at,
+                    }
+                },
+            )
+            .collect(),
+        total,
+    )))
+}
+
+pub async fn get_expiry_sweep(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Path(id): Path<i64>,
+) -> Result<Json<ExpiryWriteOffReport>, AppError> {
+    let write_off = sqlx::query!(
+        r#"SELECT id, triggered_by, requested_by, as_of, batches_written_off,
+                  units_written_off, total_value, created_at
+           FROM expiry_write_offs WHERE id = $1"#,
+        id
+    )
+    .fetch_optional(&db_pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("Expiry write-off not found"))?;
+
+    let items = sqlx::query_as!(
+        ExpiryWriteOffItem,
+        r#"SELECT i.batch_id, b.batch_number, i.product_id, p.name as product_name,
+                  i.expiry_date, i.quantity, i.unit_cost, i.value
+           FROM expiry_write_off_items i
+           JOIN batches b ON i.batch_id = b.id
+           JOIN products p ON i.product_id = p.id
+           WHERE i.write_off_id = $1
+           ORDER BY i.expiry_date, i.batch_id"#,
+        id
+    )
+    .fetch_all(&db_pool)
+    .await?;
+
+    Ok(Json(ExpiryWriteOffReport {
+        id: Some(write_off.id),
+        triggered_by: write_off.triggered_by,
+        requested_by: write_off.requested_by,
+        as_of: write_off.as_of,
+        batches_written_off: write_off.batches_written_off,
+        units_written_off: write_off.units_written_off,
+        total_value: write_off.total_value,
+        created_at: Some(write_off.created_at),
+        items,
+    }))
+}
@@ -0,0 +1,27 @@
+use axum::{extract::{Query, State}, Json};
+use crate::auth::role::Manager;
+use crate::dtos::integrity::{IntegrityReport, RepairQuery};
+use crate::error::AppError;
+use crate::jobs::integrity_check;
+use crate::middleware::auth::RequireRole;
+use crate::state::AppState;
+
+// Report invariant violations without changing anything
+pub async fn check_integrity(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+) -> Result<Json<IntegrityReport>, AppError> {
+    integrity_check::run_check(&db_pool, true, None).await.map(Json)
+}
+
+// Post corrective adjustments for ledger mismatches; dry run unless ?dry_run=false
+pub async fn repair_integrity(
+    State(AppState { db_pool }): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
+    Query(query): Query<RepairQuery>,
+) -> Result<Json<IntegrityReport>, AppError> {
+    let dry_run = query.dry_run.unwrap_or(true);
+    integrity_check::run_check(&db_pool, dry_run, Some(auth.user_id))
+        .await
+        .map(Json)
+}
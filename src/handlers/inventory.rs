@@ -0,0 +1,177 @@
+use axum::{extract::{Query, State}, Json};
+use crate::auth::role::Manager;
+use crate::dtos::inventory::{
+    InventoryValuation, OnHandBatch, OnHandProduct, ProductValuation, ValuationLayer, ValuationQuery,
+};
+use crate::database::cost_layers::{cost_layers, HeldUnits};
+use crate::error::AppError;
+use crate::middleware::auth::{RequireAnyRole, RequireRole};
+use crate::money;
+use crate::state::AppState;
+
+// Stock per product right now: warehouse units (batches.remaining_quantity) and
+// units still out on unreconciled truck loads, with the batches holding them
+pub async fn get_on_hand(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireAnyRole,
+) -> Result<Json<Vec<OnHandProduct>>, AppError> {
+    let products = sqlx::query!(
+        r#"SELECT id as "id!", name as "name!", current_stock as "warehouse_units!"
+           FROM current_stock
+           ORDER BY name"#
+    )
+    .fetch_all(&db_pool)
+    .await?;
+
+    let batches = sqlx::query!(
+        r#"SELECT b.id, b.product_id, b.batch_number, b.expiry_date, b.remaining_quantity,
+                  COALESCE(t.units_on_trucks, 0) as "truck_units!"
+           FROM batches b
+           LEFT JOIN batch_units_on_trucks t ON t.batch_id = b.id
+           WHERE b.remaining_quantity > 0 OR t.units_on_trucks > 0
+           ORDER BY b.expiry_date, b.created_at"#
+    )
+    .fetch_all(&db_pool)
+    .await?;
+
+    let on_hand = products
+        .into_iter()
+        .map(|product| {
+            let batches: Vec<OnHandBatch> = batches
+                .iter()
+                .filter(|b| b.product_id == product.id)
+                .map(|b| OnHandBatch {
+                    batch_id: b.id,
+                    batch_number: b.batch_number.clone(),
+                    expiry_date: b.expiry_date,
+                    warehouse_units: b.remaining_quantity,
+                    truck_units: b.truck_units,
+                })
+                .collect();
+            let truck_units: i64 = batches.iter().map(|b| b.truck_units as i64).sum();
+
+            OnHandProduct {
+                product_id: product.id,
+                product_name: product.name,
+                warehouse_units: product.warehouse_units,
+                truck_units,
+                total_units: product.warehouse_units + truck_units,
+                earliest_expiry: batches.first().map(|b| b.expiry_date),
+                batches,
+            }
+        })
+        .collect();
+
+    Ok(Json(on_hand))
+}
+
+// Company-owned stock at the end of `as_of`, replayed from the stock ledger: the
+// warehouse balance of each batch plus its units on trucks not reconciled yet,
+// valued per FIFO cost layer (see database::cost_layers). A supplier filter keeps
+// only the layers received from that supplier.
+pub async fn get_valuation(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Query(query): Query<ValuationQuery>,
+) -> Result<Json<InventoryValuation>, AppError> {
+    let today = sqlx::query_scalar!(r#"SELECT CURRENT_DATE as "today!""#)
+        .fetch_one(&db_pool)
+        .await?;
+    let as_of = query.as_of.unwrap_or(today);
+    if as_of > today {
+        return Err(AppError::validation("as_of cannot be in the future"));
+    }
+
+    let batches = sqlx::query!(
+        r#"SELECT b.product_id, p.name as product_name,
+                  b.id, b.batch_number, b.expiry_date,
+                  bal.balance as "warehouse_units!",
+                  COALESCE(t.units, 0) as "truck_units!"
+           FROM batch_balance_as_of($1) bal
+           LEFT JOIN batch_truck_units_as_of($1) t ON t.batch_id = bal.batch_id
+           JOIN batches b ON bal.batch_id = b.id
+           JOIN products p ON b.product_id = p.id
+           WHERE bal.balance + COALESCE(t.units, 0) > 0
+           ORDER BY p.name, b.id"#,
+        as_of
+    )
+    .fetch_all(&db_pool)
+    .await?;
+
+    let held: Vec<HeldUnits> = batches
+        .iter()
+        .map(|b| HeldUnits {
+            batch_id: b.id,
+            warehouse_units: b.warehouse_units,
+            truck_units: b.truck_units,
+        })
+        .collect();
+    let mut conn = db_pool.acquire().await?;
+    let mut layers = cost_layers(&mut conn, as_of, &held).await?;
+
+    let mut products: Vec<ProductValuation> = Vec::new();
+    for batch in batches {
+        for cost in layers.remove(&batch.id).unwrap_or_default() {
+            if query.supplier_id.is_some_and(|id| id != cost.supplier_id) {
+                continue;
+            }
+            let units = cost.units();
+            let value = money::line_amount(units, cost.unit_cost);
+            let layer = ValuationLayer {
+                batch_id: batch.id,
+                batch_number: batch.batch_number.clone(),
+                delivery_id: cost.delivery_id,
+                supplier_id: cost.supplier_id,
+                delivery_date: cost.delivery_date,
+                expiry_date: batch.expiry_date,
+                warehouse_units: cost.warehouse_units,
+                truck_units: cost.truck_units,
+                units,
+                unit_cost: cost.unit_cost,
+                value,
+            };
+
+            // Batches are ordered by product, so a new product starts a new group
+            match products.last_mut() {
+                Some(product) if product.product_id == batch.product_id => {
+                    product.warehouse_units += layer.warehouse_units;
+                    product.truck_units += layer.truck_units;
+                    product.units += units;
+                    product.value += value;
+                    product.layers.push(layer);
+                }
+                _ => products.push(ProductValuation {
+                    product_id: batch.product_id,
+                    product_name: batch.product_name.clone(),
+                    warehouse_units: layer.warehouse_units,
+                    truck_units: layer.truck_units,
+                    units,
+                    value,
+                    layers: vec![layer],
+                }),
+            }
+        }
+    }
+
+    // Layers within a product are listed oldest receipt first
+    for product in &mut products {
+        product
+            .layers
+            .sort_by_key(|l| (l.delivery_date, l.expiry_date, l.batch_id));
+    }
+
+    let warehouse_units = products.iter().map(|p| p.warehouse_units).sum();
+    let truck_units = products.iter().map(|p| p.truck_units).sum();
+    let total_units = products.iter().map(|p| p.units).sum();
+    let total_value = products.iter().fold(money::zero(), |sum, p| sum + p.value);
+
+    Ok(Json(InventoryValuation {
+        as_of,
+        supplier_id: query.supplier_id,
+        warehouse_units,
+        truck_units,
+        total_units,
+        total_value,
+        products,
+    }))
+}
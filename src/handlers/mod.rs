@@ -8,4 +8,13 @@ pub mod sale;
 pub mod allowance;
 pub mod reconciliation;
 pub mod stock_movement;
-pub mod batch;
\ No newline at end of file
+pub mod batch;
+pub mod expiry;
+pub mod report;
+pub mod inventory;
+pub mod integrity;
+pub mod stocktake;
+pub mod purchase_order;
+pub mod supplier;
+pub mod supplier_claim;
+pub mod delivery_reversal;
@@ -1,46 +1,81 @@
 // src/handlers/products.rs
-use crate::dtos::product::{CreateProductRequest, ProductResponse, UpdateProductRequest};
+use crate::auth::role::Manager;
+use crate::database::filter::{self, ListFilter, Page, Sort};
+use crate::dtos::pagination::{PageParams, Paginated};
+use crate::dtos::product::{
+    CreateProductRequest, ProductPriceResponse, ProductResponse, UpdateProductRequest,
+};
 use crate::error::AppError;
-use crate::models::product::Product;
+use crate::middleware::auth::RequireRole;
+use crate::models::product::{Product, ProductPrice};
+use crate::money;
 use crate::state::AppState;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     Json,
 };
 use sqlx::Error as SqlxError;
 use tracing::{error, instrument};
 
-fn map_unique_violation(err: SqlxError, message: &str) -> AppError {
+fn validate_reorder_settings(settings: [(&str, Option<i32>); 3]) -> Result<(), AppError> {
+    match settings.iter().find(|(_, value)| value.is_some_and(|v| v < 0)) {
+        Some((field, _)) => Err(AppError::validation(format!("{field} cannot be negative"))),
+        None => Ok(()),
+    }
+}
+
+fn validate_prices(prices: [(&str, Option<rust_decimal::Decimal>); 2]) -> Result<(), AppError> {
+    match prices.iter().find(|(_, value)| value.is_some_and(|v| v.is_sign_negative())) {
+        Some((field, _)) => Err(AppError::validation(format!("{field} cannot be negative"))),
+        None => Ok(()),
+    }
+}
+
+fn map_constraint_violation(err: SqlxError, message: &str) -> AppError {
     match err {
         SqlxError::Database(db_err) if db_err.code().as_deref() == Some("23505") => {
             AppError::conflict(message)
         }
+        SqlxError::Database(db_err) if db_err.code().as_deref() == Some("23503") => {
+            AppError::validation("Invalid supplier_id")
+        }
         other => other.into(),
     }
 }
 
 // GET /products - List all products
-#[instrument(skip(state))]
+#[instrument(skip(state, page_params))]
 pub async fn get_products(
     State(state): State<AppState>,
-) -> Result<Json<Vec<ProductResponse>>, AppError> {
-    match sqlx::query_as::<_, Product>(
+    Query(page_params): Query<PageParams>,
+) -> Result<Json<Paginated<ProductResponse>>, AppError> {
+    let sort = Sort::parse(
+        page_params.sort.as_deref(),
+        &[
+            ("name", "name"),
+            ("current_wholesale_price", "current_wholesale_price"),
+            ("commission_per_unit", "commission_per_unit"),
+            ("created_at", "created_at"),
+        ],
+        "name",
+        "id",
+    )?;
+    let page = Page::from_params(&page_params)?;
+
+    let filter = ListFilter::new(
         "SELECT id, name,
-                current_wholesale_price::FLOAT8 AS current_wholesale_price,
-                commission_per_unit::FLOAT8     AS commission_per_unit,
-                created_at
-         FROM products ORDER BY name",
-    )
-    .fetch_all(&state.db_pool)
-    .await
-    {
-        Ok(products) => {
+                current_wholesale_price, commission_per_unit,
+                reorder_point, safety_stock, lead_time_days, supplier_id, created_at
+         FROM products",
+    );
+    match filter::fetch_page::<Product>(&state.db_pool, &filter, "", &sort, &page).await {
+        Ok((products, total)) => {
             let response = products.into_iter().map(ProductResponse::from).collect();
-            Ok(Json(response))
+            Ok(Json(page.envelope(response, total)))
         }
         Err(e) => {
             error!(?e, "Failed to fetch products");
-            Err(e.into())
+            Err(e)
         }
     }
 }
@@ -53,9 +88,8 @@ pub async fn get_product(
 ) -> Result<Json<ProductResponse>, AppError> {
     let product = sqlx::query_as::<_, Product>(
         "SELECT id, name,
-                current_wholesale_price::FLOAT8 AS current_wholesale_price,
-                commission_per_unit::FLOAT8     AS commission_per_unit,
-                created_at
+                current_wholesale_price, commission_per_unit,
+                reorder_point, safety_stock, lead_time_days, supplier_id, created_at
          FROM products WHERE id = $1",
     )
     .bind(id)
@@ -67,62 +101,203 @@ pub async fn get_product(
 }
 
 // POST /products - Create new product
-#[instrument(skip(state, payload))]
+#[instrument(skip(state, auth, payload))]
 pub async fn create_product(
     State(state): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
     Json(payload): Json<CreateProductRequest>,
 ) -> Result<Json<ProductResponse>, AppError> {
+    validate_reorder_settings([
+        ("reorder_point", payload.reorder_point),
+        ("safety_stock", payload.safety_stock),
+        ("lead_time_days", payload.lead_time_days),
+    ])?;
+    validate_prices([
+        ("current_wholesale_price", Some(payload.current_wholesale_price)),
+        ("commission_per_unit", Some(payload.commission_per_unit)),
+    ])?;
+
+    let mut tx = state.db_pool.begin().await?;
+
     let product = sqlx::query_as::<_, Product>(
-        "INSERT INTO products (name, current_wholesale_price, commission_per_unit) 
-         VALUES ($1, $2, $3) RETURNING id, name,
-                current_wholesale_price::FLOAT8 AS current_wholesale_price,
-                commission_per_unit::FLOAT8     AS commission_per_unit,
-                created_at",
+        "INSERT INTO products (name, current_wholesale_price, commission_per_unit,
+                               reorder_point, safety_stock, lead_time_days, supplier_id)
+         VALUES ($1, $2, $3, COALESCE($4, 0), COALESCE($5, 0), COALESCE($6, 1), $7) RETURNING id, name,
+                current_wholesale_price, commission_per_unit,
+                reorder_point, safety_stock, lead_time_days, supplier_id, created_at",
     )
     .bind(&payload.name)
-    .bind(payload.current_wholesale_price)
-    .bind(payload.commission_per_unit)
-    .fetch_one(&state.db_pool)
+    .bind(money::normalize(payload.current_wholesale_price))
+    .bind(money::normalize(payload.commission_per_unit))
+    .bind(payload.reorder_point)
+    .bind(payload.safety_stock)
+    .bind(payload.lead_time_days)
+    .bind(payload.supplier_id)
+    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| map_unique_violation(e, "Product name already exists"))?;
+    .map_err(|e| map_constraint_violation(e, "Product name already exists"))?;
+
+    sqlx::query(
+        "INSERT INTO product_price_history (product_id, effective_from, wholesale_price, commission_per_unit, changed_by)
+         VALUES ($1, CURRENT_DATE, $2, $3, $4)",
+    )
+    .bind(product.id)
+    .bind(product.current_wholesale_price)
+    .bind(product.commission_per_unit)
+    .bind(auth.user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
 
     Ok(Json(ProductResponse::from(product)))
 }
 
 // PUT /products/:id - Update product
-#[instrument(skip(state, payload), fields(id))]
+// A price or commission change is recorded in the price history, effective from
+// `effective_from` (default today); the product keeps the figures in force today.
+#[instrument(skip(state, auth, payload), fields(id))]
 pub async fn update_product(
     Path(id): Path<i64>,
     State(state): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
     Json(payload): Json<UpdateProductRequest>,
 ) -> Result<Json<ProductResponse>, AppError> {
-    let product = sqlx::query_as::<_, Product>(
+    validate_reorder_settings([
+        ("reorder_point", payload.reorder_point),
+        ("safety_stock", payload.safety_stock),
+        ("lead_time_days", payload.lead_time_days),
+    ])?;
+    validate_prices([
+        ("current_wholesale_price", payload.current_wholesale_price),
+        ("commission_per_unit", payload.commission_per_unit),
+    ])?;
+    let price_change = payload.current_wholesale_price.is_some() || payload.commission_per_unit.is_some();
+    if payload.effective_from.is_some() && !price_change {
+        return Err(AppError::validation(
+            "effective_from requires current_wholesale_price or commission_per_unit",
+        ));
+    }
+
+    let mut tx = state.db_pool.begin().await?;
+
+    sqlx::query(
         "UPDATE products SET 
          name = COALESCE($1, name),
-         current_wholesale_price = COALESCE($2, current_wholesale_price),
-         commission_per_unit = COALESCE($3, commission_per_unit)
-         WHERE id = $4 RETURNING id, name,
-                current_wholesale_price::FLOAT8 AS current_wholesale_price,
-                commission_per_unit::FLOAT8     AS commission_per_unit,
-                created_at",
+         reorder_point = COALESCE($3, reorder_point),
+         safety_stock = COALESCE($4, safety_stock),
+         lead_time_days = COALESCE($5, lead_time_days),
+         supplier_id = COALESCE($6, supplier_id)
+         WHERE id = $2 RETURNING id",
     )
     .bind(payload.name)
-    .bind(payload.current_wholesale_price)
-    .bind(payload.commission_per_unit)
     .bind(id)
-    .fetch_optional(&state.db_pool)
+    .bind(payload.reorder_point)
+    .bind(payload.safety_stock)
+    .bind(payload.lead_time_days)
+    .bind(payload.supplier_id)
+    .fetch_optional(&mut *tx)
     .await
-    .map_err(|e| map_unique_violation(e, "Product name already exists"))?
+    .map_err(|e| map_constraint_violation(e, "Product name already exists"))?
     .ok_or_else(|| AppError::not_found("Product not found"))?;
 
+    if price_change {
+        let (effective_from, in_future): (chrono::NaiveDate, bool) = sqlx::query_as(
+            "SELECT COALESCE($1::DATE, CURRENT_DATE), COALESCE($1::DATE, CURRENT_DATE) > CURRENT_DATE",
+        )
+        .bind(payload.effective_from)
+        .fetch_one(&mut *tx)
+        .await?;
+        if in_future {
+            return Err(AppError::validation("effective_from cannot be in the future"));
+        }
+
+        // Fields left out keep the figures in force on that day
+        sqlx::query(
+            "INSERT INTO product_price_history (product_id, effective_from, wholesale_price, commission_per_unit, changed_by)
+             SELECT p.id, $2,
+                    COALESCE($3, h.wholesale_price, p.current_wholesale_price),
+                    COALESCE($4, h.commission_per_unit, p.commission_per_unit),
+                    $5
+             FROM products p
+             LEFT JOIN LATERAL product_price_on(p.id, $2) h ON true
+             WHERE p.id = $1
+             ON CONFLICT (product_id, effective_from) DO UPDATE SET
+                wholesale_price = EXCLUDED.wholesale_price,
+                commission_per_unit = EXCLUDED.commission_per_unit,
+                changed_by = EXCLUDED.changed_by,
+                created_at = NOW()",
+        )
+        .bind(id)
+        .bind(effective_from)
+        .bind(payload.current_wholesale_price.map(money::normalize))
+        .bind(payload.commission_per_unit.map(money::normalize))
+        .bind(auth.user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "UPDATE products p SET
+             current_wholesale_price = h.wholesale_price,
+             commission_per_unit = h.commission_per_unit
+             FROM product_price_on($1, CURRENT_DATE) h
+             WHERE p.id = $1",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let product = sqlx::query_as::<_, Product>(
+        "SELECT id, name,
+                current_wholesale_price, commission_per_unit,
+                reorder_point, safety_stock, lead_time_days, supplier_id, created_at
+         FROM products WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
     Ok(Json(ProductResponse::from(product)))
 }
 
+// GET /products/:id/price-history - Price and commission changes, newest first
+#[instrument(skip(state), fields(id))]
+pub async fn get_price_history(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ProductPriceResponse>>, AppError> {
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM products WHERE id = $1)")
+        .bind(id)
+        .fetch_one(&state.db_pool)
+        .await?;
+    if !exists {
+        return Err(AppError::not_found("Product not found"));
+    }
+
+    let history = sqlx::query_as::<_, ProductPrice>(
+        "SELECT effective_from,
+                (LEAD(effective_from) OVER (ORDER BY effective_from) - 1) as effective_to,
+                wholesale_price, commission_per_unit, changed_by, created_at
+         FROM product_price_history
+         WHERE product_id = $1
+         ORDER BY effective_from DESC",
+    )
+    .bind(id)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    Ok(Json(history.into_iter().map(ProductPriceResponse::from).collect()))
+}
+
 // DELETE /products/:id - Delete product
 #[instrument(skip(state), fields(id))]
 pub async fn delete_product(
     Path(id): Path<i64>,
     State(state): State<AppState>,
+    _: RequireRole<Manager>,
 ) -> Result<Json<()>, AppError> {
     let result = sqlx::query("DELETE FROM products WHERE id = $1")
         .bind(id)
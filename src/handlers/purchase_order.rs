@@ -0,0 +1,453 @@
+use std::collections::BTreeMap;
+
+use axum::{extract::{Path, Query, State}, http::StatusCode, Json};
+use rust_decimal::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
+use crate::auth::role::Manager;
+use crate::database::filter::{self, ListFilter, Page, Sort};
+use crate::dtos::pagination::{PageParams, Paginated};
+use crate::dtos::purchase_order::{
+    ComparisonDelivery, ComparisonLine, ComparisonSummary, CreatePurchaseOrderRequest,
+    PurchaseOrderComparison, PurchaseOrderLineResponse, PurchaseOrderListItem,
+    PurchaseOrderListQuery, PurchaseOrderResponse,
+};
+use crate::error::AppError;
+use crate::handlers::supplier::{ensure_active_supplier, supplier_cost_price};
+use crate::middleware::auth::RequireRole;
+use crate::money;
+use crate::state::AppState;
+
+pub async fn create_purchase_order(
+    State(AppState { db_pool }): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
+    Json(req): Json<CreatePurchaseOrderRequest>,
+) -> Result<(StatusCode, Json<PurchaseOrderResponse>), AppError> {
+    let po_number = req.po_number.trim();
+    if po_number.is_empty() {
+        return Err(AppError::validation("po_number is required"));
+    }
+    if req.lines.is_empty() {
+        return Err(AppError::validation("Purchase order must have at least one line"));
+    }
+    if req.expected_date.is_some_and(|expected| expected < req.order_date) {
+        return Err(AppError::validation("expected_date cannot be before order_date"));
+    }
+    for (i, line) in req.lines.iter().enumerate() {
+        if line.quantity <= 0 {
+            return Err(AppError::validation("Line quantity must be > 0"));
+        }
+        if line.unit_price.is_sign_negative() {
+            return Err(AppError::validation(
+                "unit_price must be greater than or equal to 0",
+            ));
+        }
+        if req.lines[..i].iter().any(|other| other.product_id == line.product_id) {
+            return Err(AppError::validation(format!(
+                "Product {} appears more than once",
+                line.product_id
+            )));
+        }
+    }
+
+    let mut tx = db_pool.begin().await?;
+
+    ensure_active_supplier(&mut tx, req.supplier_id).await?;
+    for line in &req.lines {
+        supplier_cost_price(&mut tx, req.supplier_id, line.product_id).await?;
+    }
+
+    let po_id = sqlx::query_scalar!(
+        r#"INSERT INTO purchase_orders (po_number, supplier_id, order_date, expected_date, notes, created_by)
+           VALUES ($1, $2, $3, $4, $5, $6)
+           RETURNING id"#,
+        po_number,
+        req.supplier_id,
+        req.order_date,
+        req.expected_date,
+        req.notes,
+        auth.user_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        if let Some(db) = e.as_database_error() {
+            if db.code().as_deref() == Some("23505") {
+                return AppError::conflict("PO number already exists");
+            }
+        }
+        AppError::db(e)
+    })?;
+
+    for line in &req.lines {
+        sqlx::query!(
+            r#"INSERT INTO purchase_order_lines (purchase_order_id, product_id, quantity, unit_price)
+               VALUES ($1, $2, $3, $4)"#,
+            po_id,
+            line.product_id,
+            line.quantity,
+            money::normalize(line.unit_price)
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    let purchase_order = fetch_purchase_order(&db_pool, po_id).await?;
+    Ok((StatusCode::CREATED, Json(purchase_order)))
+}
+
+pub async fn list_purchase_orders(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Query(params): Query<PurchaseOrderListQuery>,
+    Query(page_params): Query<PageParams>,
+) -> Result<Json<Paginated<PurchaseOrderListItem>>, AppError> {
+    let status = filter::one_of("status", params.status.as_deref(), &["open", "closed", "cancelled"])?;
+    let sort = Sort::parse(
+        page_params.sort.as_deref(),
+        &[("order_date", "po.order_date"), ("po_number", "po.po_number"), ("status", "po.status")],
+        "-order_date",
+        "po.id",
+    )?;
+    let page = Page::from_params(&page_params)?;
+
+    let mut filter = ListFilter::new(
+        r#"SELECT po.id, po.po_number, po.supplier_id, s.name as supplier_name,
+                  po.order_date, po.expected_date, po.status,
+                  (SELECT COUNT(*) FROM purchase_order_lines l WHERE l.purchase_order_id = po.id) as line_count,
+                  (SELECT COALESCE(SUM(l.quantity * l.unit_price), 0) FROM purchase_order_lines l
+                   WHERE l.purchase_order_id = po.id) as total_value,
+                  (SELECT COUNT(*) FROM deliveries d WHERE d.purchase_order_id = po.id) as delivery_count
+           FROM purchase_orders po
+           JOIN suppliers s ON po.supplier_id = s.id"#,
+    );
+    filter
+        .eq("po.status", status)
+        .eq("po.supplier_id", params.supplier_id)
+        .gte("po.order_date", params.from)
+        .lte("po.order_date", params.to);
+
+    let (rows, total) = filter::fetch_page::<(
+        i64,
+        String,
+        i64,
+        String,
+        chrono::NaiveDate,
+        Option<chrono::NaiveDate>,
+        String,
+        i64,
+        Decimal,
+        i64,
+    )>(&db_pool, &filter, "", &sort, &page)
+    .await?;
+
+    Ok(Json(page.envelope(
+        rows.into_iter()
+            .map(
+                |(
+                    id,
+                    po_number,
+                    supplier_id,
+                    supplier_name,
+                    order_date,
+                    expected_date,
+                    status,
+                    line_count,
+                    total_value,
+                    delivery_count,
+                )| {
+                    PurchaseOrderListItem {
+                        id,
+                        po_number,
+                        supplier_id,
+                        supplier_name,
+                        order_date,
+                        expected_date,
+                        status,
+                        line_count,
+                        total_value,
+                        delivery_count,
+                    }
+                },
+            )
+            .collect(),
+        total,
+    )))
+}
+
+pub async fn get_purchase_order(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Path(id): Path<i64>,
+) -> Result<Json<PurchaseOrderResponse>, AppError> {
+    fetch_purchase_order(&db_pool, id).await.map(Json)
+}
+
+// No further deliveries can be linked to a closed order
+pub async fn close_purchase_order(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Path(id): Path<i64>,
+) -> Result<Json<PurchaseOrderResponse>, AppError> {
+    let mut tx = db_pool.begin().await?;
+    lock_open_purchase_order(&mut tx, id).await?;
+
+    sqlx::query!(
+        r#"UPDATE purchase_orders SET status = 'closed', closed_at = NOW() WHERE id = $1"#,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    fetch_purchase_order(&db_pool, id).await.map(Json)
+}
+
+// Only an order nothing has been delivered against can be cancelled
+pub async fn cancel_purchase_order(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Path(id): Path<i64>,
+) -> Result<Json<PurchaseOrderResponse>, AppError> {
+    let mut tx = db_pool.begin().await?;
+    lock_open_purchase_order(&mut tx, id).await?;
+
+    let delivered = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM deliveries WHERE purchase_order_id = $1) as "exists!""#,
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if delivered {
+        return Err(AppError::conflict(
+            "Purchase order has deliveries; close it instead",
+        ));
+    }
+
+    sqlx::query!(
+        r#"UPDATE purchase_orders SET status = 'cancelled', closed_at = NOW() WHERE id = $1"#,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    fetch_purchase_order(&db_pool, id).await.map(Json)
+}
+
+// Ordered quantity and price per product against what the linked deliveries brought in
+pub async fn get_purchase_order_comparison(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Path(id): Path<i64>,
+) -> Result<Json<PurchaseOrderComparison>, AppError> {
+    let po = sqlx::query!(
+        r#"SELECT id, po_number, status FROM purchase_orders WHERE id = $1"#,
+        id
+    )
+    .fetch_optional(&db_pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("Purchase order not found"))?;
+
+    let ordered = sqlx::query!(
+        r#"SELECT l.product_id, p.name as product_name, l.quantity, l.unit_price
+           FROM purchase_order_lines l
+           JOIN products p ON l.product_id = p.id
+           WHERE l.purchase_order_id = $1
+           ORDER BY p.name, l.product_id"#,
+        id
+    )
+    .fetch_all(&db_pool)
+    .await?;
+
+    let received = sqlx::query!(
+        r#"SELECT di.product_id, p.name as product_name, d.id as delivery_id, d.delivery_note_number,
+                  d.delivery_date, di.quantity as received_quantity, di.unit_price,
+                  COALESCE((
+                      SELECT SUM(rl.quantity) FROM delivery_reversal_lines rl
+                      WHERE rl.delivery_item_id = di.id
+                  ), 0)::INT as "reversed_quantity!"
+           FROM deliveries d
+           JOIN delivery_items di ON di.delivery_id = d.id
+           JOIN products p ON di.product_id = p.id
+           WHERE d.purchase_order_id = $1
+           ORDER BY d.delivery_date, d.id"#,
+        id
+    )
+    .fetch_all(&db_pool)
+    .await?;
+
+    // Ordered lines first (by product name), then products that were delivered but not ordered
+    let mut lines: Vec<ComparisonLine> = ordered
+        .into_iter()
+        .map(|o| ComparisonLine {
+            product_id: o.product_id,
+            product_name: o.product_name,
+            ordered_quantity: o.quantity,
+            received_quantity: 0,
+            quantity_variance: 0,
+            quantity_status: String::new(),
+            ordered_unit_price: Some(o.unit_price),
+            ordered_value: money::line_amount(o.quantity, o.unit_price),
+            received_value: money::zero(),
+            price_variance: money::zero(),
+            deliveries: Vec::new(),
+        })
+        .collect();
+    let mut unordered: BTreeMap<(String, i64), ComparisonLine> = BTreeMap::new();
+
+    for r in received {
+        // Units a reversal sent back no longer count as received
+        let quantity = r.received_quantity - r.reversed_quantity;
+        let line = match lines.iter_mut().find(|l| l.product_id == r.product_id) {
+            Some(line) => line,
+            None => unordered
+                .entry((r.product_name.clone(), r.product_id))
+                .or_insert_with(|| ComparisonLine {
+                    product_id: r.product_id,
+                    product_name: r.product_name.clone(),
+                    ordered_quantity: 0,
+                    received_quantity: 0,
+                    quantity_variance: 0,
+                    quantity_status: String::new(),
+                    ordered_unit_price: None,
+                    ordered_value: money::zero(),
+                    received_value: money::zero(),
+                    price_variance: money::zero(),
+                    deliveries: Vec::new(),
+                }),
+        };
+        let unit_price_difference = line.ordered_unit_price.map(|ordered| r.unit_price - ordered);
+        line.received_quantity += quantity;
+        line.received_value += money::line_amount(quantity, r.unit_price);
+        if let Some(difference) = unit_price_difference {
+            line.price_variance += money::line_amount(quantity, difference);
+        }
+        line.deliveries.push(ComparisonDelivery {
+            delivery_id: r.delivery_id,
+            delivery_note_number: r.delivery_note_number,
+            delivery_date: r.delivery_date,
+            quantity,
+            reversed_quantity: r.reversed_quantity,
+            unit_price: r.unit_price,
+            unit_price_difference,
+        });
+    }
+    lines.extend(unordered.into_values());
+
+    for line in &mut lines {
+        line.quantity_variance = line.received_quantity - line.ordered_quantity;
+        line.quantity_status = if line.ordered_unit_price.is_none() {
+            "not_ordered"
+        } else if line.quantity_variance < 0 {
+            "short"
+        } else if line.quantity_variance > 0 {
+            "over"
+        } else {
+            "matched"
+        }
+        .to_string();
+    }
+
+    let count = |status: &str| lines.iter().filter(|l| l.quantity_status == status).count() as i32;
+    let summary = ComparisonSummary {
+        ordered_quantity: lines.iter().map(|l| l.ordered_quantity).sum(),
+        received_quantity: lines.iter().map(|l| l.received_quantity).sum(),
+        short_lines: count("short"),
+        over_lines: count("over"),
+        unordered_lines: count("not_ordered"),
+        price_mismatch_lines: lines
+            .iter()
+            .filter(|l| {
+                l.deliveries
+                    .iter()
+                    .any(|d| d.unit_price_difference.is_some_and(|diff| !diff.is_zero()))
+            })
+            .count() as i32,
+        ordered_value: lines.iter().map(|l| l.ordered_value).sum(),
+        received_value: lines.iter().map(|l| l.received_value).sum(),
+        price_variance: lines.iter().map(|l| l.price_variance).sum(),
+    };
+
+    Ok(Json(PurchaseOrderComparison {
+        purchase_order_id: po.id,
+        po_number: po.po_number,
+        status: po.status,
+        summary,
+        lines,
+    }))
+}
+
+// ==================== Helper Functions ====================
+
+/// Lock a purchase order that deliveries may still be linked to (create/update delivery,
+/// close, cancel); refuses closed and cancelled orders. Returns the order's supplier.
+pub async fn lock_open_purchase_order(tx: &mut Transaction<'_, Postgres>, id: i64) -> Result<i64, AppError> {
+    let po = sqlx::query!(r#"SELECT status, supplier_id FROM purchase_orders WHERE id = $1 FOR UPDATE"#, id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| AppError::not_found("Purchase order not found"))?;
+    if po.status != "open" {
+        return Err(AppError::conflict(format!("Purchase order is {}", po.status)));
+    }
+    Ok(po.supplier_id)
+}
+
+async fn fetch_purchase_order(db_pool: &PgPool, id: i64) -> Result<PurchaseOrderResponse, AppError> {
+    let po = sqlx::query!(
+        r#"SELECT po.id, po.po_number, po.supplier_id, s.name as supplier_name,
+                  po.order_date, po.expected_date, po.status, po.notes,
+                  po.created_at, po.closed_at, u.username as "created_by_username?",
+                  ARRAY(SELECT d.id FROM deliveries d
+                        WHERE d.purchase_order_id = po.id ORDER BY d.id) as "delivery_ids!"
+           FROM purchase_orders po
+           JOIN suppliers s ON po.supplier_id = s.id
+           LEFT JOIN users u ON po.created_by = u.id
+           WHERE po.id = $1"#,
+        id
+    )
+    .fetch_optional(db_pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("Purchase order not found"))?;
+
+    let lines: Vec<PurchaseOrderLineResponse> = sqlx::query!(
+        r#"SELECT l.id, l.product_id, p.name as product_name, l.quantity, l.unit_price
+           FROM purchase_order_lines l
+           JOIN products p ON l.product_id = p.id
+           WHERE l.purchase_order_id = $1
+           ORDER BY l.id"#,
+        id
+    )
+    .fetch_all(db_pool)
+    .await?
+    .into_iter()
+    .map(|l| PurchaseOrderLineResponse {
+        id: l.id,
+        product_id: l.product_id,
+        product_name: l.product_name,
+        quantity: l.quantity,
+        unit_price: l.unit_price,
+        line_value: money::line_amount(l.quantity, l.unit_price),
+    })
+    .collect();
+
+    Ok(PurchaseOrderResponse {
+        id: po.id,
+        po_number: po.po_number,
+        supplier_id: po.supplier_id,
+        supplier_name: po.supplier_name,
+        order_date: po.order_date,
+        expected_date: po.expected_date,
+        status: po.status,
+        notes: po.notes,
+        created_by_username: po.created_by_username,
+        created_at: po.created_at,
+        closed_at: po.closed_at,
+        total_value: lines.iter().map(|l| l.line_value).sum(),
+        lines,
+        delivery_ids: po.delivery_ids,
+    })
+}
@@ -1,9 +1,16 @@
 use crate::{
-    dtos::reconciliation::*, error::AppError, middleware::auth::AuthContext, state::AppState,
+    auth::role::Manager,
+    database::filter::{self, ListFilter, Page, Sort},
+    dtos::pagination::{PageParams, Paginated},
+    dtos::reconciliation::*,
+    error::AppError,
+    middleware::auth::RequireRole,
+    money,
+    state::AppState,
 };
 use axum::{
     extract::{Path, State},
-    Extension, Json,
+    Json,
 };
 use chrono::NaiveDate;
 use sqlx::{PgPool, Row};
@@ -12,16 +19,9 @@ use sqlx::{PgPool, Row};
 
 pub async fn start_reconciliation(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
     Json(req): Json<StartReconciliationRequest>,
 ) -> Result<Json<ReconciliationResponse>, AppError> {
-    // Only managers can start reconciliation
-    if auth.role != "manager" {
-        return Err(AppError::forbidden(
-            "Only managers can start reconciliation",
-        ));
-    }
-
     let mut tx = db_pool.begin().await?;
 
     // Check if reconciliation already exists for this date
@@ -52,7 +52,7 @@ pub async fn start_reconciliation(
         r#"INSERT INTO daily_reconciliations 
            (reconciliation_date, status, trucks_out, started_by, notes)
            VALUES ($1, 'in_progress', $2, $3, $4)
-           RETURNING id, started_at"#,
+           RETURNING id"#,
         req.reconciliation_date,
         trucks_out,
         auth.user_id as i32,
@@ -61,41 +61,44 @@ pub async fn start_reconciliation(
     .fetch_one(&mut *tx)
     .await?;
 
-    // Get all truck loads for this date and create reconciliation_items
-    // We need to get the driver from sales since truck_loads doesn't store driver_id
+    // Get all truck loads for this date and create reconciliation_items.
+    // The driver is the truck's assigned driver, else whoever recorded its sales or loaded it.
     let truck_loads = sqlx::query!(
         r#"SELECT 
             tl.id as truck_load_id,
             tl.truck_id,
-            t.truck_number,
-            COALESCE(s.user_id, tl.loaded_by) as "driver_id!",
-            COALESCE(u.username, u2.username) as "driver_username!",
-            COALESCE(SUM(tli.quantity_loaded), 0)::INT as "items_loaded!"
+            tl.status,
+            COALESCE(
+                t.driver_id,
+                (SELECT s.user_id FROM sales s WHERE s.truck_load_id = tl.id ORDER BY s.id LIMIT 1),
+                tl.loaded_by
+            ) as "driver_id!",
+            COALESCE((SELECT SUM(tli.quantity_loaded) FROM truck_load_items tli WHERE tli.truck_load_id = tl.id), 0)::INT as "items_loaded!",
+            COALESCE((SELECT SUM(tli.quantity_returned) FROM truck_load_items tli WHERE tli.truck_load_id = tl.id), 0)::INT as "items_returned!"
            FROM truck_loads tl
            JOIN trucks t ON tl.truck_id = t.id
-           LEFT JOIN sales s ON s.truck_load_id = tl.id AND s.sale_date = $1
-           LEFT JOIN users u ON s.user_id = u.id
-           LEFT JOIN users u2 ON tl.loaded_by = u2.id
-           LEFT JOIN truck_load_items tli ON tl.id = tli.truck_load_id
            WHERE tl.load_date = $1
-           GROUP BY tl.id, tl.truck_id, t.truck_number, s.user_id, tl.loaded_by, u.username, u2.username"#,
+           ORDER BY tl.id
+           FOR UPDATE OF tl"#,
         req.reconciliation_date
     ).fetch_all(&mut *tx).await?;
 
-    let mut truck_items = Vec::new();
-
     for tl in truck_loads {
-        // Get sales and payments for this truck on this date
+        // Get sales and payments for this truck on this date. Items are summed per sale
+        // first so a sale's amounts are counted once, not once per line.
         let sales_data = sqlx::query!(
             r#"SELECT 
-                COALESCE(SUM(si.quantity), 0)::FLOAT8 as "items_sold!",
-                COALESCE(SUM(si.quantity * p.commission_per_unit::FLOAT8), 0)::FLOAT8 as "commission!",
-                COALESCE(SUM(s.total_amount::FLOAT8), 0)::FLOAT8 as "sales_amount!",
-                COALESCE(SUM(s.amount_paid::FLOAT8), 0)::FLOAT8 as "payments!"
+                COALESCE(SUM(si.items_sold), 0)::FLOAT8 as "items_sold!",
+                -- As earned when the sale was recorded; later price changes do not apply
+                COALESCE(SUM(si.commission), 0)::NUMERIC(12,2) as "commission!",
+                COALESCE(SUM(s.total_amount), 0)::NUMERIC(12,2) as "sales_amount!",
+                COALESCE(SUM(s.amount_paid), 0)::NUMERIC(12,2) as "payments!"
                FROM sales s
-               LEFT JOIN sale_items si ON s.id = si.sale_id
-               LEFT JOIN batches b ON si.batch_id = b.id
-               LEFT JOIN products p ON b.product_id = p.id
+               LEFT JOIN (
+                   SELECT sale_id, SUM(quantity) as items_sold, SUM(commission_earned) as commission
+                   FROM sale_items
+                   GROUP BY sale_id
+               ) si ON s.id = si.sale_id
                WHERE s.truck_id = $1 AND s.sale_date = $2"#,
             tl.truck_id,
             req.reconciliation_date
@@ -103,7 +106,7 @@ pub async fn start_reconciliation(
 
         // Get allowance for this truck
         let allowance = sqlx::query_scalar!(
-            r#"SELECT COALESCE((ta.amount)::FLOAT8, 0) as "allowance!"
+            r#"SELECT COALESCE(ta.amount, 0) as "allowance!"
                FROM transport_allowances tallow
                JOIN truck_allowances ta ON tallow.id = ta.transport_allowance_id
                WHERE tallow.allowance_date = $1 AND ta.truck_id = $2"#,
@@ -112,22 +115,28 @@ pub async fn start_reconciliation(
         )
         .fetch_optional(&mut *tx)
         .await?
-        .unwrap_or(0.0);
+        .unwrap_or_else(money::zero);
 
         let items_loaded = tl.items_loaded as f64;
         let items_sold = sales_data.items_sold;
         let pending_payments = sales_data.sales_amount - sales_data.payments;
 
-        // Create reconciliation item
+        // Create reconciliation item; returns and discards are filled in on verification.
+        // A load already returned through PUT /truck-loads/{id}/reconcile (stock restored
+        // there) is carried in as verified with its returned quantities, and finalize
+        // leaves it alone.
+        let already_returned = tl.status == "reconciled";
+        let items_returned = if already_returned { tl.items_returned as f64 } else { 0.0 };
         let item = sqlx::query!(
             r#"INSERT INTO reconciliation_items 
                (reconciliation_id, truck_id, driver_id, truck_load_id, 
                 items_loaded, items_sold, items_returned, items_discarded,
                 sales_amount, commission_earned, allowance_received, 
-                payments_collected, pending_payments)
-               VALUES ($1, $2, $3, $4, ($5)::FLOAT8::NUMERIC, ($6)::FLOAT8::NUMERIC, 0, 0, 
-                       ($7)::FLOAT8::NUMERIC, ($8)::FLOAT8::NUMERIC, ($9)::FLOAT8::NUMERIC, 
-                       ($10)::FLOAT8::NUMERIC, ($11)::FLOAT8::NUMERIC)
+                payments_collected, pending_payments,
+                is_verified, has_discrepancy, discrepancy_notes, verified_at)
+               VALUES ($1, $2, $3, $4, ($5)::FLOAT8::NUMERIC, ($6)::FLOAT8::NUMERIC, ($7)::FLOAT8::NUMERIC, 0,
+                       $8, $9, $10, $11, $12,
+                       $13, $14, $15, CASE WHEN $13 THEN NOW() END)
                RETURNING id"#,
             rec.id,
             tl.truck_id as i32,
@@ -135,83 +144,60 @@ pub async fn start_reconciliation(
             tl.truck_load_id as i32,
             items_loaded,
             items_sold,
+            items_returned,
             sales_data.sales_amount,
             sales_data.commission,
             allowance,
             sales_data.payments,
-            pending_payments
+            pending_payments,
+            already_returned,
+            already_returned && items_loaded - items_sold - items_returned > 0.0,
+            already_returned.then(|| "Returns processed on the truck load before reconciliation".to_string())
         )
         .fetch_one(&mut *tx)
         .await?;
 
-        truck_items.push(TruckVerificationItem {
-            id: item.id as i64,
-            truck_id: tl.truck_id as i64,
-            truck_number: tl.truck_number,
-            driver_id: tl.driver_id as i64,
-            driver_username: tl.driver_username,
-            truck_load_id: tl.truck_load_id as i64,
-            items_loaded,
-            items_sold,
-            items_returned: 0.0,
-            items_discarded: 0.0,
-            is_verified: false,
-            has_discrepancy: false,
-            discrepancy_notes: None,
-            sales_amount: sales_data.sales_amount,
-            commission_earned: sales_data.commission,
-            allowance_received: allowance,
-            payments_collected: sales_data.payments,
-            pending_payments,
-            verified_by: None,
-            verified_at: None,
-        });
+        if already_returned {
+            sqlx::query!(
+                r#"INSERT INTO reconciliation_item_lines
+                   (reconciliation_item_id, truck_load_item_id, batch_id, product_id, line_type, quantity)
+                   SELECT $1, tli.id, tli.batch_id, b.product_id, 'returned', tli.quantity_returned
+                   FROM truck_load_items tli
+                   JOIN batches b ON tli.batch_id = b.id
+                   WHERE tli.truck_load_id = $2 AND tli.quantity_returned > 0"#,
+                item.id,
+                tl.truck_load_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
     }
 
+    sqlx::query!(
+        r#"UPDATE daily_reconciliations 
+           SET trucks_verified = (
+               SELECT COUNT(*)::INT FROM reconciliation_items 
+               WHERE reconciliation_id = $1 AND is_verified = true
+           )
+           WHERE id = $1"#,
+        rec.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
     tx.commit().await?;
 
-    Ok(Json(ReconciliationResponse {
-        id: rec.id as i64,
-        reconciliation_date: req.reconciliation_date,
-        status: "in_progress".to_string(),
-        trucks_out,
-        trucks_verified: 0,
-        total_items_loaded: 0.0,
-        total_items_sold: 0.0,
-        total_items_returned: 0.0,
-        total_items_discarded: 0.0,
-        total_sales_amount: 0.0,
-        total_commission_earned: 0.0,
-        total_allowance_allocated: 0.0,
-        total_payments_collected: 0.0,
-        pending_payments: 0.0,
-        net_profit: 0.0,
-        started_by: Some(auth.user_id),
-        started_by_username: Some(auth.username.clone()),
-        started_at: rec.started_at,
-        finalized_by: None,
-        finalized_by_username: None,
-        finalized_at: None,
-        notes: req.notes,
-        truck_items,
-    }))
+    Ok(Json(fetch_reconciliation(&db_pool, req.reconciliation_date).await?))
 }
 
 // ==================== Verify Truck Return ====================
 
 pub async fn verify_truck_return(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
     Path((date, truck_id)): Path<(NaiveDate, i64)>,
     Json(req): Json<VerifyTruckReturnRequest>,
 ) -> Result<Json<TruckVerificationItem>, AppError> {
-    // Only managers can verify returns
-    if auth.role != "manager" {
-        return Err(AppError::forbidden(
-            "Only managers can verify truck returns",
-        ));
-    }
-
     let mut tx = db_pool.begin().await?;
 
     // Get reconciliation for this date
@@ -225,27 +211,90 @@ pub async fn verify_truck_return(
         return Err(AppError::conflict("Reconciliation is not in progress"));
     }
 
-    // Get reconciliation item for this truck
+    // Get reconciliation item for this truck, locking the load against concurrent sales
     let item = sqlx::query!(
-        r#"SELECT id, (items_loaded)::FLOAT8 as "items_loaded!", (items_sold)::FLOAT8 as "items_sold!" 
-           FROM reconciliation_items 
-           WHERE reconciliation_id = $1 AND truck_id = $2"#,
+        r#"SELECT ri.id, ri.truck_load_id, tl.status as truck_load_status
+           FROM reconciliation_items ri
+           JOIN truck_loads tl ON ri.truck_load_id = tl.id
+           WHERE ri.reconciliation_id = $1 AND ri.truck_id = $2
+           FOR UPDATE OF ri, tl"#,
         rec.id,
         truck_id as i32
     ).fetch_optional(&mut *tx).await?
         .ok_or_else(|| AppError::not_found("Truck not found in this reconciliation"))?;
 
-    // Calculate totals
+    if item.truck_load_status == "reconciled" {
+        return Err(AppError::conflict(
+            "Returns for this truck load were already processed on the truck load",
+        ));
+    }
+
+    // Batches on the truck with what is left of each, earliest expiry first
+    let mut on_truck: Vec<OnTruckBatch> = sqlx::query!(
+        r#"SELECT tli.id, tli.batch_id, b.product_id,
+                  (tli.quantity_loaded - tli.quantity_sold - tli.quantity_returned - tli.quantity_discarded) as "remaining!"
+           FROM truck_load_items tli
+           JOIN batches b ON tli.batch_id = b.id
+           WHERE tli.truck_load_id = $1
+           ORDER BY b.expiry_date ASC, tli.id ASC"#,
+        item.truck_load_id as i64
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|r| OnTruckBatch {
+        truck_load_item_id: r.id,
+        batch_id: r.batch_id,
+        product_id: r.product_id,
+        remaining: r.remaining,
+    })
+    .collect();
+
+    validate_verification_lines(&req, &on_truck)?;
+
+    // Re-verifying replaces the previous lines
+    sqlx::query!(
+        "DELETE FROM reconciliation_item_lines WHERE reconciliation_item_id = $1",
+        item.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Discards are taken from the earliest-expiring batches first, returns from what is left
+    let discards = req
+        .items_discarded
+        .iter()
+        .map(|d| (d.product_id, d.quantity, "discarded", Some(d.reason.as_str())));
+    let returns = req
+        .items_returned
+        .iter()
+        .map(|r| (r.product_id, r.quantity, "returned", None));
+
+    for (product_id, quantity, line_type, reason) in discards.chain(returns) {
+        for (batch, quantity) in allocate_to_batches(&mut on_truck, product_id, quantity) {
+            sqlx::query!(
+                r#"INSERT INTO reconciliation_item_lines
+                   (reconciliation_item_id, truck_load_item_id, batch_id, product_id,
+                    line_type, quantity, discard_reason)
+                   VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+                item.id,
+                batch.truck_load_item_id,
+                batch.batch_id,
+                batch.product_id,
+                line_type,
+                quantity,
+                reason
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
     let total_returned: f64 = req.items_returned.iter().map(|i| i.quantity as f64).sum();
     let total_discarded: f64 = req.items_discarded.iter().map(|i| i.quantity as f64).sum();
 
-    let items_loaded = item.items_loaded;
-    let items_sold = item.items_sold;
-    let expected_return = items_loaded - items_sold;
-    let actual_return = total_returned + total_discarded;
-
-    // Check for discrepancy
-    let has_discrepancy = (expected_return - actual_return).abs() > 0.01;
+    // Anything still on the truck after allocation was neither returned nor discarded
+    let has_discrepancy = on_truck.iter().any(|b| b.remaining > 0);
 
     // Update reconciliation item
     sqlx::query!(
@@ -268,6 +317,14 @@ pub async fn verify_truck_return(
     .execute(&mut *tx)
     .await?;
 
+    // Truck is back and counted; stock moves when the day is finalized
+    sqlx::query!(
+        "UPDATE truck_loads SET status = 'returned' WHERE id = $1",
+        item.truck_load_id as i64
+    )
+    .execute(&mut *tx)
+    .await?;
+
     // Update reconciliation trucks_verified count
     sqlx::query!(
         r#"UPDATE daily_reconciliations 
@@ -291,22 +348,16 @@ pub async fn verify_truck_return(
 
 pub async fn finalize_reconciliation(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
     Path(date): Path<NaiveDate>,
 ) -> Result<Json<ReconciliationResponse>, AppError> {
-    // Only managers can finalize
-    if auth.role != "manager" {
-        return Err(AppError::forbidden(
-            "Only managers can finalize reconciliation",
-        ));
-    }
-
     let mut tx = db_pool.begin().await?;
 
     // Get reconciliation
     let rec = sqlx::query!(
         r#"SELECT id, (status)::TEXT as "status!", trucks_out, trucks_verified FROM daily_reconciliations 
-           WHERE reconciliation_date = $1"#,
+           WHERE reconciliation_date = $1
+           FOR UPDATE"#,
         date
     ).fetch_optional(&mut *tx).await?
         .ok_or_else(|| AppError::not_found("Reconciliation not found"))?;
@@ -323,77 +374,107 @@ pub async fn finalize_reconciliation(
         )));
     }
 
-    // Get all reconciliation items
-    let items = sqlx::query!(
-        r#"SELECT 
-            ri.truck_load_id,
-            (ri.items_returned)::FLOAT8 as "items_returned!",
-            (ri.items_discarded)::FLOAT8 as "items_discarded!",
-            (ri.sales_amount)::FLOAT8 as "sales_amount!",
-            (ri.commission_earned)::FLOAT8 as "commission_earned!",
-            (ri.allowance_received)::FLOAT8 as "allowance_received!",
-            (ri.payments_collected)::FLOAT8 as "payments_collected!",
-            (ri.pending_payments)::FLOAT8 as "pending_payments!"
-           FROM reconciliation_items ri
-           WHERE ri.reconciliation_id = $1"#,
+    // Verified lines, resolved to the truck load batch they came from. Loads already
+    // reconciled on the truck load had their stock restored there and are skipped.
+    let lines = sqlx::query!(
+        r#"SELECT l.truck_load_item_id, l.batch_id, l.product_id, b.batch_number,
+                  l.line_type, l.quantity, l.discard_reason
+           FROM reconciliation_item_lines l
+           JOIN reconciliation_items ri ON l.reconciliation_item_id = ri.id
+           JOIN truck_loads tl ON ri.truck_load_id = tl.id
+           JOIN batches b ON l.batch_id = b.id
+           WHERE ri.reconciliation_id = $1 AND tl.status <> 'reconciled'
+           ORDER BY l.id"#,
         rec.id
     )
     .fetch_all(&mut *tx)
     .await?;
 
-    // Return stock to batches and create stock movements
-    for item in &items {
-        if item.items_returned > 0.0 {
-            // Get truck load items to know which batches to return stock to
-            let truck_items = sqlx::query!(
-                r#"SELECT 
-                    tli.batch_id,
-                    b.product_id,
-                    tli.quantity_loaded as loaded,
-                    tli.quantity_sold as sold,
-                    (tli.quantity_loaded - tli.quantity_sold) as remaining
-                   FROM truck_load_items tli
-                   JOIN batches b ON tli.batch_id = b.id
-                   WHERE tli.truck_load_id = $1 AND (tli.quantity_loaded - tli.quantity_sold) > 0"#,
-                item.truck_load_id as i32
+    for line in &lines {
+        if line.line_type == "returned" {
+            // Returned stock goes back into the batch and counts against the truck load item
+            sqlx::query!(
+                r#"UPDATE batches 
+                   SET remaining_quantity = remaining_quantity + $1
+                   WHERE id = $2"#,
+                line.quantity,
+                line.batch_id
             )
-            .fetch_all(&mut *tx)
+            .execute(&mut *tx)
             .await?;
 
-            for ti in truck_items {
-                let return_qty = ti.remaining.unwrap_or(0);
-
-                // Update batch remaining_quantity
-                sqlx::query!(
-                    r#"UPDATE batches 
-                       SET remaining_quantity = remaining_quantity + $1
-                       WHERE id = $2"#,
-                    return_qty,
-                    ti.batch_id
-                )
-                .execute(&mut *tx)
-                .await?;
-
-                // Log stock movement
-                sqlx::query!(
-                    r#"INSERT INTO stock_movements 
-                       (batch_id, product_id, movement_type, quantity, 
-                        reference_type, reference_id, notes, created_by, movement_date)
-                       VALUES ($1, $2, 'truck_return_in', $3, 'reconciliation', $4, 
-                               'Truck return - end of day reconciliation', $5, $6)"#,
-                    ti.batch_id as i32,
-                    ti.product_id as i32,
-                    return_qty as f64,
-                    rec.id as i32,
-                    auth.user_id as i32,
-                    date
-                )
-                .execute(&mut *tx)
-                .await?;
-            }
+            sqlx::query!(
+                "UPDATE truck_load_items SET quantity_returned = quantity_returned + $1 WHERE id = $2",
+                line.quantity,
+                line.truck_load_item_id
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query!(
+                r#"INSERT INTO stock_movements 
+                   (batch_id, product_id, movement_type, quantity, 
+                    reference_type, reference_id, notes, created_by, movement_date)
+                   VALUES ($1, $2, 'truck_return_in', $3, 'reconciliation', $4, $5, $6, $7)"#,
+                line.batch_id as i32,
+                line.product_id as i32,
+                line.quantity as f64,
+                rec.id as i32,
+                format!("Truck return - end of day reconciliation - Batch: {}", line.batch_number),
+                auth.user_id as i32,
+                date
+            )
+            .execute(&mut *tx)
+            .await?;
+        } else {
+            // Discarded stock left the warehouse with the truck load and does not come back;
+            // the write-off records why (batch_stock_balance does not count it twice)
+            sqlx::query!(
+                "UPDATE truck_load_items SET quantity_discarded = quantity_discarded + $1 WHERE id = $2",
+                line.quantity,
+                line.truck_load_item_id
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            let reason = line.discard_reason.as_deref().unwrap_or("damaged");
+            let movement_type = if reason == "expired" {
+                StockMovementType::ExpiredOut
+            } else {
+                StockMovementType::Adjustment
+            };
+
+            sqlx::query(
+                r#"INSERT INTO stock_movements 
+                   (batch_id, product_id, movement_type, quantity, 
+                    reference_type, reference_id, notes, created_by, movement_date)
+                   VALUES ($1, $2, $3, $4, 'reconciliation', $5, $6, $7, $8)"#,
+            )
+            .bind(line.batch_id as i32)
+            .bind(line.product_id as i32)
+            .bind(movement_type)
+            .bind(line.quantity as f64)
+            .bind(rec.id)
+            .bind(format!(
+                "Discarded on truck ({}) - end of day reconciliation - Batch: {}",
+                reason, line.batch_number
+            ))
+            .bind(auth.user_id as i32)
+            .bind(date)
+            .execute(&mut *tx)
+            .await?;
         }
     }
 
+    // Every truck load in this reconciliation is now settled
+    sqlx::query!(
+        r#"UPDATE truck_loads SET status = 'reconciled'
+           WHERE id IN (SELECT truck_load_id FROM reconciliation_items WHERE reconciliation_id = $1)"#,
+        rec.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
     // Calculate totals
     let totals = sqlx::query!(
         r#"SELECT 
@@ -401,11 +482,11 @@ pub async fn finalize_reconciliation(
             COALESCE(SUM(items_sold), 0)::FLOAT8 as "total_sold!",
             COALESCE(SUM(items_returned), 0)::FLOAT8 as "total_returned!",
             COALESCE(SUM(items_discarded), 0)::FLOAT8 as "total_discarded!",
-            COALESCE(SUM(sales_amount), 0)::FLOAT8 as "total_sales!",
-            COALESCE(SUM(commission_earned), 0)::FLOAT8 as "total_commission!",
-            COALESCE(SUM(allowance_received), 0)::FLOAT8 as "total_allowance!",
-            COALESCE(SUM(payments_collected), 0)::FLOAT8 as "total_payments!",
-            COALESCE(SUM(pending_payments), 0)::FLOAT8 as "total_pending!"
+            COALESCE(SUM(sales_amount), 0) as "total_sales!",
+            COALESCE(SUM(commission_earned), 0) as "total_commission!",
+            COALESCE(SUM(allowance_received), 0) as "total_allowance!",
+            COALESCE(SUM(payments_collected), 0) as "total_payments!",
+            COALESCE(SUM(pending_payments), 0) as "total_pending!"
            FROM reconciliation_items
            WHERE reconciliation_id = $1"#,
         rec.id
@@ -423,12 +504,12 @@ pub async fn finalize_reconciliation(
                total_items_sold = ($2)::FLOAT8::NUMERIC,
                total_items_returned = ($3)::FLOAT8::NUMERIC,
                total_items_discarded = ($4)::FLOAT8::NUMERIC,
-               total_sales_amount = ($5)::FLOAT8::NUMERIC,
-               total_commission_earned = ($6)::FLOAT8::NUMERIC,
-               total_allowance_allocated = ($7)::FLOAT8::NUMERIC,
-               total_payments_collected = ($8)::FLOAT8::NUMERIC,
-               pending_payments = ($9)::FLOAT8::NUMERIC,
-               net_profit = ($10)::FLOAT8::NUMERIC,
+               total_sales_amount = $5,
+               total_commission_earned = $6,
+               total_allowance_allocated = $7,
+               total_payments_collected = $8,
+               pending_payments = $9,
+               net_profit = $10,
                finalized_by = $11,
                finalized_at = NOW()
            WHERE id = $12"#,
@@ -458,10 +539,9 @@ pub async fn finalize_reconciliation(
 
 pub async fn get_reconciliation(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    _: RequireRole<Manager>,
     Path(date): Path<NaiveDate>,
 ) -> Result<Json<ReconciliationResponse>, AppError> {
-    // Managers can see all, drivers can only see if they're involved
     fetch_reconciliation(&db_pool, date).await.map(Json)
 }
 
@@ -469,47 +549,47 @@ pub async fn get_reconciliation(
 
 pub async fn list_reconciliations(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<Vec<ReconciliationSummary>>, AppError> {
-    // Only managers can list all reconciliations
-    if auth.role != "manager" {
-        return Err(AppError::forbidden(
-            "Only managers can list reconciliations",
-        ));
-    }
-
-    let status = params.get("status");
-    let start_date = params
-        .get("start_date")
-        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
-    let end_date = params
-        .get("end_date")
-        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
-
-    let mut query = String::from(
+    _: RequireRole<Manager>,
+    axum::extract::Query(params): axum::extract::Query<ReconciliationListQuery>,
+    axum::extract::Query(page_params): axum::extract::Query<PageParams>,
+) -> Result<Json<Paginated<ReconciliationSummary>>, AppError> {
+    let status = filter::one_of(
+        "status",
+        params.status.as_deref(),
+        &["in_progress", "completed", "finalized"],
+    )?;
+    let sort = Sort::parse(
+        page_params.sort.as_deref(),
+        &[
+            ("reconciliation_date", "reconciliation_date"),
+            ("net_profit", "net_profit"),
+            ("status", "status"),
+        ],
+        "-reconciliation_date",
+        "id",
+    )?;
+    let page = Page::from_params(&page_params)?;
+
+    let mut filter = ListFilter::new(
         r#"SELECT 
             id, reconciliation_date, status, trucks_out, trucks_verified,
-            (net_profit)::FLOAT8 as net_profit,
+            net_profit,
             CASE WHEN net_profit >= 0 THEN 'profit' ELSE 'loss' END as profit_status,
             started_at, finalized_at
-           FROM daily_reconciliations
-           WHERE 1=1"#,
+           FROM daily_reconciliations"#,
     );
 
-    if let Some(s) = status {
-        query.push_str(&format!(" AND status = '{}'", s));
-    }
-    if let Some(sd) = start_date {
-        query.push_str(&format!(" AND reconciliation_date >= '{}'", sd));
-    }
-    if let Some(ed) = end_date {
-        query.push_str(&format!(" AND reconciliation_date <= '{}'", ed));
-    }
-
-    query.push_str(" ORDER BY reconciliation_date DESC");
+    filter
+        .eq("status::TEXT", status)
+        .gte("reconciliation_date", params.start_date)
+        .lte("reconciliation_date", params.end_date);
 
-    let rows = sqlx::query(&query).fetch_all(&db_pool).await?;
+    let rows = filter
+        .page("", &sort, &page)
+        .build()
+        .fetch_all(&db_pool)
+        .await?;
+    let total = filter::count_rows(&db_pool, &filter, "").await?;
 
     let summaries: Vec<ReconciliationSummary> = rows
         .iter()
@@ -526,11 +606,160 @@ pub async fn list_reconciliations(
         })
         .collect();
 
-    Ok(Json(summaries))
+    Ok(Json(page.envelope(summaries, total)))
 }
 
 // ==================== Helper Functions ====================
 
+const DISCARD_REASONS: &[&str] = &["damaged", "expired", "wasted"];
+
+struct OnTruckBatch {
+    truck_load_item_id: i64,
+    batch_id: i64,
+    product_id: i64,
+    remaining: i32,
+}
+
+/// Every line must name a product loaded on this truck, and per product the returned
+/// plus discarded quantities cannot exceed what was loaded and not sold.
+fn validate_verification_lines(
+    req: &VerifyTruckReturnRequest,
+    on_truck: &[OnTruckBatch],
+) -> Result<(), AppError> {
+    let lines = req
+        .items_returned
+        .iter()
+        .map(|r| (r.product_id, r.quantity))
+        .chain(req.items_discarded.iter().map(|d| (d.product_id, d.quantity)));
+
+    let mut requested: std::collections::BTreeMap<i64, i64> = std::collections::BTreeMap::new();
+    for (product_id, quantity) in lines {
+        if quantity <= 0 {
+            return Err(AppError::validation(format!(
+                "Quantity for product {} must be greater than zero",
+                product_id
+            )));
+        }
+        *requested.entry(product_id).or_insert(0) += quantity as i64;
+    }
+
+    if let Some(d) = req.items_discarded.iter().find(|d| !DISCARD_REASONS.contains(&d.reason.as_str())) {
+        return Err(AppError::validation(format!(
+            "Invalid discard reason '{}'. Use: {}",
+            d.reason,
+            DISCARD_REASONS.join(", ")
+        )));
+    }
+
+    for (product_id, quantity) in requested {
+        let mut batches = on_truck.iter().filter(|b| b.product_id == product_id).peekable();
+        if batches.peek().is_none() {
+            return Err(AppError::validation(format!(
+                "Product {} was not loaded on this truck",
+                product_id
+            )));
+        }
+        let available: i64 = batches.map(|b| b.remaining as i64).sum();
+        if quantity > available {
+            return Err(AppError::validation(format!(
+                "Product {}: {} returned and discarded but only {} left on the truck",
+                product_id, quantity, available
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Take `quantity` of a product from the truck's batches in order, reducing what is left.
+fn allocate_to_batches(
+    on_truck: &mut [OnTruckBatch],
+    product_id: i64,
+    mut quantity: i32,
+) -> Vec<(&OnTruckBatch, i32)> {
+    let mut allocated = Vec::new();
+    for batch in on_truck.iter_mut().filter(|b| b.product_id == product_id) {
+        if quantity == 0 {
+            break;
+        }
+        let take = quantity.min(batch.remaining);
+        if take > 0 {
+            batch.remaining -= take;
+            quantity -= take;
+            allocated.push((&*batch, take));
+        }
+    }
+    allocated
+}
+
+async fn fetch_product_verifications(
+    db_pool: &PgPool,
+    reconciliation_item_id: i32,
+    truck_load_id: i64,
+) -> Result<Vec<ProductVerification>, AppError> {
+    let products = sqlx::query!(
+        r#"SELECT b.product_id, p.name as product_name,
+                  SUM(tli.quantity_loaded)::BIGINT as "loaded!",
+                  SUM(tli.quantity_sold)::BIGINT as "sold!"
+           FROM truck_load_items tli
+           JOIN batches b ON tli.batch_id = b.id
+           JOIN products p ON b.product_id = p.id
+           WHERE tli.truck_load_id = $1
+           GROUP BY b.product_id, p.name
+           ORDER BY p.name"#,
+        truck_load_id
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    let lines = sqlx::query!(
+        r#"SELECT l.product_id, l.batch_id, b.batch_number, l.line_type, l.quantity, l.discard_reason
+           FROM reconciliation_item_lines l
+           JOIN batches b ON l.batch_id = b.id
+           WHERE l.reconciliation_item_id = $1
+           ORDER BY l.id"#,
+        reconciliation_item_id
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    Ok(products
+        .into_iter()
+        .map(|p| {
+            let batches: Vec<VerificationBatchLine> = lines
+                .iter()
+                .filter(|l| l.product_id == p.product_id)
+                .map(|l| VerificationBatchLine {
+                    batch_id: l.batch_id,
+                    batch_number: l.batch_number.clone(),
+                    line_type: l.line_type.clone(),
+                    quantity: l.quantity,
+                    discard_reason: l.discard_reason.clone(),
+                })
+                .collect();
+            let sum_of = |line_type: &str| -> i64 {
+                batches
+                    .iter()
+                    .filter(|b| b.line_type == line_type)
+                    .map(|b| b.quantity as i64)
+                    .sum()
+            };
+            let quantity_returned = sum_of("returned");
+            let quantity_discarded = sum_of("discarded");
+            ProductVerification {
+                product_id: p.product_id,
+                product_name: p.product_name,
+                quantity_loaded: p.loaded,
+                quantity_sold: p.sold,
+                quantity_returned,
+                quantity_discarded,
+                quantity_unaccounted: p.loaded - p.sold - quantity_returned - quantity_discarded,
+                batches,
+            }
+        })
+        .collect())
+}
+
 async fn fetch_reconciliation(
     db_pool: &PgPool,
     date: NaiveDate,
@@ -543,12 +772,12 @@ async fn fetch_reconciliation(
             (dr.total_items_sold)::FLOAT8 as "total_items_sold!",
             (dr.total_items_returned)::FLOAT8 as "total_items_returned!",
             (dr.total_items_discarded)::FLOAT8 as "total_items_discarded!",
-            (dr.total_sales_amount)::FLOAT8 as "total_sales_amount!",
-            (dr.total_commission_earned)::FLOAT8 as "total_commission_earned!",
-            (dr.total_allowance_allocated)::FLOAT8 as "total_allowance_allocated!",
-            (dr.total_payments_collected)::FLOAT8 as "total_payments_collected!",
-            (dr.pending_payments)::FLOAT8 as "pending_payments!",
-            (dr.net_profit)::FLOAT8 as "net_profit!",
+            dr.total_sales_amount as "total_sales_amount!",
+            dr.total_commission_earned as "total_commission_earned!",
+            dr.total_allowance_allocated as "total_allowance_allocated!",
+            dr.total_payments_collected as "total_payments_collected!",
+            dr.pending_payments as "pending_payments!",
+            dr.net_profit as "net_profit!",
             dr.started_by, su.username as "started_by_username?", dr.started_at,
             dr.finalized_by, fu.username as "finalized_by_username?", dr.finalized_at,
             dr.notes
@@ -572,11 +801,11 @@ async fn fetch_reconciliation(
             (ri.items_returned)::FLOAT8 as "items_returned!",
             (ri.items_discarded)::FLOAT8 as "items_discarded!",
             ri.is_verified, ri.has_discrepancy, ri.discrepancy_notes,
-            (ri.sales_amount)::FLOAT8 as "sales_amount!",
-            (ri.commission_earned)::FLOAT8 as "commission_earned!",
-            (ri.allowance_received)::FLOAT8 as "allowance_received!",
-            (ri.payments_collected)::FLOAT8 as "payments_collected!",
-            (ri.pending_payments)::FLOAT8 as "pending_payments!",
+            ri.sales_amount as "sales_amount!",
+            ri.commission_earned as "commission_earned!",
+            ri.allowance_received as "allowance_received!",
+            ri.payments_collected as "payments_collected!",
+            ri.pending_payments as "pending_payments!",
             ri.verified_by, ri.verified_at
            FROM reconciliation_items ri
            JOIN trucks t ON ri.truck_id = t.id
@@ -588,9 +817,10 @@ async fn fetch_reconciliation(
     .fetch_all(db_pool)
     .await?;
 
-    let truck_items = items
-        .into_iter()
-        .map(|item| TruckVerificationItem {
+    let mut truck_items = Vec::with_capacity(items.len());
+    for item in items {
+        let products = fetch_product_verifications(db_pool, item.id, item.truck_load_id as i64).await?;
+        truck_items.push(TruckVerificationItem {
             id: item.id as i64,
             truck_id: item.truck_id as i64,
             truck_number: item.truck_number,
@@ -611,8 +841,9 @@ async fn fetch_reconciliation(
             pending_payments: item.pending_payments,
             verified_by: item.verified_by.map(|id| id as i64),
             verified_at: item.verified_at,
-        })
-        .collect();
+            products,
+        });
+    }
 
     Ok(ReconciliationResponse {
         id: rec.id as i64,
@@ -655,11 +886,11 @@ async fn fetch_truck_verification_item(
             (ri.items_returned)::FLOAT8 as "items_returned!",
             (ri.items_discarded)::FLOAT8 as "items_discarded!",
             ri.is_verified, ri.has_discrepancy, ri.discrepancy_notes,
-            (ri.sales_amount)::FLOAT8 as "sales_amount!",
-            (ri.commission_earned)::FLOAT8 as "commission_earned!",
-            (ri.allowance_received)::FLOAT8 as "allowance_received!",
-            (ri.payments_collected)::FLOAT8 as "payments_collected!",
-            (ri.pending_payments)::FLOAT8 as "pending_payments!",
+            ri.sales_amount as "sales_amount!",
+            ri.commission_earned as "commission_earned!",
+            ri.allowance_received as "allowance_received!",
+            ri.payments_collected as "payments_collected!",
+            ri.pending_payments as "pending_payments!",
             ri.verified_by, ri.verified_at
            FROM reconciliation_items ri
            JOIN trucks t ON ri.truck_id = t.id
@@ -672,6 +903,8 @@ async fn fetch_truck_verification_item(
     .await?
     .ok_or_else(|| AppError::not_found("Truck not found in reconciliation"))?;
 
+    let products = fetch_product_verifications(db_pool, item.id, item.truck_load_id as i64).await?;
+
     Ok(Json(TruckVerificationItem {
         id: item.id as i64,
         truck_id: item.truck_id as i64,
@@ -693,5 +926,6 @@ async fn fetch_truck_verification_item(
         pending_payments: item.pending_payments,
         verified_by: item.verified_by.map(|id| id as i64),
         verified_at: item.verified_at,
+        products,
     }))
 }
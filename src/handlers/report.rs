@@ -0,0 +1,203 @@
+use axum::{extract::{Query, State}, Json};
+use crate::auth::role::Manager;
+use crate::dtos::report::{
+    ExpiringBatch, ExpiringProduct, ExpiringReportQuery, ExpiringStockReport, ReorderReport,
+    ReorderReportQuery, ReorderSuggestion,
+};
+use crate::database::cost_layers::{cost_layers, HeldUnits};
+use crate::error::AppError;
+use crate::middleware::auth::RequireRole;
+use crate::money;
+use crate::state::AppState;
+
+const DEFAULT_EXPIRY_WINDOW_DAYS: i32 = 7;
+const MAX_EXPIRY_WINDOW_DAYS: i32 = 365;
+const DEFAULT_SALES_WINDOW_DAYS: i32 = 28;
+const DEFAULT_COVER_DAYS: i32 = 1;
+const MAX_REORDER_DAYS: i32 = 365;
+
+// Batches expiring between today and today + within_days, grouped by product, counting
+// both warehouse stock and units still out on unreconciled truck loads. Units are
+// valued per FIFO cost layer, as in the inventory valuation.
+pub async fn get_expiring_report(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Query(query): Query<ExpiringReportQuery>,
+) -> Result<Json<ExpiringStockReport>, AppError> {
+    let within_days = query.within_days.unwrap_or(DEFAULT_EXPIRY_WINDOW_DAYS);
+    if !(0..=MAX_EXPIRY_WINDOW_DAYS).contains(&within_days) {
+        return Err(AppError::validation(format!(
+            "within_days must be between 0 and {MAX_EXPIRY_WINDOW_DAYS}"
+        )));
+    }
+
+    let as_of = sqlx::query_scalar!(r#"SELECT CURRENT_DATE as "today!""#)
+        .fetch_one(&db_pool)
+        .await?;
+
+    let rows = sqlx::query!(
+        r#"SELECT b.id, b.batch_number, b.product_id, p.name as product_name, b.expiry_date,
+                  (b.expiry_date - $2::DATE) as "days_left!",
+                  b.remaining_quantity,
+                  COALESCE(t.units_on_trucks, 0) as "truck_units!"
+           FROM batches b
+           JOIN products p ON b.product_id = p.id
+           LEFT JOIN batch_units_on_trucks t ON t.batch_id = b.id
+           WHERE b.expiry_date BETWEEN $2 AND $2 + $1::INT
+             AND (b.remaining_quantity > 0 OR t.units_on_trucks > 0)
+           ORDER BY p.name, b.expiry_date, b.id"#,
+        within_days,
+        as_of
+    )
+    .fetch_all(&db_pool)
+    .await?;
+
+    let held: Vec<HeldUnits> = rows
+        .iter()
+        .map(|r| HeldUnits {
+            batch_id: r.id,
+            warehouse_units: r.remaining_quantity,
+            truck_units: r.truck_units,
+        })
+        .collect();
+    let mut conn = db_pool.acquire().await?;
+    let mut layers = cost_layers(&mut conn, as_of, &held).await?;
+
+    let mut products: Vec<ExpiringProduct> = Vec::new();
+    for row in rows {
+        let units = row.remaining_quantity + row.truck_units;
+        let layers = layers.remove(&row.id).unwrap_or_default();
+        let value = layers
+            .iter()
+            .fold(money::zero(), |sum, l| sum + money::line_amount(l.units(), l.unit_cost));
+        let batch = ExpiringBatch {
+            batch_id: row.id,
+            batch_number: row.batch_number,
+            expiry_date: row.expiry_date,
+            days_left: row.days_left,
+            warehouse_units: row.remaining_quantity,
+            truck_units: row.truck_units,
+            unit_cost: money::average_cost(value, units),
+            value,
+        };
+
+        // Rows are ordered by product, so a new product starts a new group
+        match products.last_mut() {
+            Some(product) if product.product_id == row.product_id => {
+                product.warehouse_units += batch.warehouse_units;
+                product.truck_units += batch.truck_units;
+                product.value += value;
+                product.batches.push(batch);
+            }
+            _ => products.push(ExpiringProduct {
+                product_id: row.product_id,
+                product_name: row.product_name,
+                earliest_expiry: batch.expiry_date,
+                warehouse_units: batch.warehouse_units,
+                truck_units: batch.truck_units,
+                value,
+                batches: vec![batch],
+            }),
+        }
+    }
+
+    let total_units = products.iter().map(|p| p.warehouse_units + p.truck_units).sum();
+    let total_value = products.iter().fold(money::zero(), |sum, p| sum + p.value);
+
+    Ok(Json(ExpiringStockReport {
+        as_of,
+        within_days,
+        total_units,
+        total_value,
+        products,
+    }))
+}
+
+// Suggested order quantities for the next delivery. Demand is the average daily
+// volume of sale_items over the window; a product needs reordering once its stock in
+// the warehouse and on trucks is at or below the larger of its configured reorder
+// point and the demand expected during its lead time plus safety stock. The
+// suggestion tops stock up to that level plus `cover_days` of demand.
+pub async fn get_reorder_report(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Query(query): Query<ReorderReportQuery>,
+) -> Result<Json<ReorderReport>, AppError> {
+    let window_days = query.window_days.unwrap_or(DEFAULT_SALES_WINDOW_DAYS);
+    if !(1..=MAX_REORDER_DAYS).contains(&window_days) {
+        return Err(AppError::validation(format!(
+            "window_days must be between 1 and {MAX_REORDER_DAYS}"
+        )));
+    }
+    let cover_days = query.cover_days.unwrap_or(DEFAULT_COVER_DAYS);
+    if !(0..=MAX_REORDER_DAYS).contains(&cover_days) {
+        return Err(AppError::validation(format!(
+            "cover_days must be between 0 and {MAX_REORDER_DAYS}"
+        )));
+    }
+
+    let as_of = sqlx::query_scalar!(r#"SELECT CURRENT_DATE as "today!""#)
+        .fetch_one(&db_pool)
+        .await?;
+
+    // Sales in the window ending today: sale_date in (as_of - window_days, as_of]
+    let rows = sqlx::query!(
+        r#"SELECT p.id, p.name, p.reorder_point, p.safety_stock, p.lead_time_days,
+                  (SELECT COALESCE(SUM(b.remaining_quantity), 0) FROM batches b
+                   WHERE b.product_id = p.id)::BIGINT as "warehouse_units!",
+                  (SELECT COALESCE(SUM(t.units_on_trucks), 0) FROM batch_units_on_trucks t
+                   JOIN batches b ON t.batch_id = b.id
+                   WHERE b.product_id = p.id)::BIGINT as "truck_units!",
+                  (SELECT COALESCE(SUM(si.quantity), 0) FROM sale_items si
+                   JOIN sales s ON si.sale_id = s.id
+                   JOIN batches b ON si.batch_id = b.id
+                   WHERE b.product_id = p.id
+                     AND s.sale_date > $1::DATE - $2::INT AND s.sale_date <= $1)::BIGINT as "units_sold!"
+           FROM products p
+           ORDER BY p.name"#,
+        as_of,
+        window_days
+    )
+    .fetch_all(&db_pool)
+    .await?;
+
+    let products = rows
+        .into_iter()
+        .map(|p| {
+            let avg_daily_sales = p.units_sold as f64 / window_days as f64;
+            let lead_time_demand = (avg_daily_sales * p.lead_time_days as f64).ceil() as i64;
+            let cover_demand = (avg_daily_sales * cover_days as f64).ceil() as i64;
+            let on_hand = p.warehouse_units + p.truck_units;
+            let effective_reorder_point = (p.reorder_point as i64).max(lead_time_demand + p.safety_stock as i64);
+            let needs_reorder = on_hand <= effective_reorder_point && effective_reorder_point + cover_demand > 0;
+            let suggested_order_quantity = if needs_reorder {
+                (effective_reorder_point + cover_demand - on_hand).max(0)
+            } else {
+                0
+            };
+
+            ReorderSuggestion {
+                product_id: p.id,
+                product_name: p.name,
+                warehouse_units: p.warehouse_units,
+                truck_units: p.truck_units,
+                on_hand,
+                units_sold: p.units_sold,
+                avg_daily_sales: (avg_daily_sales * 100.0).round() / 100.0,
+                lead_time_days: p.lead_time_days,
+                safety_stock: p.safety_stock,
+                reorder_point: p.reorder_point,
+                effective_reorder_point,
+                needs_reorder,
+                suggested_order_quantity,
+            }
+        })
+        .collect();
+
+    Ok(Json(ReorderReport {
+        as_of,
+        window_days,
+        cover_days,
+        products,
+    }))
+}
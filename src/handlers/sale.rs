@@ -1,17 +1,22 @@
 use crate::dtos::sale::{
-    CreateSaleRequest, SaleItemResponse, SaleListItem, SaleResponse, SaleSummary,
+    CreateSaleRequest, SaleItemResponse, SaleListItem, SaleListQuery, SaleResponse, SaleSummary,
     UpdatePaymentRequest,
 };
+use crate::database::filter::{self, ListFilter, Page, Sort};
+use crate::dtos::pagination::{PageParams, Paginated};
 use crate::error::AppError;
-use crate::middleware::auth::AuthContext;
+use crate::auth::role::Role;
+use crate::middleware::auth::RequireAnyRole;
+use crate::money;
 use crate::state::AppState;
 use axum::http::StatusCode;
-use axum::{extract::State, Extension, Json};
+use axum::{extract::State, Json};
+use rust_decimal::Decimal;
 use sqlx::PgPool;
 
 pub async fn create_sale(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    RequireAnyRole(auth): RequireAnyRole,
     Json(req): Json<CreateSaleRequest>,
 ) -> Result<(StatusCode, Json<SaleResponse>), AppError> {
     if req.items.is_empty() {
@@ -21,21 +26,31 @@ pub async fn create_sale(
     // Start transaction
     let mut tx = db_pool.begin().await?;
 
-    // Verify truck load exists and get truck info
+    // Verify truck load exists and get truck info; the lock keeps verification and
+    // finalize from settling the load while this sale is recorded
     let truck_load = sqlx::query!(
-        r#"SELECT tl.id, tl.truck_id, t.truck_number, t.driver_id, u.username as driver_username
+        r#"SELECT tl.id, tl.truck_id, tl.status, t.truck_number, t.driver_id, u.username as driver_username
         FROM truck_loads tl
         JOIN trucks t ON tl.truck_id = t.id
         JOIN users u ON t.driver_id = u.id
-        WHERE tl.id = $1"#,
+        WHERE tl.id = $1
+        FOR UPDATE OF tl"#,
         req.truck_load_id
     )
     .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::not_found("Truck load not found"))?;
 
+    // Once the truck is back (returned) or settled (reconciled) its stock is counted
+    if truck_load.status != "loaded" {
+        return Err(AppError::conflict(format!(
+            "Truck load is {}; sales can only be recorded while it is loaded",
+            truck_load.status
+        )));
+    }
+
     // Verify driver can only create sales for their own truck
-    if auth.role == "driver" && truck_load.driver_id != Some(auth.user_id) {
+    if auth.role == Role::Driver && truck_load.driver_id != Some(auth.user_id) {
         return Err(AppError::forbidden(
             "You can only create sales for your own truck",
         ));
@@ -48,7 +63,7 @@ pub async fn create_sale(
         .ok_or_else(|| AppError::not_found("Shop not found"))?;
 
     // Calculate total amount and prepare items
-    let mut total_amount: f64 = 0.0;
+    let mut total_amount = money::zero();
     let mut sale_items = Vec::new();
 
     for item in &req.items {
@@ -56,25 +71,30 @@ pub async fn create_sale(
             return Err(AppError::validation("Quantity must be greater than 0"));
         }
 
-        // Get product info
+        // Get product info with the price and commission in force on the sale date
         let product = sqlx::query!(
-            r#"SELECT id, name, (current_wholesale_price)::FLOAT8 as "current_wholesale_price!", 
-               (commission_per_unit)::FLOAT8 as "commission_per_unit!"
-            FROM products WHERE id = $1"#,
-            item.product_id
+            r#"SELECT p.id, p.name,
+                COALESCE(h.wholesale_price, p.current_wholesale_price) as "wholesale_price!",
+                COALESCE(h.commission_per_unit, p.commission_per_unit) as "commission_per_unit!"
+            FROM products p
+            LEFT JOIN LATERAL product_price_on(p.id, $2) h ON true
+            WHERE p.id = $1"#,
+            item.product_id,
+            req.sale_date
         )
         .fetch_optional(&mut *tx)
         .await?
         .ok_or_else(|| AppError::not_found(&format!("Product {} not found", item.product_id)))?;
 
-        // Use provided unit_price or default to current_wholesale_price
-        let unit_price = item.unit_price.unwrap_or(product.current_wholesale_price);
+        // Use provided unit_price or default to the wholesale price on the sale date
+        let unit_price = money::normalize(item.unit_price.unwrap_or(product.wholesale_price));
 
-        if unit_price < 0.0 {
+        if unit_price.is_sign_negative() {
             return Err(AppError::validation("Unit price cannot be negative"));
         }
 
-        // Find available batch from truck load (FIFO by expiry_date)
+        // Find available batch from truck load (FIFO by expiry_date), skipping batches
+        // already expired on the sale date
         let batch = sqlx::query!(
             r#"SELECT 
                 tli.batch_id,
@@ -87,23 +107,66 @@ pub async fn create_sale(
             JOIN batches b ON tli.batch_id = b.id
             WHERE tli.truck_load_id = $1 
             AND b.product_id = $2
-            AND (tli.quantity_loaded - tli.quantity_sold - tli.quantity_returned) >= $3
+            AND (tli.quantity_loaded - tli.quantity_sold - tli.quantity_returned - tli.quantity_discarded) >= $3
+            AND b.expiry_date >= $4
             ORDER BY b.expiry_date ASC, b.created_at ASC
             LIMIT 1"#,
             req.truck_load_id,
             item.product_id,
-            item.quantity
+            item.quantity,
+            req.sale_date
         )
         .fetch_optional(&mut *tx)
-        .await?
-        .ok_or_else(|| AppError::validation(&format!(
-            "Insufficient quantity for product '{}' in truck load. Need {}, but not enough available.",
-            product.name, item.quantity
-        )))?;
+        .await?;
+        let Some(batch) = batch else {
+            // Say why: enough unexpired units split over several batches, or the truck
+            // still carries the units but they have expired
+            let stock = sqlx::query!(
+                r#"SELECT
+                    COUNT(*) FILTER (WHERE b.expiry_date >= $3)::INT as "unexpired_batches!",
+                    COALESCE(SUM(tli.quantity_loaded - tli.quantity_sold - tli.quantity_returned - tli.quantity_discarded)
+                        FILTER (WHERE b.expiry_date >= $3), 0)::INT as "unexpired_units!",
+                    COUNT(*) FILTER (WHERE b.expiry_date < $3)::INT as "expired_batches!",
+                    COALESCE(SUM(tli.quantity_loaded - tli.quantity_sold - tli.quantity_returned - tli.quantity_discarded)
+                        FILTER (WHERE b.expiry_date < $3), 0)::INT as "expired_units!"
+                FROM truck_load_items tli
+                JOIN batches b ON tli.batch_id = b.id
+                WHERE tli.truck_load_id = $1
+                AND b.product_id = $2
+                AND (tli.quantity_loaded - tli.quantity_sold - tli.quantity_returned - tli.quantity_discarded) > 0"#,
+                req.truck_load_id,
+                item.product_id,
+                req.sale_date
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+
+            if stock.unexpired_units >= item.quantity {
+                return Err(AppError::validation(format!(
+                    "No single batch of product '{}' in the truck load covers {} units: {} unexpired units are split across {} batches",
+                    product.name, item.quantity, stock.unexpired_units, stock.unexpired_batches
+                )));
+            }
+            if stock.expired_units >= item.quantity {
+                return Err(AppError::validation(format!(
+                    "Only expired stock of product '{}' remains in the truck load: {} units in {} batches expired before {}",
+                    product.name, stock.expired_units, stock.expired_batches, req.sale_date
+                )));
+            }
+            let skipped_note = if stock.expired_units > 0 {
+                format!(" ({} expired units in {} batches skipped)", stock.expired_units, stock.expired_batches)
+            } else {
+                String::new()
+            };
+            return Err(AppError::validation(format!(
+                "Insufficient quantity for product '{}' in truck load. Need {} unexpired units, but not enough available{}.",
+                product.name, item.quantity, skipped_note
+            )));
+        };
 
         // Calculate commission (always fixed per unit)
-        let commission_earned = item.quantity as f64 * product.commission_per_unit;
-        let line_total = item.quantity as f64 * unit_price;
+        let commission_earned = money::line_amount(item.quantity, product.commission_per_unit);
+        let line_total = money::line_amount(item.quantity, unit_price);
 
         total_amount += line_total;
 
@@ -120,9 +183,9 @@ pub async fn create_sale(
     }
 
     // Set amount_paid (default to 0 if not provided)
-    let amount_paid = req.amount_paid.unwrap_or(0.0);
+    let amount_paid = money::normalize(req.amount_paid.unwrap_or(Decimal::ZERO));
 
-    if amount_paid < 0.0 {
+    if amount_paid.is_sign_negative() {
         return Err(AppError::validation("Amount paid cannot be negative"));
     }
 
@@ -142,9 +205,9 @@ pub async fn create_sale(
     // Create sale record
     let sale = sqlx::query!(
         r#"INSERT INTO sales (shop_id, truck_id, user_id, truck_load_id, total_amount, amount_paid, payment_status, sale_date)
-        VALUES ($1, $2, $3, $4, $5::FLOAT8, $6::FLOAT8, $7, $8)
-        RETURNING id, shop_id, truck_id, user_id, truck_load_id, (total_amount)::FLOAT8 as "total_amount!", 
-                  (amount_paid)::FLOAT8 as "amount_paid!", payment_status, sale_date, created_at"#,
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING id, shop_id, truck_id, user_id, truck_load_id, total_amount, amount_paid,
+                  payment_status, sale_date, created_at"#,
         req.shop_id,
         truck_load.truck_id,
         auth.user_id,
@@ -159,7 +222,7 @@ pub async fn create_sale(
 
     // Insert sale items and collect response data
     let mut item_responses = Vec::new();
-    let mut total_commission = 0.0;
+    let mut total_commission = money::zero();
 
     for (
         product_id,
@@ -174,7 +237,7 @@ pub async fn create_sale(
     {
         let sale_item = sqlx::query!(
             r#"INSERT INTO sale_items (sale_id, batch_id, quantity, unit_price, commission_earned)
-            VALUES ($1, $2, $3, $4::FLOAT8, $5::FLOAT8)
+            VALUES ($1, $2, $3, $4, $5)
             RETURNING id"#,
             sale.id,
             batch_id,
@@ -185,6 +248,22 @@ pub async fn create_sale(
         .fetch_one(&mut *tx)
         .await?;
 
+        // Create stock movement for sale_out (stock already left the warehouse on truck_load_out)
+        sqlx::query!(
+            r#"INSERT INTO stock_movements
+               (batch_id, product_id, movement_type, quantity, reference_type, reference_id, notes, created_by, movement_date)
+               VALUES ($1, $2, 'sale_out', ($3)::FLOAT8::NUMERIC, 'sale', $4, $5, $6, $7)"#,
+            batch_id as i32,
+            product_id as i32,
+            quantity as f64,
+            sale.id as i32,
+            format!("Sold to {} - Batch: {}", shop.name, batch_number),
+            auth.user_id as i32,
+            req.sale_date
+        )
+        .execute(&mut *tx)
+        .await?;
+
         total_commission += commission;
 
         item_responses.push(SaleItemResponse {
@@ -238,20 +317,28 @@ pub async fn get_sale(
 
 pub async fn list_sales(
     State(AppState { db_pool }): State<AppState>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<Vec<SaleListItem>>, AppError> {
-    let driver_id = params.get("driver_id").and_then(|s| s.parse::<i64>().ok());
-    let shop_id = params.get("shop_id").and_then(|s| s.parse::<i64>().ok());
-    let sale_date = params
-        .get("sale_date")
-        .and_then(|s| s.parse::<chrono::NaiveDate>().ok());
-    let payment_status = params.get("payment_status");
-
-    let mut query_str = String::from(
+    axum::extract::Query(params): axum::extract::Query<SaleListQuery>,
+    axum::extract::Query(page_params): axum::extract::Query<PageParams>,
+) -> Result<Json<Paginated<SaleListItem>>, AppError> {
+    let payment_status = filter::one_of("payment_status", params.payment_status.as_deref(), &["paid", "pending"])?;
+    let sort = Sort::parse(
+        page_params.sort.as_deref(),
+        &[
+            ("sale_date", "s.sale_date"),
+            ("total_amount", "s.total_amount"),
+            ("amount_paid", "s.amount_paid"),
+            ("shop_name", "sh.name"),
+        ],
+        "-sale_date",
+        "s.id",
+    )?;
+    let page = Page::from_params(&page_params)?;
+
+    let mut filter = ListFilter::new(
         r#"SELECT 
             s.id, s.sale_date, s.payment_status,
-            (s.total_amount)::FLOAT8 as total_amount,
-            (s.amount_paid)::FLOAT8 as amount_paid,
+            s.total_amount,
+            s.amount_paid,
             sh.name as shop_name,
             t.truck_number,
             u.username as driver_username,
@@ -260,75 +347,35 @@ pub async fn list_sales(
         JOIN shops sh ON s.shop_id = sh.id
         JOIN trucks t ON s.truck_id = t.id
         JOIN users u ON s.user_id = u.id
-        LEFT JOIN sale_items si ON s.id = si.sale_id
-        WHERE 1=1"#,
+        LEFT JOIN sale_items si ON s.id = si.sale_id"#,
     );
 
-    if driver_id.is_some() {
-        query_str.push_str(" AND s.user_id = $1");
-    }
-    if shop_id.is_some() {
-        let param_num = if driver_id.is_some() { 2 } else { 1 };
-        query_str.push_str(&format!(" AND s.shop_id = ${}", param_num));
-    }
-    if sale_date.is_some() {
-        let param_num = if driver_id.is_some() && shop_id.is_some() {
-            3
-        } else if driver_id.is_some() || shop_id.is_some() {
-            2
-        } else {
-            1
-        };
-        query_str.push_str(&format!(" AND s.sale_date = ${}", param_num));
-    }
-    if payment_status.is_some() {
-        let param_num = if driver_id.is_some() && shop_id.is_some() && sale_date.is_some() {
-            4
-        } else if (driver_id.is_some() as u8 + shop_id.is_some() as u8 + sale_date.is_some() as u8)
-            == 2
-        {
-            3
-        } else if driver_id.is_some() || shop_id.is_some() || sale_date.is_some() {
-            2
-        } else {
-            1
-        };
-        query_str.push_str(&format!(" AND s.payment_status = ${}", param_num));
-    }
-
-    query_str.push_str(" GROUP BY s.id, s.sale_date, s.payment_status, s.total_amount, s.amount_paid, sh.name, t.truck_number, u.username ORDER BY s.sale_date DESC, s.id DESC");
-
-    let mut query = sqlx::query_as::<
-        _,
-        (
-            i64,
-            chrono::NaiveDate,
-            String,
-            f64,
-            f64,
-            String,
-            String,
-            String,
-            i32,
-        ),
-    >(&query_str);
-
-    if let Some(did) = driver_id {
-        query = query.bind(did);
-    }
-    if let Some(sid) = shop_id {
-        query = query.bind(sid);
-    }
-    if let Some(date) = sale_date {
-        query = query.bind(date);
-    }
-    if let Some(status) = payment_status {
-        query = query.bind(status);
-    }
-
-    let sales = query.fetch_all(&db_pool).await?;
+    filter
+        .eq("s.user_id", params.driver_id)
+        .eq("s.shop_id", params.shop_id)
+        .eq("s.sale_date", params.sale_date)
+        .eq("s.payment_status", payment_status);
+
+    let (sales, total) = filter::fetch_page::<(
+        i64,
+        chrono::NaiveDate,
+        String,
+        Decimal,
+        Decimal,
+        String,
+        String,
+        String,
+        i32,
+    )>(
+        &db_pool,
+        &filter,
+        "GROUP BY s.id, s.sale_date, s.payment_status, s.total_amount, s.amount_paid, sh.name, t.truck_number, u.username",
+        &sort,
+        &page,
+    )
+    .await?;
 
-    Ok(Json(
+    Ok(Json(page.envelope(
         sales
             .into_iter()
             .map(
@@ -357,16 +404,18 @@ pub async fn list_sales(
                 },
             )
             .collect(),
-    ))
+        total,
+    )))
 }
 
 pub async fn update_payment(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    RequireAnyRole(auth): RequireAnyRole,
     axum::extract::Path(id): axum::extract::Path<i64>,
     Json(req): Json<UpdatePaymentRequest>,
 ) -> Result<Json<SaleResponse>, AppError> {
-    if req.additional_payment <= 0.0 {
+    let additional_payment = money::normalize(req.additional_payment);
+    if additional_payment <= Decimal::ZERO {
         return Err(AppError::validation(
             "Additional payment must be greater than 0",
         ));
@@ -377,8 +426,7 @@ pub async fn update_payment(
 
     // Get sale and verify ownership if driver
     let sale = sqlx::query!(
-        r#"SELECT s.id, s.user_id, s.truck_id, (s.total_amount)::FLOAT8 as "total_amount!", 
-           (s.amount_paid)::FLOAT8 as "amount_paid!"
+        r#"SELECT s.id, s.user_id, s.truck_id, s.total_amount, s.amount_paid
         FROM sales s
         WHERE s.id = $1"#,
         id
@@ -388,13 +436,13 @@ pub async fn update_payment(
     .ok_or_else(|| AppError::not_found("Sale not found"))?;
 
     // If driver, verify they own this sale
-    if auth.role == "driver" && sale.user_id != auth.user_id {
+    if auth.role == Role::Driver && sale.user_id != auth.user_id {
         return Err(AppError::forbidden(
             "You can only update payments for your own sales",
         ));
     }
 
-    let new_amount_paid = sale.amount_paid + req.additional_payment;
+    let new_amount_paid = sale.amount_paid + additional_payment;
 
     if new_amount_paid > sale.total_amount {
         return Err(AppError::validation(&format!(
@@ -412,7 +460,7 @@ pub async fn update_payment(
 
     sqlx::query!(
         r#"UPDATE sales 
-        SET amount_paid = $2::FLOAT8, payment_status = $3
+        SET amount_paid = $2, payment_status = $3
         WHERE id = $1"#,
         id,
         new_amount_paid,
@@ -434,8 +482,7 @@ async fn fetch_sale_by_id(db_pool: &PgPool, id: i64) -> Result<SaleResponse, App
     let sale = sqlx::query!(
         r#"SELECT 
             s.id, s.shop_id, s.truck_id, s.user_id, s.truck_load_id, s.sale_date,
-            (s.total_amount)::FLOAT8 as "total_amount!",
-            (s.amount_paid)::FLOAT8 as "amount_paid!",
+            s.total_amount, s.amount_paid,
             s.payment_status, s.created_at,
             sh.name as shop_name,
             t.truck_number,
@@ -455,8 +502,7 @@ async fn fetch_sale_by_id(db_pool: &PgPool, id: i64) -> Result<SaleResponse, App
     let items_data = sqlx::query!(
         r#"SELECT 
             si.id, si.batch_id, si.quantity,
-            (si.unit_price)::FLOAT8 as "unit_price!",
-            (si.commission_earned)::FLOAT8 as "commission_earned!",
+            si.unit_price, si.commission_earned,
             b.batch_number, b.product_id,
             p.name as product_name
         FROM sale_items si
@@ -470,14 +516,14 @@ async fn fetch_sale_by_id(db_pool: &PgPool, id: i64) -> Result<SaleResponse, App
     .await?;
 
     let mut total_items = 0;
-    let mut total_commission = 0.0;
+    let mut total_commission = money::zero();
 
     let items: Vec<SaleItemResponse> = items_data
         .into_iter()
         .map(|item| {
             total_items += item.quantity;
             total_commission += item.commission_earned;
-            let line_total = item.quantity as f64 * item.unit_price;
+            let line_total = money::line_amount(item.quantity, item.unit_price);
 
             SaleItemResponse {
                 id: item.id,
@@ -1,19 +1,18 @@
 use crate::dtos::shop::{CreateShopRequest, ShopResponse, ShopSummary, UpdateShopRequest};
+use crate::database::filter::{self, ListFilter, Page, Sort};
+use crate::dtos::pagination::{PageParams, Paginated};
 use crate::error::AppError;
-use crate::middleware::auth::AuthContext;
+use crate::auth::role::Manager;
+use crate::middleware::auth::RequireRole;
 use crate::state::AppState;
 use axum::http::StatusCode;
-use axum::{extract::State, Extension, Json};
+use axum::{extract::State, Json};
 
 pub async fn create_shop(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    _: RequireRole<Manager>,
     Json(req): Json<CreateShopRequest>,
 ) -> Result<(StatusCode, Json<ShopResponse>), AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden("Only managers can create shops"));
-    }
-
     if req.name.trim().is_empty() {
         return Err(AppError::validation("Shop name is required"));
     }
@@ -84,38 +83,42 @@ pub async fn get_shop(
 
 pub async fn list_shops(
     State(AppState { db_pool }): State<AppState>,
-) -> Result<Json<Vec<ShopSummary>>, AppError> {
-    let shops = sqlx::query!(
-        r#"SELECT id, name, location, (distance)::FLOAT8 as "distance?"
-        FROM shops
-        ORDER BY name ASC"#
+    axum::extract::Query(page_params): axum::extract::Query<PageParams>,
+) -> Result<Json<Paginated<ShopSummary>>, AppError> {
+    let sort = Sort::parse(
+        page_params.sort.as_deref(),
+        &[("name", "name"), ("distance", "distance"), ("created_at", "created_at")],
+        "name",
+        "id",
+    )?;
+    let page = Page::from_params(&page_params)?;
+
+    let filter = ListFilter::new("SELECT id, name, location, (distance)::FLOAT8 AS distance FROM shops");
+    let (shops, total) = filter::fetch_page::<(i64, String, Option<String>, Option<f64>)>(
+        &db_pool, &filter, "", &sort, &page,
     )
-    .fetch_all(&db_pool)
     .await?;
 
-    Ok(Json(
+    Ok(Json(page.envelope(
         shops
             .into_iter()
-            .map(|s| ShopSummary {
-                id: s.id,
-                name: s.name,
-                location: s.location,
-                distance: s.distance,
+            .map(|(id, name, location, distance)| ShopSummary {
+                id,
+                name,
+                location,
+                distance,
             })
             .collect(),
-    ))
+        total,
+    )))
 }
 
 pub async fn update_shop(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    _: RequireRole<Manager>,
     axum::extract::Path(id): axum::extract::Path<i64>,
     Json(req): Json<UpdateShopRequest>,
 ) -> Result<Json<ShopResponse>, AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden("Only managers can update shops"));
-    }
-
     // Validate distance is not negative
     if let Some(dist) = req.distance {
         if dist < 0.0 {
@@ -166,13 +169,9 @@ pub async fn update_shop(
 
 pub async fn delete_shop(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    _: RequireRole<Manager>,
     axum::extract::Path(id): axum::extract::Path<i64>,
 ) -> Result<StatusCode, AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden("Only managers can delete shops"));
-    }
-
     // Check if shop has sales
     let has_sales = sqlx::query_scalar!(
         r#"SELECT EXISTS(SELECT 1 FROM sales WHERE shop_id = $1) as "exists!""#,
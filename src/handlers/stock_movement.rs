@@ -1,10 +1,16 @@
 use crate::{
-    dtos::reconciliation::*, error::AppError, middleware::auth::AuthContext, state::AppState,
+    auth::role::Manager,
+    database::filter::{self, ListFilter, Page, Sort},
+    dtos::pagination::{PageParams, Paginated},
+    dtos::reconciliation::*,
+    error::AppError,
+    middleware::auth::{RequireAnyRole, RequireRole},
+    state::AppState,
 };
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    Extension, Json,
+    Json,
 };
 use chrono::NaiveDate;
 use sqlx::Row;
@@ -13,7 +19,7 @@ use sqlx::Row;
 
 pub async fn get_batch_movements(
     State(AppState { db_pool }): State<AppState>,
-    Extension(_auth): Extension<AuthContext>,
+    _: RequireAnyRole,
     Path(batch_id): Path<i64>,
 ) -> Result<Json<BatchMovementHistory>, AppError> {
     // Get batch details
@@ -41,11 +47,7 @@ pub async fn get_batch_movements(
             u.username as "created_by?",
             sm.movement_date,
             SUM(
-                CASE 
-                    WHEN sm.movement_type IN ('delivery_in', 'truck_return_in', 'adjustment') 
-                    THEN (sm.quantity)::FLOAT8
-                    ELSE -(sm.quantity)::FLOAT8
-                END
+                stock_movement_effect(sm.movement_type, sm.reference_type, sm.quantity)::FLOAT8
             ) OVER (ORDER BY sm.created_at, sm.id) as "running_balance!"
            FROM stock_movements sm
            LEFT JOIN users u ON sm.created_by = u.id
@@ -86,7 +88,7 @@ pub async fn get_batch_movements(
 
 pub async fn get_daily_movements(
     State(AppState { db_pool }): State<AppState>,
-    Extension(_auth): Extension<AuthContext>,
+    _: RequireAnyRole,
     Path(date): Path<NaiveDate>,
 ) -> Result<Json<DailyStockSummary>, AppError> {
     let movements = sqlx::query!(
@@ -138,23 +140,147 @@ pub async fn get_daily_movements(
     }))
 }
 
+// ==================== Point-in-time Balances ====================
+
+// Today's date from the database, the default for the point-in-time endpoints
+async fn database_today(db_pool: &sqlx::PgPool) -> Result<NaiveDate, AppError> {
+    let today = sqlx::query_scalar!(r#"SELECT CURRENT_DATE as "today!""#)
+        .fetch_one(db_pool)
+        .await?;
+    Ok(today)
+}
+
+// Warehouse stock per batch at the end of a day, computed only from stock_movements
+pub async fn get_stock_balance(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireAnyRole,
+    Query(query): Query<StockBalanceQuery>,
+) -> Result<Json<StockBalanceAsOf>, AppError> {
+    let today = database_today(&db_pool).await?;
+    let date = query.date.unwrap_or(today);
+    if date > today {
+        return Err(AppError::validation("date cannot be in the future"));
+    }
+
+    if let Some(batch_id) = query.batch_id {
+        sqlx::query_scalar!(r#"SELECT id FROM batches WHERE id = $1"#, batch_id)
+            .fetch_optional(&db_pool)
+            .await?
+            .ok_or_else(|| AppError::not_found("Batch not found"))?;
+    }
+
+    // A requested batch is listed even at zero; otherwise only batches holding stock
+    let rows = sqlx::query!(
+        r#"SELECT b.id, b.batch_number, b.product_id, p.name as product_name, b.expiry_date,
+                  bal.balance as "balance!"
+           FROM batch_balance_as_of($1) bal
+           JOIN batches b ON bal.batch_id = b.id
+           JOIN products p ON b.product_id = p.id
+           WHERE ($2::BIGINT IS NULL OR b.product_id = $2)
+             AND ($3::BIGINT IS NULL OR b.id = $3)
+             AND (bal.balance <> 0 OR $3::BIGINT IS NOT NULL)
+           ORDER BY p.name, b.expiry_date, b.id"#,
+        date,
+        query.product_id,
+        query.batch_id
+    )
+    .fetch_all(&db_pool)
+    .await?;
+
+    let batches: Vec<BatchBalanceAsOf> = rows
+        .into_iter()
+        .map(|r| BatchBalanceAsOf {
+            batch_id: r.id,
+            batch_number: r.batch_number,
+            product_id: r.product_id,
+            product_name: r.product_name,
+            expiry_date: r.expiry_date,
+            balance: r.balance,
+        })
+        .collect();
+
+    Ok(Json(StockBalanceAsOf {
+        date,
+        product_id: query.product_id,
+        batch_id: query.batch_id,
+        total_units: batches.iter().map(|b| b.balance as i64).sum(),
+        batches,
+    }))
+}
+
+// Ledger vs batches.remaining_quantity: every movement of a batch, whatever its
+// date, must add up to the live balance. Balances on a past day come from
+// get_stock_balance; nothing recorded what remaining_quantity was back then.
+pub async fn check_stock_balances(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Query(query): Query<BalanceCheckQuery>,
+) -> Result<Json<LedgerBalanceCheck>, AppError> {
+    let mismatches_only = query.mismatches_only.unwrap_or(true);
+
+    let rows = sqlx::query!(
+        r#"SELECT b.id, b.batch_number, b.product_id, p.name as product_name,
+                  COALESCE((
+                      SELECT SUM(stock_movement_effect(sm.movement_type, sm.reference_type, sm.quantity))
+                      FROM stock_movements sm
+                      WHERE sm.batch_id = b.id
+                  ), 0)::INT as "ledger_balance!",
+                  b.remaining_quantity
+           FROM batches b
+           JOIN products p ON b.product_id = p.id
+           ORDER BY p.name, b.expiry_date, b.id"#
+    )
+    .fetch_all(&db_pool)
+    .await?;
+
+    let batches_checked = rows.len() as i64;
+    let batches: Vec<BatchBalanceCheck> = rows
+        .into_iter()
+        .map(|r| BatchBalanceCheck {
+            batch_id: r.id,
+            batch_number: r.batch_number,
+            product_id: r.product_id,
+            product_name: r.product_name,
+            ledger_balance: r.ledger_balance,
+            remaining_quantity: r.remaining_quantity,
+            difference: r.remaining_quantity - r.ledger_balance,
+            matches: r.remaining_quantity == r.ledger_balance,
+        })
+        .collect();
+    let mismatches = batches.iter().filter(|b| !b.matches).count() as i64;
+
+    Ok(Json(LedgerBalanceCheck {
+        batches_checked,
+        mismatches,
+        batches: batches
+            .into_iter()
+            .filter(|b| !mismatches_only || !b.matches)
+            .collect(),
+    }))
+}
+
 // ==================== Get Product Movements ====================
 
 pub async fn get_product_movements(
     State(AppState { db_pool }): State<AppState>,
-    Extension(_auth): Extension<AuthContext>,
+    _: RequireAnyRole,
     Path(product_id): Path<i64>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<Vec<StockMovementResponse>>, AppError> {
-    let start_date = params
-        .get("start_date")
-        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
-    let end_date = params
-        .get("end_date")
-        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
-    let movement_type = params.get("movement_type");
-
-    let mut query = String::from(
+    axum::extract::Query(params): axum::extract::Query<ProductMovementQuery>,
+    axum::extract::Query(page_params): axum::extract::Query<PageParams>,
+) -> Result<Json<Paginated<StockMovementResponse>>, AppError> {
+    let sort = Sort::parse(
+        page_params.sort.as_deref(),
+        &[
+            ("created_at", "sm.created_at"),
+            ("movement_date", "sm.movement_date"),
+            ("quantity", "sm.quantity"),
+        ],
+        "-created_at",
+        "sm.id",
+    )?;
+    let page = Page::from_params(&page_params)?;
+
+    let mut filter = ListFilter::new(
         r#"SELECT 
             sm.id, sm.batch_id, sm.product_id, p.name as product_name,
             sm.movement_type::TEXT as movement_type,
@@ -165,24 +291,21 @@ pub async fn get_product_movements(
             sm.movement_date, sm.created_at
            FROM stock_movements sm
            JOIN products p ON sm.product_id = p.id
-           LEFT JOIN users u ON sm.created_by = u.id
-           WHERE sm.product_id = "#,
+           LEFT JOIN users u ON sm.created_by = u.id"#,
     );
-    query.push_str(&product_id.to_string());
 
-    if let Some(sd) = start_date {
-        query.push_str(&format!(" AND sm.movement_date >= '{}'", sd));
-    }
-    if let Some(ed) = end_date {
-        query.push_str(&format!(" AND sm.movement_date <= '{}'", ed));
-    }
-    if let Some(mt) = movement_type {
-        query.push_str(&format!(" AND sm.movement_type::TEXT = '{}'", mt));
-    }
-
-    query.push_str(" ORDER BY sm.created_at DESC");
+    filter
+        .eq("sm.product_id", Some(product_id as i32))
+        .gte("sm.movement_date", params.start_date)
+        .lte("sm.movement_date", params.end_date)
+        .eq("sm.movement_type", params.movement_type);
 
-    let rows = sqlx::query(&query).fetch_all(&db_pool).await?;
+    let rows = filter
+        .page("", &sort, &page)
+        .build()
+        .fetch_all(&db_pool)
+        .await?;
+    let total = filter::count_rows(&db_pool, &filter, "").await?;
 
     let movements: Vec<StockMovementResponse> = rows
         .iter()
@@ -216,23 +339,16 @@ pub async fn get_product_movements(
         })
         .collect();
 
-    Ok(Json(movements))
+    Ok(Json(page.envelope(movements, total)))
 }
 
 // ==================== Create Stock Adjustment ====================
 
 pub async fn create_stock_adjustment(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
     Json(req): Json<CreateStockAdjustmentRequest>,
 ) -> Result<(StatusCode, Json<StockMovementResponse>), AppError> {
-    // Only managers can create adjustments
-    if auth.role != "manager" {
-        return Err(AppError::forbidden(
-            "Only managers can create stock adjustments",
-        ));
-    }
-
     // Validate movement type - only adjustment and expired_out are allowed
     use crate::dtos::reconciliation::StockMovementType;
     match req.movement_type {
@@ -0,0 +1,395 @@
+use axum::{extract::{Path, Query, State}, http::StatusCode, Json};
+use sqlx::PgPool;
+use crate::auth::role::Manager;
+use crate::database::filter::{self, ListFilter, Page, Sort};
+use crate::dtos::pagination::{PageParams, Paginated};
+use crate::dtos::stocktake::{
+    CreateStocktakeRequest, RecordCountsRequest, StocktakeLine, StocktakeListItem,
+    StocktakeListQuery, StocktakeResponse, StocktakeSummary,
+};
+use crate::error::AppError;
+use crate::middleware::auth::{RequireAnyRole, RequireRole};
+use crate::state::AppState;
+
+pub async fn create_stocktake(
+    State(AppState { db_pool }): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
+    Json(req): Json<CreateStocktakeRequest>,
+) -> Result<(StatusCode, Json<StocktakeResponse>), AppError> {
+    let mut product_ids = req.product_ids.clone();
+    product_ids.sort_unstable();
+    product_ids.dedup();
+    if product_ids.is_empty() {
+        return Err(AppError::validation("A stocktake must include at least one product"));
+    }
+
+    let known = sqlx::query_scalar!(
+        r#"SELECT id FROM products WHERE id = ANY($1)"#,
+        &product_ids
+    )
+    .fetch_all(&db_pool)
+    .await?;
+    if let Some(missing) = product_ids.iter().find(|id| !known.contains(id)) {
+        return Err(AppError::not_found(format!("Product {} not found", missing)));
+    }
+
+    let mut tx = db_pool.begin().await?;
+
+    let stocktake_id = sqlx::query_scalar!(
+        r#"INSERT INTO stocktakes (notes, opened_by) VALUES ($1, $2) RETURNING id"#,
+        req.notes,
+        auth.user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"INSERT INTO stocktake_products (stocktake_id, product_id)
+           SELECT $1, UNNEST($2::BIGINT[])"#,
+        stocktake_id,
+        &product_ids
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let stocktake = fetch_stocktake(&db_pool, stocktake_id).await?;
+    Ok((StatusCode::CREATED, Json(stocktake)))
+}
+
+pub async fn list_stocktakes(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireAnyRole,
+    Query(params): Query<StocktakeListQuery>,
+    Query(page_params): Query<PageParams>,
+) -> Result<Json<Paginated<StocktakeListItem>>, AppError> {
+    let status = filter::one_of("status", params.status.as_deref(), &["open", "posted", "cancelled"])?;
+    let sort = Sort::parse(
+        page_params.sort.as_deref(),
+        &[("opened_at", "s.opened_at"), ("status", "s.status")],
+        "-opened_at",
+        "s.id",
+    )?;
+    let page = Page::from_params(&page_params)?;
+
+    let mut filter = ListFilter::new(
+        r#"SELECT s.id, s.status, u.username as opened_by_username, s.opened_at, s.closed_at,
+                  (SELECT COUNT(*) FROM stocktake_products sp WHERE sp.stocktake_id = s.id) as product_count,
+                  (SELECT COUNT(*) FROM stocktake_counts c WHERE c.stocktake_id = s.id) as counted_batches
+           FROM stocktakes s
+           LEFT JOIN users u ON s.opened_by = u.id"#,
+    );
+    filter.eq("s.status", status);
+
+    let (rows, total) = filter::fetch_page::<(
+        i64,
+        String,
+        Option<String>,
+        chrono::DateTime<chrono::Utc>,
+        Option<chrono::DateTime<chrono::Utc>>,
+        i64,
+        i64,
+    )>(&db_pool, &filter, "", &sort, &page)
+    .await?;
+
+    Ok(Json(page.envelope(
+        rows.into_iter()
+            .map(
+                |(id, status, opened_by_username, opened_at, closed_at, product_count, counted_batches)| {
+                    StocktakeListItem {
+                        id,
+                        status,
+                        opened_by_username,
+                        opened_at,
+                        closed_at,
+                        product_count,
+                        counted_batches,
+                    }
+                },
+            )
+            .collect(),
+        total,
+    )))
+}
+
+pub async fn get_stocktake(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireAnyRole,
+    Path(id): Path<i64>,
+) -> Result<Json<StocktakeResponse>, AppError> {
+    fetch_stocktake(&db_pool, id).await.map(Json)
+}
+
+// Record counted quantities; any user may count, the latest count of a batch wins.
+// Each count keeps the batch balance it was taken against as its system quantity.
+pub async fn record_counts(
+    State(AppState { db_pool }): State<AppState>,
+    RequireAnyRole(auth): RequireAnyRole,
+    Path(id): Path<i64>,
+    Json(req): Json<RecordCountsRequest>,
+) -> Result<Json<StocktakeResponse>, AppError> {
+    if req.counts.is_empty() {
+        return Err(AppError::validation("At least one count is required"));
+    }
+
+    let mut tx = db_pool.begin().await?;
+
+    // Shared lock: counters can work in parallel, posting waits for them
+    let status = sqlx::query_scalar!(r#"SELECT status FROM stocktakes WHERE id = $1 FOR SHARE"#, id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found("Stocktake not found"))?;
+    if status != "open" {
+        return Err(AppError::conflict(format!("Stocktake is {status} and can no longer be counted")));
+    }
+
+    for count in &req.counts {
+        if count.counted_quantity < 0 {
+            return Err(AppError::validation("Counted quantity cannot be negative"));
+        }
+
+        // Shared lock so the system quantity is the balance the count was taken against
+        let batch = sqlx::query!(
+            r#"SELECT b.id, b.batch_number, b.product_id, b.remaining_quantity,
+                      EXISTS (SELECT 1 FROM stocktake_products sp
+                              WHERE sp.stocktake_id = $2 AND sp.product_id = b.product_id) as "in_scope!"
+               FROM batches b WHERE b.id = $1
+               FOR SHARE OF b"#,
+            count.batch_id,
+            id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Batch {} not found", count.batch_id)))?;
+
+        if !batch.in_scope {
+            return Err(AppError::validation(format!(
+                "Batch {} is not for a product in this stocktake",
+                batch.batch_number
+            )));
+        }
+
+        sqlx::query!(
+            r#"INSERT INTO stocktake_counts (stocktake_id, batch_id, product_id, counted_quantity, counted_by, system_quantity)
+               VALUES ($1, $2, $3, $4, $5, $6)
+               ON CONFLICT (stocktake_id, batch_id) DO UPDATE
+               SET counted_quantity = EXCLUDED.counted_quantity,
+                   counted_by = EXCLUDED.counted_by,
+                   counted_at = NOW(),
+                   system_quantity = EXCLUDED.system_quantity"#,
+            id,
+            batch.id,
+            batch.product_id,
+            count.counted_quantity,
+            auth.user_id,
+            batch.remaining_quantity
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    fetch_stocktake(&db_pool, id).await.map(Json)
+}
+
+// Post every counted variance as an adjustment and freeze the session
+pub async fn post_stocktake(
+    State(AppState { db_pool }): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
+    Path(id): Path<i64>,
+) -> Result<Json<StocktakeResponse>, AppError> {
+    let mut tx = db_pool.begin().await?;
+
+    let status = sqlx::query_scalar!(r#"SELECT status FROM stocktakes WHERE id = $1 FOR UPDATE"#, id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found("Stocktake not found"))?;
+    if status != "open" {
+        return Err(AppError::conflict(format!("Stocktake is already {status}")));
+    }
+
+    // Variances are against the balance each count was taken at, so sales, loads or
+    // deliveries since then are not booked as shrinkage. Batches locked while they change.
+    let counts = sqlx::query!(
+        r#"SELECT c.id, c.batch_id, c.product_id, c.counted_quantity,
+                  c.system_quantity, b.batch_number, b.remaining_quantity
+           FROM stocktake_counts c
+           JOIN batches b ON c.batch_id = b.id
+           WHERE c.stocktake_id = $1
+           ORDER BY c.batch_id
+           FOR UPDATE OF b"#,
+        id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if counts.is_empty() {
+        return Err(AppError::validation("Nothing has been counted in this stocktake"));
+    }
+
+    for count in &counts {
+        let variance = count.counted_quantity - count.system_quantity;
+        if count.remaining_quantity + variance < 0 {
+            return Err(AppError::conflict(format!(
+                "Batch {}: a shortfall of {} counted against {} units, but only {} are left now; recount the batch",
+                count.batch_number, -variance, count.system_quantity, count.remaining_quantity
+            )));
+        }
+
+        sqlx::query!(
+            r#"UPDATE stocktake_counts SET variance = $2 WHERE id = $1"#,
+            count.id,
+            variance
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if variance == 0 {
+            continue;
+        }
+
+        // Same as a manual adjustment: quantity moves with remaining_quantity
+        sqlx::query!(
+            r#"UPDATE batches
+               SET quantity = quantity + $2,
+                   remaining_quantity = remaining_quantity + $2
+               WHERE id = $1"#,
+            count.batch_id,
+            variance
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"INSERT INTO stock_movements
+               (batch_id, product_id, movement_type, quantity, reference_type, reference_id, notes, created_by, movement_date)
+               VALUES ($1, $2, 'adjustment', ($3)::FLOAT8::NUMERIC, 'stocktake', $4, $5, $6, CURRENT_DATE)"#,
+            count.batch_id as i32,
+            count.product_id as i32,
+            variance as f64,
+            id as i32,
+            format!(
+                "Stocktake #{} - counted {}, system {} - Batch: {}",
+                id, count.counted_quantity, count.system_quantity, count.batch_number
+            ),
+            auth.user_id as i32
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query!(
+        r#"UPDATE stocktakes SET status = 'posted', closed_by = $2, closed_at = NOW() WHERE id = $1"#,
+        id,
+        auth.user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    fetch_stocktake(&db_pool, id).await.map(Json)
+}
+
+pub async fn cancel_stocktake(
+    State(AppState { db_pool }): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
+    Path(id): Path<i64>,
+) -> Result<Json<StocktakeResponse>, AppError> {
+    let result = sqlx::query!(
+        r#"UPDATE stocktakes SET status = 'cancelled', closed_by = $2, closed_at = NOW()
+           WHERE id = $1 AND status = 'open'"#,
+        id,
+        auth.user_id
+    )
+    .execute(&db_pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        let status = sqlx::query_scalar!(r#"SELECT status FROM stocktakes WHERE id = $1"#, id)
+            .fetch_optional(&db_pool)
+            .await?
+            .ok_or_else(|| AppError::not_found("Stocktake not found"))?;
+        return Err(AppError::conflict(format!("Stocktake is already {status}")));
+    }
+
+    fetch_stocktake(&db_pool, id).await.map(Json)
+}
+
+// ==================== Helper Functions ====================
+
+/// Session with one line per batch: while open, every in-stock batch of the counted
+/// products (plus counted ones); once closed, the counts as posted. Counted batches show
+/// the balance at the count, uncounted ones their live balance.
+async fn fetch_stocktake(db_pool: &PgPool, id: i64) -> Result<StocktakeResponse, AppError> {
+    let stocktake = sqlx::query!(
+        r#"SELECT s.id, s.status, s.notes, s.opened_at, s.closed_at,
+                  o.username as "opened_by_username?", c.username as "closed_by_username?",
+                  ARRAY(SELECT sp.product_id FROM stocktake_products sp
+                        WHERE sp.stocktake_id = s.id ORDER BY sp.product_id) as "product_ids!"
+           FROM stocktakes s
+           LEFT JOIN users o ON s.opened_by = o.id
+           LEFT JOIN users c ON s.closed_by = c.id
+           WHERE s.id = $1"#,
+        id
+    )
+    .fetch_optional(db_pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("Stocktake not found"))?;
+
+    let lines: Vec<StocktakeLine> = sqlx::query!(
+        r#"SELECT b.id, b.batch_number, b.product_id, p.name as product_name, b.expiry_date,
+                  CASE WHEN c.id IS NULL THEN b.remaining_quantity ELSE c.system_quantity END as system_quantity,
+                  c.counted_quantity as "counted_quantity?",
+                  u.username as "counted_by_username?",
+                  c.counted_at as "counted_at?"
+           FROM stocktakes s
+           JOIN stocktake_products sp ON sp.stocktake_id = s.id
+           JOIN batches b ON b.product_id = sp.product_id
+           JOIN products p ON b.product_id = p.id
+           LEFT JOIN stocktake_counts c ON c.stocktake_id = s.id AND c.batch_id = b.id
+           LEFT JOIN users u ON c.counted_by = u.id
+           WHERE s.id = $1
+             AND ((s.status = 'open' AND b.remaining_quantity > 0) OR c.id IS NOT NULL)
+           ORDER BY p.name, b.expiry_date, b.id"#,
+        id
+    )
+    .fetch_all(db_pool)
+    .await?
+    .into_iter()
+    .map(|r| StocktakeLine {
+        batch_id: r.id,
+        batch_number: r.batch_number,
+        product_id: r.product_id,
+        product_name: r.product_name,
+        expiry_date: r.expiry_date,
+        system_quantity: r.system_quantity,
+        counted_quantity: r.counted_quantity,
+        variance: r.counted_quantity.zip(r.system_quantity).map(|(counted, system)| counted - system),
+        counted_by_username: r.counted_by_username,
+        counted_at: r.counted_at,
+    })
+    .collect();
+
+    let summary = StocktakeSummary {
+        batches: lines.len() as i32,
+        counted_batches: lines.iter().filter(|l| l.counted_quantity.is_some()).count() as i32,
+        batches_with_variance: lines.iter().filter(|l| l.variance.is_some_and(|v| v != 0)).count() as i32,
+        net_variance: lines.iter().filter_map(|l| l.variance).sum(),
+    };
+
+    Ok(StocktakeResponse {
+        id: stocktake.id,
+        status: stocktake.status,
+        notes: stocktake.notes,
+        opened_by_username: stocktake.opened_by_username,
+        opened_at: stocktake.opened_at,
+        closed_by_username: stocktake.closed_by_username,
+        closed_at: stocktake.closed_at,
+        product_ids: stocktake.product_ids,
+        summary,
+        lines,
+    })
+}
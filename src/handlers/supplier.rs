@@ -0,0 +1,271 @@
+use axum::{extract::{Path, Query, State}, http::StatusCode, Json};
+use rust_decimal::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
+use crate::auth::role::Manager;
+use crate::database::filter::{self, ListFilter, Page, Sort};
+use crate::dtos::pagination::{PageParams, Paginated};
+use crate::dtos::supplier::{
+    CreateSupplierRequest, SetSupplierPriceRequest, SupplierListQuery, SupplierProductPrice,
+    SupplierResponse, SupplierSummary, UpdateSupplierRequest,
+};
+use crate::error::AppError;
+use crate::middleware::auth::RequireRole;
+use crate::money;
+use crate::state::AppState;
+
+pub async fn create_supplier(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Json(req): Json<CreateSupplierRequest>,
+) -> Result<(StatusCode, Json<SupplierResponse>), AppError> {
+    if req.name.trim().is_empty() {
+        return Err(AppError::validation("Supplier name is required"));
+    }
+
+    let id = sqlx::query_scalar!(
+        r#"INSERT INTO suppliers (name, contact_name, phone, email)
+           VALUES ($1, $2, $3, $4)
+           RETURNING id"#,
+        req.name.trim(),
+        req.contact_name,
+        req.phone,
+        req.email
+    )
+    .fetch_one(&db_pool)
+    .await
+    .map_err(|e| {
+        if let Some(db) = e.as_database_error() {
+            if db.code().as_deref() == Some("23505") {
+                return AppError::conflict("Supplier name already exists");
+            }
+        }
+        AppError::db(e)
+    })?;
+
+    let supplier = fetch_supplier(&db_pool, id).await?;
+    Ok((StatusCode::CREATED, Json(supplier)))
+}
+
+pub async fn list_suppliers(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Query(params): Query<SupplierListQuery>,
+    Query(page_params): Query<PageParams>,
+) -> Result<Json<Paginated<SupplierSummary>>, AppError> {
+    let sort = Sort::parse(
+        page_params.sort.as_deref(),
+        &[("name", "s.name"), ("created_at", "s.created_at")],
+        "name",
+        "s.id",
+    )?;
+    let page = Page::from_params(&page_params)?;
+
+    let mut filter = ListFilter::new(
+        r#"SELECT s.id, s.name, s.contact_name, s.phone, s.is_active,
+                  (SELECT COUNT(*) FROM supplier_products sp WHERE sp.supplier_id = s.id) as product_count
+           FROM suppliers s"#,
+    );
+    filter.eq("s.is_active", params.is_active);
+
+    let (rows, total) = filter::fetch_page::<(i64, String, Option<String>, Option<String>, bool, i64)>(
+        &db_pool, &filter, "", &sort, &page,
+    )
+    .await?;
+
+    Ok(Json(page.envelope(
+        rows.into_iter()
+            .map(|(id, name, contact_name, phone, is_active, product_count)| SupplierSummary {
+                id,
+                name,
+                contact_name,
+                phone,
+                is_active,
+                product_count,
+            })
+            .collect(),
+        total,
+    )))
+}
+
+pub async fn get_supplier(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Path(id): Path<i64>,
+) -> Result<Json<SupplierResponse>, AppError> {
+    fetch_supplier(&db_pool, id).await.map(Json)
+}
+
+pub async fn update_supplier(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Path(id): Path<i64>,
+    Json(req): Json<UpdateSupplierRequest>,
+) -> Result<Json<SupplierResponse>, AppError> {
+    if req.name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return Err(AppError::validation("Supplier name cannot be empty"));
+    }
+
+    let result = sqlx::query!(
+        r#"UPDATE suppliers SET
+            name = COALESCE($2, name),
+            contact_name = COALESCE($3, contact_name),
+            phone = COALESCE($4, phone),
+            email = COALESCE($5, email),
+            is_active = COALESCE($6, is_active)
+        WHERE id = $1"#,
+        id,
+        req.name.as_deref().map(|s| s.trim()),
+        req.contact_name,
+        req.phone,
+        req.email,
+        req.is_active
+    )
+    .execute(&db_pool)
+    .await
+    .map_err(|e| {
+        if let Some(db) = e.as_database_error() {
+            if db.code().as_deref() == Some("23505") {
+                return AppError::conflict("Supplier name already exists");
+            }
+        }
+        AppError::db(e)
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Supplier not found"));
+    }
+
+    fetch_supplier(&db_pool, id).await.map(Json)
+}
+
+// Add a product to the supplier's price list or change its cost price
+pub async fn set_supplier_price(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Path((id, product_id)): Path<(i64, i64)>,
+    Json(req): Json<SetSupplierPriceRequest>,
+) -> Result<Json<SupplierResponse>, AppError> {
+    if req.cost_price.is_sign_negative() {
+        return Err(AppError::validation(
+            "cost_price must be greater than or equal to 0",
+        ));
+    }
+
+    sqlx::query!(
+        r#"INSERT INTO supplier_products (supplier_id, product_id, cost_price)
+           VALUES ($1, $2, $3)
+           ON CONFLICT (supplier_id, product_id) DO UPDATE
+           SET cost_price = EXCLUDED.cost_price, updated_at = NOW()"#,
+        id,
+        product_id,
+        money::normalize(req.cost_price)
+    )
+    .execute(&db_pool)
+    .await
+    .map_err(|e| {
+        if let Some(db) = e.as_database_error() {
+            if db.code().as_deref() == Some("23503") {
+                return AppError::not_found("Supplier or product not found");
+            }
+        }
+        AppError::db(e)
+    })?;
+
+    fetch_supplier(&db_pool, id).await.map(Json)
+}
+
+pub async fn remove_supplier_price(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Path((id, product_id)): Path<(i64, i64)>,
+) -> Result<StatusCode, AppError> {
+    let result = sqlx::query!(
+        r#"DELETE FROM supplier_products WHERE supplier_id = $1 AND product_id = $2"#,
+        id,
+        product_id
+    )
+    .execute(&db_pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Product is not on this supplier's price list"));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ==================== Helper Functions ====================
+
+/// Refuse new deliveries and purchase orders for unknown or inactive suppliers.
+pub async fn ensure_active_supplier(tx: &mut Transaction<'_, Postgres>, id: i64) -> Result<(), AppError> {
+    let is_active = sqlx::query_scalar!(r#"SELECT is_active FROM suppliers WHERE id = $1"#, id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| AppError::validation(format!("Supplier {} not found", id)))?;
+    if !is_active {
+        return Err(AppError::conflict(format!("Supplier {} is inactive", id)));
+    }
+    Ok(())
+}
+
+/// The supplier's cost price for a product (None when it is only the product's primary
+/// supplier without a price). Products the supplier does not supply are refused, so one
+/// brand's stock cannot be received under another supplier.
+pub async fn supplier_cost_price(
+    tx: &mut Transaction<'_, Postgres>,
+    supplier_id: i64,
+    product_id: i64,
+) -> Result<Option<Decimal>, AppError> {
+    let product = sqlx::query!(
+        r#"SELECT p.supplier_id, sp.cost_price as "cost_price?"
+           FROM products p
+           LEFT JOIN supplier_products sp ON sp.product_id = p.id AND sp.supplier_id = $1
+           WHERE p.id = $2"#,
+        supplier_id,
+        product_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or_else(|| AppError::validation(format!("Invalid product_id {}", product_id)))?;
+
+    if product.cost_price.is_none() && product.supplier_id != Some(supplier_id) {
+        return Err(AppError::validation(format!(
+            "Product {} is not supplied by supplier {}",
+            product_id, supplier_id
+        )));
+    }
+    Ok(product.cost_price)
+}
+
+async fn fetch_supplier(db_pool: &PgPool, id: i64) -> Result<SupplierResponse, AppError> {
+    let supplier = sqlx::query!(
+        r#"SELECT id, name, contact_name, phone, email, is_active, created_at
+           FROM suppliers WHERE id = $1"#,
+        id
+    )
+    .fetch_optional(db_pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("Supplier not found"))?;
+
+    let products = sqlx::query_as!(
+        SupplierProductPrice,
+        r#"SELECT sp.product_id, p.name as product_name, sp.cost_price, sp.updated_at
+           FROM supplier_products sp
+           JOIN products p ON sp.product_id = p.id
+           WHERE sp.supplier_id = $1
+           ORDER BY p.name"#,
+        id
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    Ok(SupplierResponse {
+        id: supplier.id,
+        name: supplier.name,
+        contact_name: supplier.contact_name,
+        phone: supplier.phone,
+        email: supplier.email,
+        is_active: supplier.is_active,
+        created_at: supplier.created_at,
+        products,
+    })
+}
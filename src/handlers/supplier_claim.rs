@@ -0,0 +1,160 @@
+use axum::{extract::{Path, Query, State}, Json};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use crate::auth::role::Manager;
+use crate::database::filter::{self, ListFilter, Page, Sort};
+use crate::dtos::pagination::{PageParams, Paginated};
+use crate::dtos::supplier_claim::{
+    ResolveClaimRequest, SupplierClaimLine, SupplierClaimListItem, SupplierClaimListQuery,
+    SupplierClaimResponse,
+};
+use crate::error::AppError;
+use crate::middleware::auth::RequireRole;
+use crate::state::AppState;
+
+pub async fn list_supplier_claims(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Query(params): Query<SupplierClaimListQuery>,
+    Query(page_params): Query<PageParams>,
+) -> Result<Json<Paginated<SupplierClaimListItem>>, AppError> {
+    let status = filter::one_of("status", params.status.as_deref(), &["open", "settled", "rejected"])?;
+    let sort = Sort::parse(
+        page_params.sort.as_deref(),
+        &[("created_at", "c.created_at"), ("total_value", "c.total_value"), ("status", "c.status")],
+        "-created_at",
+        "c.id",
+    )?;
+    let page = Page::from_params(&page_params)?;
+
+    let mut filter = ListFilter::new(
+        r#"SELECT c.id, c.delivery_id, d.delivery_note_number, c.supplier_id, s.name as supplier_name,
+                  c.status, c.total_value, c.created_at
+           FROM supplier_claims c
+           JOIN deliveries d ON c.delivery_id = d.id
+           JOIN suppliers s ON c.supplier_id = s.id"#,
+    );
+    filter
+        .eq("c.status", status)
+        .eq("c.supplier_id", params.supplier_id);
+
+    let (rows, total) = filter::fetch_page::<(
+        i64,
+        i64,
+        String,
+        i64,
+        String,
+        String,
+        Decimal,
+        chrono::DateTime<chrono::Utc>,
+    )>(&db_pool, &filter, "", &sort, &page)
+    .await?;
+
+    Ok(Json(page.envelope(
+        rows.into_iter()
+            .map(
+                |(id, delivery_id, delivery_note_number, supplier_id, supplier_name, status, total_value, created_at)| {
+                    SupplierClaimListItem {
+                        id,
+                        delivery_id,
+                        delivery_note_number,
+                        supplier_id,
+                        supplier_name,
+                        status,
+                        total_value,
+                        created_at,
+                    }
+                },
+            )
+            .collect(),
+        total,
+    )))
+}
+
+pub async fn get_supplier_claim(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Path(id): Path<i64>,
+) -> Result<Json<SupplierClaimResponse>, AppError> {
+    fetch_supplier_claim(&db_pool, id).await.map(Json)
+}
+
+// Record the supplier's answer to an open claim
+pub async fn resolve_supplier_claim(
+    State(AppState { db_pool }): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
+    Path(id): Path<i64>,
+    Json(req): Json<ResolveClaimRequest>,
+) -> Result<Json<SupplierClaimResponse>, AppError> {
+    let status = filter::one_of("status", Some(req.status.as_str()), &["settled", "rejected"])?;
+
+    let result = sqlx::query!(
+        r#"UPDATE supplier_claims
+           SET status = $2, notes = COALESCE($3, notes), resolved_by = $4, resolved_at = NOW()
+           WHERE id = $1 AND status = 'open'"#,
+        id,
+        status,
+        req.notes,
+        auth.user_id
+    )
+    .execute(&db_pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        let status = sqlx::query_scalar!(r#"SELECT status FROM supplier_claims WHERE id = $1"#, id)
+            .fetch_optional(&db_pool)
+            .await?
+            .ok_or_else(|| AppError::not_found("Supplier claim not found"))?;
+        return Err(AppError::conflict(format!("Supplier claim is already {status}")));
+    }
+
+    fetch_supplier_claim(&db_pool, id).await.map(Json)
+}
+
+// ==================== Helper Functions ====================
+
+async fn fetch_supplier_claim(db_pool: &PgPool, id: i64) -> Result<SupplierClaimResponse, AppError> {
+    let claim = sqlx::query!(
+        r#"SELECT c.id, c.delivery_id, d.delivery_note_number, d.delivery_date, c.supplier_id,
+                  s.name as supplier_name, c.status, c.total_value, c.notes, c.created_at,
+                  u.username as "resolved_by_username?", c.resolved_at
+           FROM supplier_claims c
+           JOIN deliveries d ON c.delivery_id = d.id
+           JOIN suppliers s ON c.supplier_id = s.id
+           LEFT JOIN users u ON c.resolved_by = u.id
+           WHERE c.id = $1"#,
+        id
+    )
+    .fetch_optional(db_pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("Supplier claim not found"))?;
+
+    let lines = sqlx::query_as!(
+        SupplierClaimLine,
+        r#"SELECT l.id, l.delivery_item_id, l.product_id, p.name as product_name, l.claim_type,
+                  l.quantity, l.unit_price, l.value, l.reason
+           FROM supplier_claim_lines l
+           JOIN products p ON l.product_id = p.id
+           WHERE l.claim_id = $1
+           ORDER BY l.delivery_item_id, l.claim_type"#,
+        id
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    Ok(SupplierClaimResponse {
+        id: claim.id,
+        delivery_id: claim.delivery_id,
+        delivery_note_number: claim.delivery_note_number,
+        delivery_date: claim.delivery_date,
+        supplier_id: claim.supplier_id,
+        supplier_name: claim.supplier_name,
+        status: claim.status,
+        total_value: claim.total_value,
+        notes: claim.notes,
+        created_at: claim.created_at,
+        resolved_by_username: claim.resolved_by_username,
+        resolved_at: claim.resolved_at,
+        lines,
+    })
+}
@@ -1,22 +1,21 @@
 use crate::dtos::truck::{
     CreateTruckRequest, TruckResponse, TruckSummary, UpdateTruckMaxLimitRequest, UpdateTruckRequest,
 };
+use crate::database::filter::{self, ListFilter, Page, Sort};
+use crate::dtos::pagination::{PageParams, Paginated};
 use crate::error::AppError;
-use crate::middleware::auth::AuthContext;
+use crate::auth::role::Manager;
+use crate::middleware::auth::RequireRole;
+use crate::money;
 use crate::state::AppState;
-use axum::extract::Extension;
 use axum::http::StatusCode;
 use axum::{extract::State, Json};
 
 pub async fn create_truck(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    _: RequireRole<Manager>,
     Json(req): Json<CreateTruckRequest>,
 ) -> Result<(StatusCode, Json<TruckResponse>), AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden("Only managers can create trucks"));
-    }
-
     if req.truck_number.trim().is_empty() {
         return Err(AppError::validation("Truck number is required"));
     }
@@ -38,7 +37,7 @@ pub async fn create_truck(
     let truck = sqlx::query!(
         r#"INSERT INTO trucks (truck_number, driver_id)
         VALUES ($1, $2)
-        RETURNING id, truck_number, driver_id, is_active, (max_allowance_limit)::FLOAT8 as "max_allowance_limit!", created_at"#,
+        RETURNING id, truck_number, driver_id, is_active, max_allowance_limit as "max_allowance_limit!", created_at"#,
         req.truck_number.trim(),
         req.driver_id
     )
@@ -90,7 +89,7 @@ pub async fn get_truck(
 ) -> Result<Json<TruckResponse>, AppError> {
     let truck = sqlx::query!(
         r#"SELECT t.id, t.truck_number, t.driver_id, t.is_active, 
-        (t.max_allowance_limit)::FLOAT8 as "max_allowance_limit!", 
+        t.max_allowance_limit as "max_allowance_limit!", 
         t.created_at, u.username as "driver_username?"
         FROM trucks t
         LEFT JOIN users u ON t.driver_id = u.id
@@ -114,46 +113,53 @@ pub async fn get_truck(
 
 pub async fn list_trucks(
     State(AppState { db_pool }): State<AppState>,
-) -> Result<Json<Vec<TruckSummary>>, AppError> {
-    let trucks = sqlx::query!(
-        r#"SELECT t.id, t.truck_number, t.is_active, u.username as "driver_username?"
+    axum::extract::Query(page_params): axum::extract::Query<PageParams>,
+) -> Result<Json<Paginated<TruckSummary>>, AppError> {
+    let sort = Sort::parse(
+        page_params.sort.as_deref(),
+        &[("truck_number", "t.truck_number"), ("is_active", "t.is_active")],
+        "truck_number",
+        "t.id",
+    )?;
+    let page = Page::from_params(&page_params)?;
+
+    let filter = ListFilter::new(
+        r#"SELECT t.id, t.truck_number, t.is_active, u.username AS driver_username
         FROM trucks t
-        LEFT JOIN users u ON t.driver_id = u.id
-        ORDER BY t.truck_number ASC"#
+        LEFT JOIN users u ON t.driver_id = u.id"#,
+    );
+    let (trucks, total) = filter::fetch_page::<(i64, String, bool, Option<String>)>(
+        &db_pool, &filter, "", &sort, &page,
     )
-    .fetch_all(&db_pool)
     .await?;
 
-    Ok(Json(
+    Ok(Json(page.envelope(
         trucks
             .into_iter()
-            .map(|t| TruckSummary {
-                id: t.id,
-                truck_number: t.truck_number,
-                driver_username: t.driver_username,
-                is_active: t.is_active,
+            .map(|(id, truck_number, is_active, driver_username)| TruckSummary {
+                id,
+                truck_number,
+                driver_username,
+                is_active,
             })
             .collect(),
-    ))
+        total,
+    )))
 }
 
 pub async fn update_truck(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    _: RequireRole<Manager>,
     axum::extract::Path(id): axum::extract::Path<i64>,
     Json(req): Json<UpdateTruckRequest>,
 ) -> Result<Json<TruckResponse>, AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden("Only managers can update trucks"));
-    }
-
     // Check if truck exists
     let existing_truck = sqlx::query!("SELECT driver_id FROM trucks WHERE id = $1", id)
         .fetch_optional(&db_pool)
         .await?
         .ok_or_else(|| AppError::not_found("Truck not found"))?;
 
-    let mut truck_number = req.truck_number;
+    let truck_number = req.truck_number;
     let mut driver_id = existing_truck.driver_id;
     let mut is_active = None;
 
@@ -186,7 +192,7 @@ pub async fn update_truck(
             is_active = COALESCE($4, is_active)
         WHERE id = $1
         RETURNING id, truck_number, driver_id, is_active, 
-        (max_allowance_limit)::FLOAT8 as "max_allowance_limit!", created_at"#,
+        max_allowance_limit as "max_allowance_limit!", created_at"#,
         id,
         truck_number.as_deref().map(|s| s.trim()),
         driver_id,
@@ -235,13 +241,9 @@ pub async fn update_truck(
 
 pub async fn delete_truck(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    _: RequireRole<Manager>,
     axum::extract::Path(id): axum::extract::Path<i64>,
 ) -> Result<StatusCode, AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden("Only managers can delete trucks"));
-    }
-
     // Check if truck has sales
     let has_sales = sqlx::query_scalar!(
         r#"SELECT EXISTS(SELECT 1 FROM sales WHERE truck_id = $1) as "exists!""#,
@@ -283,17 +285,11 @@ pub async fn delete_truck(
 
 pub async fn update_truck_max_limit(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    _: RequireRole<Manager>,
     axum::extract::Path(id): axum::extract::Path<i64>,
     Json(req): Json<UpdateTruckMaxLimitRequest>,
 ) -> Result<Json<TruckResponse>, AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden(
-            "Only managers can update truck max limit",
-        ));
-    }
-
-    if req.max_allowance_limit < 0.0 {
+    if req.max_allowance_limit.is_sign_negative() {
         return Err(AppError::validation(
             "Max allowance limit cannot be negative",
         ));
@@ -301,11 +297,11 @@ pub async fn update_truck_max_limit(
 
     let truck = sqlx::query!(
         r#"UPDATE trucks SET
-            max_allowance_limit = $2::FLOAT8
+            max_allowance_limit = $2
         WHERE id = $1
-        RETURNING id, truck_number, driver_id, is_active, (max_allowance_limit)::FLOAT8 as "max_allowance_limit!", created_at"#,
+        RETURNING id, truck_number, driver_id, is_active, max_allowance_limit as "max_allowance_limit!", created_at"#,
         id,
-        req.max_allowance_limit
+        money::normalize(req.max_allowance_limit)
     )
     .fetch_optional(&db_pool)
     .await?
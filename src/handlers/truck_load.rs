@@ -1,23 +1,33 @@
 use crate::dtos::truck_load::{
-    CreateTruckLoadRequest, ReconcileTruckLoadRequest, TruckLoadItemResponse, TruckLoadListItem,
-    TruckLoadResponse, TruckLoadSummary,
+    CreateTruckLoadRequest, ReconcileTruckLoadRequest, SkippedBatch, TruckLoadItemResponse,
+    TruckLoadListItem, TruckLoadListQuery, TruckLoadResponse, TruckLoadSummary,
 };
+use crate::database::filter::{self, ListFilter, Page, Sort};
+use crate::dtos::pagination::{PageParams, Paginated};
 use crate::error::AppError;
-use crate::middleware::auth::AuthContext;
+use crate::auth::role::Manager;
+use crate::middleware::auth::RequireRole;
 use crate::state::AppState;
 use axum::http::StatusCode;
-use axum::{extract::State, Extension, Json};
+use axum::{extract::State, Json};
+use chrono::{Duration, NaiveDate};
 use sqlx::PgPool;
 
+/// Minimum days of shelf life left on the load date for FIFO product selection,
+/// from MIN_SHELF_LIFE_DAYS (default 0: only already-expired batches are skipped).
+fn default_min_shelf_life_days() -> i32 {
+    std::env::var("MIN_SHELF_LIFE_DAYS")
+        .ok()
+        .and_then(|v| v.parse::<i32>().ok())
+        .filter(|days| *days >= 0)
+        .unwrap_or(0)
+}
+
 pub async fn create_truck_load(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    _: RequireRole<Manager>,
     Json(req): Json<CreateTruckLoadRequest>,
 ) -> Result<(StatusCode, Json<TruckLoadResponse>), AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden("Only managers can create truck loads"));
-    }
-
     if req.items.is_empty() {
         return Err(AppError::validation(
             "Truck load must contain at least one item",
@@ -40,6 +50,13 @@ pub async fn create_truck_load(
         return Err(AppError::validation("Truck is not active"));
     }
 
+    let min_shelf_life_days = req.min_shelf_life_days.unwrap_or_else(default_min_shelf_life_days);
+    if min_shelf_life_days < 0 {
+        return Err(AppError::validation("min_shelf_life_days cannot be negative"));
+    }
+    // Batches expiring before this date are passed over by FIFO selection
+    let shelf_life_cutoff = req.load_date + Duration::days(min_shelf_life_days as i64);
+
     // Start transaction
     let mut tx = db_pool.begin().await?;
 
@@ -68,6 +85,7 @@ pub async fn create_truck_load(
 
     // Validate and insert items
     let mut items = Vec::new();
+    let mut skipped_batches = Vec::new();
     for item in &req.items {
         // Validate that exactly one of batch_id or product_id is provided
         match (item.batch_id, item.product_id) {
@@ -88,20 +106,24 @@ pub async fn create_truck_load(
                     truck_load.id as i64,
                     batch_id,
                     item.quantity_loaded,
+                    req.load_date,
                 )
                 .await?;
                 items.extend(loaded_items);
             }
             (None, Some(product_id)) => {
                 // Auto FIFO batch selection
-                let loaded_items = load_product_fifo(
+                let (loaded_items, skipped) = load_product_fifo(
                     &mut tx,
                     truck_load.id as i64,
                     product_id,
                     item.quantity_loaded,
+                    req.load_date,
+                    shelf_life_cutoff,
                 )
                 .await?;
                 items.extend(loaded_items);
+                skipped_batches.extend(skipped);
             }
         }
     }
@@ -144,6 +166,7 @@ pub async fn create_truck_load(
                 total_lost_damaged,
                 product_lines: req.items.len() as i32,
             },
+            skipped_batches,
         }),
     ))
 }
@@ -157,15 +180,23 @@ pub async fn get_truck_load(
 
 pub async fn list_truck_loads(
     State(AppState { db_pool }): State<AppState>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<Vec<TruckLoadListItem>>, AppError> {
-    let truck_id = params.get("truck_id").and_then(|s| s.parse::<i64>().ok());
-    let load_date = params
-        .get("load_date")
-        .and_then(|s| s.parse::<chrono::NaiveDate>().ok());
-    let status = params.get("status");
-
-    let mut query_str = String::from(
+    axum::extract::Query(params): axum::extract::Query<TruckLoadListQuery>,
+    axum::extract::Query(page_params): axum::extract::Query<PageParams>,
+) -> Result<Json<Paginated<TruckLoadListItem>>, AppError> {
+    let status = filter::one_of(
+        "status",
+        params.status.as_deref(),
+        &["loaded", "in_transit", "returned", "reconciled"],
+    )?;
+    let sort = Sort::parse(
+        page_params.sort.as_deref(),
+        &[("load_date", "tl.load_date"), ("status", "tl.status"), ("truck_number", "t.truck_number")],
+        "-load_date",
+        "tl.id",
+    )?;
+    let page = Page::from_params(&page_params)?;
+
+    let mut filter = ListFilter::new(
         r#"SELECT 
             tl.id, tl.truck_id, tl.load_date, tl.status,
             t.truck_number, u.username as driver_username,
@@ -175,58 +206,34 @@ pub async fn list_truck_loads(
         FROM truck_loads tl
         JOIN trucks t ON tl.truck_id = t.id
         LEFT JOIN users u ON t.driver_id = u.id
-        LEFT JOIN truck_load_items tli ON tl.id = tli.truck_load_id
-        WHERE 1=1"#,
+        LEFT JOIN truck_load_items tli ON tl.id = tli.truck_load_id"#,
     );
 
-    if truck_id.is_some() {
-        query_str.push_str(" AND tl.truck_id = $1");
-    }
-    if load_date.is_some() {
-        let param_num = if truck_id.is_some() { 2 } else { 1 };
-        query_str.push_str(&format!(" AND tl.load_date = ${}", param_num));
-    }
-    if status.is_some() {
-        let param_num = if truck_id.is_some() && load_date.is_some() {
-            3
-        } else if truck_id.is_some() || load_date.is_some() {
-            2
-        } else {
-            1
-        };
-        query_str.push_str(&format!(" AND tl.status = ${}", param_num));
-    }
-
-    query_str.push_str(" GROUP BY tl.id, tl.truck_id, tl.load_date, tl.status, t.truck_number, u.username ORDER BY tl.load_date DESC, tl.id DESC");
-
-    let mut query = sqlx::query_as::<
-        _,
-        (
-            i64,
-            i64,
-            chrono::NaiveDate,
-            String,
-            String,
-            Option<String>,
-            i32,
-            i32,
-            i32,
-        ),
-    >(&query_str);
-
-    if let Some(tid) = truck_id {
-        query = query.bind(tid);
-    }
-    if let Some(date) = load_date {
-        query = query.bind(date);
-    }
-    if let Some(st) = status {
-        query = query.bind(st);
-    }
-
-    let loads = query.fetch_all(&db_pool).await?;
+    filter
+        .eq("tl.truck_id", params.truck_id)
+        .eq("tl.load_date", params.load_date)
+        .eq("tl.status", status);
+
+    let (loads, total) = filter::fetch_page::<(
+        i64,
+        i64,
+        chrono::NaiveDate,
+        String,
+        String,
+        Option<String>,
+        i32,
+        i32,
+        i32,
+    )>(
+        &db_pool,
+        &filter,
+        "GROUP BY tl.id, tl.truck_id, tl.load_date, tl.status, t.truck_number, u.username",
+        &sort,
+        &page,
+    )
+    .await?;
 
-    Ok(Json(
+    Ok(Json(page.envelope(
         loads
             .into_iter()
             .map(
@@ -256,41 +263,63 @@ pub async fn list_truck_loads(
                 },
             )
             .collect(),
-    ))
+        total,
+    )))
 }
 
 pub async fn reconcile_truck_load(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
     axum::extract::Path(id): axum::extract::Path<i64>,
     Json(req): Json<ReconcileTruckLoadRequest>,
 ) -> Result<Json<TruckLoadResponse>, AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden(
-            "Only managers can reconcile truck loads",
-        ));
-    }
-
     // Start transaction
     let mut tx = db_pool.begin().await?;
 
     // Verify truck load exists and is not already reconciled
-    let truck_load = sqlx::query!(r#"SELECT id, status FROM truck_loads WHERE id = $1"#, id)
-        .fetch_optional(&mut *tx)
-        .await?
-        .ok_or_else(|| AppError::not_found("Truck load not found"))?;
+    let truck_load = sqlx::query!(
+        r#"SELECT id, truck_id, load_date, status FROM truck_loads WHERE id = $1 FOR UPDATE"#,
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::not_found("Truck load not found"))?;
 
     if truck_load.status == "reconciled" {
         return Err(AppError::conflict("Truck load is already reconciled"));
     }
 
+    // Once the day's reconciliation is started it owns the returns for every load of that
+    // date, so the same units cannot be restocked here and again on finalize.
+    let reconciliation_started = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM daily_reconciliations WHERE reconciliation_date = $1) as "exists!""#,
+        truck_load.load_date
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if reconciliation_started {
+        return Err(AppError::conflict(format!(
+            "A reconciliation exists for {date}; verify returns with POST /reconciliations/{date}/trucks/{truck_id}/verify",
+            date = truck_load.load_date,
+            truck_id = truck_load.truck_id
+        )));
+    }
+
     // Update return quantities
     for return_item in &req.returns {
+        if return_item.quantity_returned < 0 {
+            return Err(AppError::validation(format!(
+                "Batch {}: returned quantity cannot be negative",
+                return_item.batch_id
+            )));
+        }
+
         let result = sqlx::query!(
             r#"UPDATE truck_load_items
             SET quantity_returned = $2
             WHERE truck_load_id = $1 AND batch_id = $3
-            RETURNING quantity_loaded, quantity_sold, quantity_returned"#,
+            RETURNING quantity_loaded, quantity_sold, quantity_returned, quantity_discarded"#,
             id,
             return_item.quantity_returned,
             return_item.batch_id
@@ -298,31 +327,51 @@ pub async fn reconcile_truck_load(
         .fetch_optional(&mut *tx)
         .await?
         .ok_or_else(|| {
-            AppError::not_found(&format!(
+            AppError::not_found(format!(
                 "Batch {} not found in this truck load",
                 return_item.batch_id
             ))
         })?;
 
         // Validate returned quantity
-        if result.quantity_sold + result.quantity_returned > result.quantity_loaded {
-            return Err(AppError::validation(&format!(
-                "Batch {}: Total sold ({}) + returned ({}) cannot exceed loaded quantity ({})",
+        if result.quantity_sold + result.quantity_returned + result.quantity_discarded > result.quantity_loaded {
+            return Err(AppError::validation(format!(
+                "Batch {}: Total sold ({}) + returned ({}) + discarded ({}) cannot exceed loaded quantity ({})",
                 return_item.batch_id,
                 result.quantity_sold,
                 result.quantity_returned,
+                result.quantity_discarded,
                 result.quantity_loaded
             )));
         }
 
+        if return_item.quantity_returned == 0 {
+            continue;
+        }
+
         // Restore returned quantity back to batch remaining_quantity
-        sqlx::query!(
+        let batch = sqlx::query!(
             r#"UPDATE batches 
             SET remaining_quantity = remaining_quantity + $2
-            WHERE id = $1"#,
+            WHERE id = $1
+            RETURNING product_id, batch_number"#,
             return_item.batch_id,
             return_item.quantity_returned
         )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"INSERT INTO stock_movements 
+               (batch_id, product_id, movement_type, quantity, reference_type, reference_id, notes, created_by, movement_date)
+               VALUES ($1, $2, 'truck_return_in', ($3)::FLOAT8::NUMERIC, 'truck_load', $4, $5, $6, CURRENT_DATE)"#,
+            return_item.batch_id as i32,
+            batch.product_id as i32,
+            return_item.quantity_returned as f64,
+            id as i32,
+            format!("Returned from truck - Batch: {}", batch.batch_number),
+            auth.user_id as i32
+        )
         .execute(&mut *tx)
         .await?;
     }
@@ -344,13 +393,9 @@ pub async fn reconcile_truck_load(
 
 pub async fn delete_truck_load(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    _: RequireRole<Manager>,
     axum::extract::Path(id): axum::extract::Path<i64>,
 ) -> Result<StatusCode, AppError> {
-    if auth.role != "manager" {
-        return Err(AppError::forbidden("Only managers can delete truck loads"));
-    }
-
     // Start transaction
     let mut tx = db_pool.begin().await?;
 
@@ -502,17 +547,20 @@ async fn fetch_truck_load_by_id(db_pool: &PgPool, id: i64) -> Result<TruckLoadRe
             total_lost_damaged,
             product_lines,
         },
+        skipped_batches: Vec::new(),
     })
 }
 
 // ==================== Helper Functions ====================
 
-/// Load a specific batch (manual selection)
+/// Load a specific batch (manual selection). Expired batches are refused; the
+/// shelf-life margin only applies to automatic selection.
 async fn load_specific_batch(
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     truck_load_id: i64,
     batch_id: i64,
     quantity_loaded: i32,
+    load_date: NaiveDate,
 ) -> Result<Vec<TruckLoadItemResponse>, AppError> {
     // Verify batch exists and has enough quantity
     let batch = sqlx::query!(
@@ -526,6 +574,13 @@ async fn load_specific_batch(
     .await?
     .ok_or_else(|| AppError::not_found(&format!("Batch {} not found", batch_id)))?;
 
+    if batch.expiry_date < load_date {
+        return Err(AppError::validation(format!(
+            "Batch {} expired on {} and cannot be loaded",
+            batch.batch_number, batch.expiry_date
+        )));
+    }
+
     if batch.remaining_quantity < quantity_loaded {
         return Err(AppError::validation(&format!(
             "Batch {} only has {} units remaining, cannot load {}",
@@ -593,15 +648,19 @@ async fn load_specific_batch(
     }])
 }
 
-/// Load product using FIFO (First In First Out by expiry date)
+/// Load product using FIFO (First Expired First Out). Batches expired on the load
+/// date, or expiring before `shelf_life_cutoff`, are skipped and returned so the
+/// caller can report them.
 async fn load_product_fifo(
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     truck_load_id: i64,
     product_id: i64,
     total_quantity_needed: i32,
-) -> Result<Vec<TruckLoadItemResponse>, AppError> {
-    // Get available batches for this product, ordered by expiry date (FIFO)
-    let batches = sqlx::query!(
+    load_date: NaiveDate,
+    shelf_life_cutoff: NaiveDate,
+) -> Result<(Vec<TruckLoadItemResponse>, Vec<SkippedBatch>), AppError> {
+    // Get batches in stock for this product, ordered by expiry date (FIFO)
+    let all_batches = sqlx::query!(
         r#"SELECT b.id, b.batch_number, b.remaining_quantity, b.expiry_date, p.name as product_name
         FROM batches b
         JOIN products p ON b.product_id = p.id
@@ -612,10 +671,43 @@ async fn load_product_fifo(
     .fetch_all(&mut **tx)
     .await?;
 
+    let (batches, rejected): (Vec<_>, Vec<_>) = all_batches
+        .into_iter()
+        .partition(|b| b.expiry_date >= shelf_life_cutoff);
+
+    let skipped: Vec<SkippedBatch> = rejected
+        .into_iter()
+        .map(|b| SkippedBatch {
+            batch_id: b.id,
+            batch_number: b.batch_number,
+            product_id,
+            expiry_date: b.expiry_date,
+            remaining_quantity: b.remaining_quantity,
+            reason: if b.expiry_date < load_date { "expired" } else { "short_shelf_life" }.to_string(),
+        })
+        .collect();
+    let skipped_quantity: i32 = skipped.iter().map(|b| b.remaining_quantity).sum();
+    let skipped_note = if skipped.is_empty() {
+        String::new()
+    } else {
+        format!(
+            " ({} units in {} batches skipped: expired or expiring before {})",
+            skipped_quantity,
+            skipped.len(),
+            shelf_life_cutoff
+        )
+    };
+
     if batches.is_empty() {
-        return Err(AppError::not_found(&format!(
-            "No available batches found for product {}",
-            product_id
+        if skipped.is_empty() {
+            return Err(AppError::not_found(&format!(
+                "No available batches found for product {}",
+                product_id
+            )));
+        }
+        return Err(AppError::validation(format!(
+            "No loadable batches for product {}{}",
+            product_id, skipped_note
         )));
     }
 
@@ -623,8 +715,8 @@ async fn load_product_fifo(
     let total_available: i32 = batches.iter().map(|b| b.remaining_quantity).sum();
     if total_available < total_quantity_needed {
         return Err(AppError::validation(&format!(
-            "Insufficient stock for product {}. Available: {}, Requested: {}",
-            product_id, total_available, total_quantity_needed
+            "Insufficient stock for product {}. Available: {}, Requested: {}{}",
+            product_id, total_available, total_quantity_needed, skipped_note
         )));
     }
 
@@ -692,5 +784,5 @@ async fn load_product_fifo(
         remaining_to_load -= quantity_from_this_batch;
     }
 
-    Ok(loaded_items)
+    Ok((loaded_items, skipped))
 }
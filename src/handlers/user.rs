@@ -1,29 +1,32 @@
-use crate::auth::jwt::sign_token;
-use crate::dtos::user::{LoginRequest, LoginResponse, RegisterUserRequest, UserResponse};
+use crate::auth::jwt::{sign_token, ACCESS_TOKEN_TTL_SECONDS};
+use crate::auth::role::{Manager, Role};
+use crate::auth::session::{self, IssuedSession};
+use crate::dtos::user::{
+    ChangePasswordRequest, LoginRequest, LoginResponse, RefreshTokenRequest, RegisterUserRequest,
+    ResetPasswordRequest, UpdateUserRoleRequest, UserListQuery, UserResponse,
+};
 use crate::error::AppError;
-use crate::middleware::auth::AuthContext;
+use crate::middleware::auth::{RequireAnyRole, RequireRole};
 use crate::state::AppState;
-use axum::extract::Extension;
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
 use axum::{extract::State, Json};
 use bcrypt::{hash, verify, DEFAULT_COST};
 
+// Only managers can create accounts; the seeded 'manager' user bootstraps a fresh database
 pub async fn register_user(
     State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
     Json(payload): Json<RegisterUserRequest>,
-) -> Result<(axum::http::StatusCode, Json<UserResponse>), AppError> {
+) -> Result<(StatusCode, Json<UserResponse>), AppError> {
     // Basic validation
-    if payload.role != "manager" && payload.role != "driver" {
-        return Err(AppError::validation("Invalid role"));
-    }
+    let role = parse_role(&payload.role)?;
     if payload.username.trim().is_empty() {
         return Err(AppError::validation("Username required"));
     }
-    if payload.password.len() < 6 {
-        return Err(AppError::validation("Password too short"));
-    }
+    validate_password(&payload.password)?;
 
-    let password_hash = hash(&payload.password, DEFAULT_COST)
-        .map_err(|e| AppError::internal(format!("Hash error: {e}")))?;
+    let password_hash = hash_password(&payload.password)?;
 
     let rec = sqlx::query_as!(
         UserInsertReturn,
@@ -34,7 +37,7 @@ pub async fn register_user(
         "#,
         payload.username,
         password_hash,
-        payload.role
+        role.as_str()
     )
     .fetch_one(&db_pool)
     .await
@@ -48,7 +51,7 @@ pub async fn register_user(
     })?;
 
     Ok((
-        axum::http::StatusCode::CREATED,
+        StatusCode::CREATED,
         Json(UserResponse {
             id: rec.id,
             username: rec.username,
@@ -74,7 +77,7 @@ pub async fn login_user(
         UserRow,
         r#"SELECT id, username, password_hash, role, is_active 
         FROM users 
-        WHERE username = $1"#,
+        WHERE username = $1 AND role <> 'system'"#,
         payload.username
     )
     .fetch_optional(&db_pool)
@@ -94,25 +97,43 @@ pub async fn login_user(
         return Err(AppError::validation("Invalid credentials"));
     }
 
-    let secret =
-        std::env::var("JWT_SECRET").map_err(
-            |_| AppError::internal("JWT secret not configured"
-        ))?;
+    let session = session::create_session(&db_pool, user.id).await?;
+    issue_tokens(user.id, &user.role, &user.username, session).map(Json)
+}
 
-    let token = sign_token(user.id, &user.role, &user.username, &secret)?;
+// Open endpoint: exchanges a refresh token for a new access token and a rotated refresh token
+pub async fn refresh_token(
+    State(AppState { db_pool }): State<AppState>,
+    Json(payload): Json<RefreshTokenRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    if payload.refresh_token.trim().is_empty() {
+        return Err(AppError::validation("Refresh token required"));
+    }
 
-    // 8 hours = 28800 seconds
-    Ok(Json(LoginResponse {
-        access_token: token,
-        token_type: "Bearer",
-        expires_in_seconds: 8 * 60 * 60,
-    }))
+    let (user_id, session) = session::rotate_session(&db_pool, payload.refresh_token.trim()).await?;
+
+    let user = fetch_user(&db_pool, user_id).await?;
+    if !user.is_active {
+        session::revoke_session(&db_pool, session.session_id).await?;
+        return Err(AppError::unauthorized("User inactive"));
+    }
+
+    issue_tokens(user.id, &user.role, &user.username, session).map(Json)
+}
+
+// Revokes the session the access token belongs to; its refresh token stops working too
+pub async fn logout_user(
+    State(AppState { db_pool }): State<AppState>,
+    RequireAnyRole(auth): RequireAnyRole,
+) -> Result<StatusCode, AppError> {
+    session::revoke_session(&db_pool, auth.session_id).await?;
+    Ok(StatusCode::NO_CONTENT)
 }
 
 // Authenticated endpoint: returns full user profile from DB using the id in AuthContext
 pub async fn get_me(
     State(AppState { db_pool }): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    RequireAnyRole(auth): RequireAnyRole,
 ) -> Result<Json<UserResponse>, AppError> {
     let rec = sqlx::query_as!(
         UserProfileRow,
@@ -122,13 +143,264 @@ pub async fn get_me(
     .fetch_one(&db_pool)
     .await?;
 
-    Ok(Json(UserResponse {
-        id: rec.id,
-        username: rec.username,
-        role: rec.role,
-        is_active: rec.is_active,
-        created_at: rec.created_at,
-    }))
+    Ok(Json(UserResponse::from(rec)))
+}
+
+pub async fn list_users(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Query(params): Query<UserListQuery>,
+) -> Result<Json<Vec<UserResponse>>, AppError> {
+    let role = params.role.as_deref().map(parse_role).transpose()?;
+
+    let users = sqlx::query_as!(
+        UserProfileRow,
+        r#"SELECT id, username, role, is_active, created_at as "created_at!"
+        FROM users
+        WHERE role <> 'system'
+          AND ($1::VARCHAR IS NULL OR role = $1)
+          AND ($2::BOOLEAN IS NULL OR is_active = $2)
+        ORDER BY username"#,
+        role.map(|r| r.as_str()),
+        params.is_active
+    )
+    .fetch_all(&db_pool)
+    .await?;
+
+    Ok(Json(users.into_iter().map(UserResponse::from).collect()))
+}
+
+// Managers can view any user; drivers only themselves
+pub async fn get_user(
+    State(AppState { db_pool }): State<AppState>,
+    RequireAnyRole(auth): RequireAnyRole,
+    Path(id): Path<i64>,
+) -> Result<Json<UserResponse>, AppError> {
+    if auth.role != Role::Manager && auth.user_id != id {
+        return Err(AppError::forbidden("You can only view your own account"));
+    }
+
+    let user = fetch_user(&db_pool, id).await?;
+    Ok(Json(UserResponse::from(user)))
+}
+
+pub async fn update_user_role(
+    State(AppState { db_pool }): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
+    Path(id): Path<i64>,
+    Json(req): Json<UpdateUserRoleRequest>,
+) -> Result<Json<UserResponse>, AppError> {
+    let new_role = parse_role(&req.role)?;
+    // Prevents a manager from demoting themselves and leaving no manager behind
+    if auth.user_id == id {
+        return Err(AppError::validation("You cannot change your own role"));
+    }
+
+    let user = fetch_user(&db_pool, id).await?;
+
+    // trucks.driver_id must always reference a driver
+    if user.role == Role::Driver.as_str() && new_role != Role::Driver {
+        if let Some(truck_number) = assigned_truck(&db_pool, id).await? {
+            return Err(AppError::conflict(format!(
+                "User {} is assigned as driver of truck {}. Unassign them before changing role",
+                user.username, truck_number
+            )));
+        }
+    }
+
+    let updated = sqlx::query_as!(
+        UserProfileRow,
+        r#"UPDATE users SET role = $1 WHERE id = $2
+        RETURNING id, username, role, is_active, created_at as "created_at!""#,
+        new_role.as_str(),
+        id
+    )
+    .fetch_one(&db_pool)
+    .await?;
+
+    Ok(Json(UserResponse::from(updated)))
+}
+
+pub async fn deactivate_user(
+    State(AppState { db_pool }): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
+    Path(id): Path<i64>,
+) -> Result<Json<UserResponse>, AppError> {
+    if auth.user_id == id {
+        return Err(AppError::validation("You cannot deactivate your own account"));
+    }
+
+    let user = fetch_user(&db_pool, id).await?;
+    if !user.is_active {
+        return Err(AppError::conflict("User is already inactive"));
+    }
+
+    // An inactive driver cannot log in, so their truck would be stranded
+    if let Some(truck_number) = assigned_truck(&db_pool, id).await? {
+        return Err(AppError::conflict(format!(
+            "Driver {} is still assigned to truck {}. Reassign the truck before deactivating",
+            user.username, truck_number
+        )));
+    }
+
+    let user = set_active(&db_pool, id, false).await?;
+    session::revoke_user_sessions(&db_pool, id, None).await?;
+    Ok(Json(user))
+}
+
+pub async fn reactivate_user(
+    State(AppState { db_pool }): State<AppState>,
+    _: RequireRole<Manager>,
+    Path(id): Path<i64>,
+) -> Result<Json<UserResponse>, AppError> {
+    let user = fetch_user(&db_pool, id).await?;
+    if user.is_active {
+        return Err(AppError::conflict("User is already active"));
+    }
+
+    set_active(&db_pool, id, true).await.map(Json)
+}
+
+// Resets are for drivers who forgot their password. A manager account, the
+// caller's own included, changes its password itself with the current one.
+pub async fn reset_user_password(
+    State(AppState { db_pool }): State<AppState>,
+    RequireRole { auth, .. }: RequireRole<Manager>,
+    Path(id): Path<i64>,
+    Json(req): Json<ResetPasswordRequest>,
+) -> Result<StatusCode, AppError> {
+    validate_password(&req.new_password)?;
+
+    let user = fetch_user(&db_pool, id).await?;
+    if auth.user_id == id {
+        return Err(AppError::validation(
+            "Use /users/me/password to change your own password",
+        ));
+    }
+    if user.role == Role::Manager.as_str() {
+        return Err(AppError::forbidden(format!(
+            "User {} is a manager; managers change their own password with their current one",
+            user.username
+        )));
+    }
+
+    let password_hash = hash_password(&req.new_password)?;
+    sqlx::query!(
+        r#"UPDATE users SET password_hash = $1 WHERE id = $2"#,
+        password_hash,
+        id
+    )
+    .execute(&db_pool)
+    .await?;
+
+    // Whoever held the old password must log in again
+    session::revoke_user_sessions(&db_pool, id, None).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn change_my_password(
+    State(AppState { db_pool }): State<AppState>,
+    RequireAnyRole(auth): RequireAnyRole,
+    Json(req): Json<ChangePasswordRequest>,
+) -> Result<StatusCode, AppError> {
+    validate_password(&req.new_password)?;
+
+    let current_hash = sqlx::query_scalar!(
+        r#"SELECT password_hash FROM users WHERE id = $1"#,
+        auth.user_id
+    )
+    .fetch_optional(&db_pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("User not found"))?;
+
+    let ok = verify(&req.current_password, &current_hash)
+        .map_err(|e| AppError::internal(format!("Password verify error: {e}")))?;
+    if !ok {
+        return Err(AppError::validation("Current password is incorrect"));
+    }
+
+    let password_hash = hash_password(&req.new_password)?;
+    sqlx::query!(
+        r#"UPDATE users SET password_hash = $1 WHERE id = $2"#,
+        password_hash,
+        auth.user_id
+    )
+    .execute(&db_pool)
+    .await?;
+
+    // Sign out other devices; the session making this request stays valid
+    session::revoke_user_sessions(&db_pool, auth.user_id, Some(auth.session_id)).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn issue_tokens(user_id: i64, role: &str, username: &str, session: IssuedSession) -> Result<LoginResponse, AppError> {
+    let secret =
+        std::env::var("JWT_SECRET").map_err(
+            |_| AppError::internal("JWT secret not configured"
+        ))?;
+
+    let token = sign_token(user_id, role, username, session.session_id, &secret)?;
+
+    Ok(LoginResponse {
+        access_token: token,
+        token_type: "Bearer",
+        expires_in_seconds: ACCESS_TOKEN_TTL_SECONDS as usize,
+        refresh_token: session.refresh_token,
+        refresh_expires_at: session.expires_at,
+    })
+}
+
+fn parse_role(role: &str) -> Result<Role, AppError> {
+    role.parse::<Role>().map_err(|_| AppError::validation("Invalid role"))
+}
+
+fn validate_password(password: &str) -> Result<(), AppError> {
+    if password.len() < 6 {
+        return Err(AppError::validation("Password too short"));
+    }
+    Ok(())
+}
+
+fn hash_password(password: &str) -> Result<String, AppError> {
+    hash(password, DEFAULT_COST).map_err(|e| AppError::internal(format!("Hash error: {e}")))
+}
+
+// The system principal that owns automated movements is not an account and is never returned
+async fn fetch_user(db_pool: &sqlx::PgPool, id: i64) -> Result<UserProfileRow, AppError> {
+    sqlx::query_as!(
+        UserProfileRow,
+        r#"SELECT id, username, role, is_active, created_at as "created_at!" FROM users WHERE id = $1 AND role <> 'system'"#,
+        id
+    )
+    .fetch_optional(db_pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("User not found"))
+}
+
+// Truck number the user is currently assigned to as driver, if any
+async fn assigned_truck(db_pool: &sqlx::PgPool, user_id: i64) -> Result<Option<String>, AppError> {
+    let truck_number = sqlx::query_scalar!(
+        r#"SELECT truck_number FROM trucks WHERE driver_id = $1"#,
+        user_id
+    )
+    .fetch_optional(db_pool)
+    .await?;
+    Ok(truck_number)
+}
+
+async fn set_active(db_pool: &sqlx::PgPool, id: i64, is_active: bool) -> Result<UserResponse, AppError> {
+    let user = sqlx::query_as!(
+        UserProfileRow,
+        r#"UPDATE users SET is_active = $1 WHERE id = $2
+        RETURNING id, username, role, is_active, created_at as "created_at!""#,
+        is_active,
+        id
+    )
+    .fetch_one(db_pool)
+    .await?;
+    Ok(UserResponse::from(user))
 }
 
 #[derive(sqlx::FromRow)]
@@ -155,3 +427,15 @@ struct UserProfileRow {
     is_active: bool,
     created_at: chrono::DateTime<chrono::Utc>,
 }
+
+impl From<UserProfileRow> for UserResponse {
+    fn from(row: UserProfileRow) -> Self {
+        UserResponse {
+            id: row.id,
+            username: row.username,
+            role: row.role,
+            is_active: row.is_active,
+            created_at: row.created_at,
+        }
+    }
+}
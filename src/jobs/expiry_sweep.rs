@@ -0,0 +1,202 @@
+// Writes off warehouse stock of expired batches. Runs on a tokio interval inside the
+// server (EXPIRY_SWEEP_INTERVAL_MINUTES, default 60, 0 disables) and on demand through
+// POST /expiry-sweeps.
+use std::time::Duration;
+use sqlx::PgPool;
+use crate::database::cost_layers::{cost_layers, HeldUnits};
+use crate::dtos::expiry::{ExpiryWriteOffItem, ExpiryWriteOffReport};
+use crate::error::AppError;
+use crate::money;
+
+const DEFAULT_INTERVAL_MINUTES: u64 = 60;
+
+#[derive(Clone, Copy)]
+pub enum SweepTrigger {
+    Scheduler,
+    Manual { user_id: i64 },
+}
+
+impl SweepTrigger {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SweepTrigger::Scheduler => "scheduler",
+            SweepTrigger::Manual { .. } => "manual",
+        }
+    }
+
+    fn requested_by(&self) -> Option<i64> {
+        match self {
+            SweepTrigger::Scheduler => None,
+            SweepTrigger::Manual { user_id } => Some(*user_id),
+        }
+    }
+}
+
+/// Start the periodic sweep unless it is disabled.
+pub fn spawn(db_pool: PgPool) {
+    let minutes = std::env::var("EXPIRY_SWEEP_INTERVAL_MINUTES")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_INTERVAL_MINUTES);
+
+    if minutes == 0 {
+        tracing::info!("Expiry sweep disabled");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(minutes * 60));
+        loop {
+            interval.tick().await;
+            match run_sweep(&db_pool, SweepTrigger::Scheduler).await {
+                Ok(report) if report.batches_written_off > 0 => tracing::info!(
+                    write_off_id = ?report.id,
+                    batches = report.batches_written_off,
+                    units = report.units_written_off,
+                    value = %report.total_value,
+                    "Expired stock written off"
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::error!(error = ?e, "Expiry sweep failed"),
+            }
+        }
+    });
+}
+
+/// Zero the remaining quantity of every batch whose expiry_date has passed, log an
+/// expired_out movement per batch and store the write-off. Movements are attributed to
+/// the requesting manager, or to the `system` user for scheduled sweeps.
+pub async fn run_sweep(db_pool: &PgPool, trigger: SweepTrigger) -> Result<ExpiryWriteOffReport, AppError> {
+    let mut tx = db_pool.begin().await?;
+
+    let created_by = match trigger.requested_by() {
+        Some(user_id) => user_id,
+        None => super::system_user_id(&mut tx).await?,
+    };
+
+    let as_of = sqlx::query_scalar!(r#"SELECT CURRENT_DATE as "today!""#)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    // Locked so a concurrent sale or load cannot take stock that is being written off
+    let expired = sqlx::query!(
+        r#"SELECT b.id, b.batch_number, b.product_id, p.name as product_name,
+                  b.expiry_date, b.remaining_quantity
+           FROM batches b
+           JOIN products p ON b.product_id = p.id
+           WHERE b.expiry_date < $1 AND b.remaining_quantity > 0
+           ORDER BY b.expiry_date, b.id
+           FOR UPDATE OF b"#,
+        as_of
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    // Valued per FIFO cost layer, as in the inventory valuation
+    let held: Vec<HeldUnits> = expired
+        .iter()
+        .map(|b| HeldUnits {
+            batch_id: b.id,
+            warehouse_units: b.remaining_quantity,
+            truck_units: 0,
+        })
+        .collect();
+    let mut layers = cost_layers(&mut tx, as_of, &held).await?;
+
+    let items: Vec<ExpiryWriteOffItem> = expired
+        .into_iter()
+        .map(|b| {
+            let value = layers
+                .remove(&b.id)
+                .unwrap_or_default()
+                .iter()
+                .fold(money::zero(), |sum, l| sum + money::line_amount(l.units(), l.unit_cost));
+            ExpiryWriteOffItem {
+                batch_id: b.id,
+                batch_number: b.batch_number,
+                product_id: b.product_id,
+                product_name: b.product_name,
+                expiry_date: b.expiry_date,
+                quantity: b.remaining_quantity,
+                unit_cost: money::average_cost(value, b.remaining_quantity),
+                value,
+            }
+        })
+        .collect();
+
+    let mut report = ExpiryWriteOffReport {
+        id: None,
+        triggered_by: trigger.as_str().to_string(),
+        requested_by: trigger.requested_by(),
+        as_of,
+        batches_written_off: items.len() as i32,
+        units_written_off: items.iter().map(|i| i.quantity).sum(),
+        total_value: money::normalize(items.iter().map(|i| i.value).sum()),
+        created_at: None,
+        items,
+    };
+
+    if report.items.is_empty() {
+        return Ok(report);
+    }
+
+    let write_off = sqlx::query!(
+        r#"INSERT INTO expiry_write_offs
+           (triggered_by, requested_by, as_of, batches_written_off, units_written_off, total_value)
+           VALUES ($1, $2, $3, $4, $5, $6)
+           RETURNING id, created_at"#,
+        report.triggered_by,
+        report.requested_by,
+        as_of,
+        report.batches_written_off,
+        report.units_written_off,
+        report.total_value
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    for item in &report.items {
+        sqlx::query!(
+            "UPDATE batches SET remaining_quantity = 0 WHERE id = $1",
+            item.batch_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"INSERT INTO stock_movements 
+               (batch_id, product_id, movement_type, quantity, reference_type, reference_id, notes, created_by, movement_date)
+               VALUES ($1, $2, 'expired_out', ($3)::FLOAT8::NUMERIC, 'expiry_write_off', $4, $5, $6, $7)"#,
+            item.batch_id as i32,
+            item.product_id as i32,
+            item.quantity as f64,
+            write_off.id as i32,
+            format!("Expired {} - automatic write-off - Batch: {}", item.expiry_date, item.batch_number),
+            created_by as i32,
+            as_of
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"INSERT INTO expiry_write_off_items
+               (write_off_id, batch_id, product_id, expiry_date, quantity, unit_cost, value)
+               VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+            write_off.id,
+            item.batch_id,
+            item.product_id,
+            item.expiry_date,
+            item.quantity,
+            item.unit_cost,
+            item.value
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    report.id = Some(write_off.id);
+    report.created_at = Some(write_off.created_at);
+    Ok(report)
+}
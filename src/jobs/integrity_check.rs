@@ -0,0 +1,305 @@
+// Scans batches, truck loads and reconciliations for broken stock invariants and,
+// outside dry-run mode, posts 'adjustment' movements that bring a batch's ledger back
+// in line with its remaining_quantity. Used by GET /integrity, POST /integrity/repair
+// and the `integrity-check` command (see main).
+use sqlx::PgPool;
+use crate::dtos::integrity::{IntegrityIssue, IntegrityReport};
+use crate::error::AppError;
+
+/// Run every check. With `dry_run` false, ledger mismatches are corrected with an
+/// adjustment movement attributed to `requested_by`, or to the `system` user.
+pub async fn run_check(
+    db_pool: &PgPool,
+    dry_run: bool,
+    requested_by: Option<i64>,
+) -> Result<IntegrityReport, AppError> {
+    let mut issues = Vec::new();
+
+    // Ledger vs live balance, per batch
+    let batches = sqlx::query!(
+        r#"SELECT batch_id as "batch_id!", remaining_quantity as "remaining_quantity!",
+                  calculated_balance::BIGINT as "ledger_balance!"
+           FROM batch_stock_balance
+           ORDER BY batch_id"#
+    )
+    .fetch_all(db_pool)
+    .await?;
+    let batches_checked = batches.len() as i64;
+
+    for b in &batches {
+        if b.remaining_quantity as i64 != b.ledger_balance {
+            issues.push(IntegrityIssue {
+                check: "batch_ledger".to_string(),
+                entity: "batch".to_string(),
+                entity_id: b.batch_id,
+                expected: b.ledger_balance,
+                actual: b.remaining_quantity as i64,
+                message: format!(
+                    "remaining_quantity is {} but the stock movements add up to {}",
+                    b.remaining_quantity, b.ledger_balance
+                ),
+                correction: Some((b.remaining_quantity as i64 - b.ledger_balance) as i32),
+                repaired: false,
+            });
+        }
+    }
+
+    // Truck load items: never more sold + returned + discarded than loaded, every
+    // loaded unit sold, returned or discarded once the load is reconciled, and
+    // quantity_sold matching the sale items recorded against the load
+    let load_items = sqlx::query!(
+        r#"SELECT tli.id, tli.quantity_loaded, tli.quantity_sold, tli.quantity_returned,
+                  tli.quantity_discarded,
+                  tl.status = 'reconciled' as "reconciled!",
+                  COALESCE(s.sold, 0)::BIGINT as "sale_items_sold!"
+           FROM truck_load_items tli
+           JOIN truck_loads tl ON tli.truck_load_id = tl.id
+           LEFT JOIN (
+               SELECT s.truck_load_id, si.batch_id, SUM(si.quantity) as sold
+               FROM sale_items si
+               JOIN sales s ON si.sale_id = s.id
+               GROUP BY s.truck_load_id, si.batch_id
+           ) s ON s.truck_load_id = tli.truck_load_id AND s.batch_id = tli.batch_id
+           ORDER BY tli.id"#
+    )
+    .fetch_all(db_pool)
+    .await?;
+    let truck_load_items_checked = load_items.len() as i64;
+
+    for item in &load_items {
+        let accounted = item.quantity_sold + item.quantity_returned + item.quantity_discarded;
+        if accounted > item.quantity_loaded {
+            issues.push(IntegrityIssue {
+                check: "truck_load_overdrawn".to_string(),
+                entity: "truck_load_item".to_string(),
+                entity_id: item.id,
+                expected: item.quantity_loaded as i64,
+                actual: accounted as i64,
+                message: format!(
+                    "sold {} + returned {} + discarded {} exceeds loaded {}",
+                    item.quantity_sold, item.quantity_returned, item.quantity_discarded, item.quantity_loaded
+                ),
+                correction: None,
+                repaired: false,
+            });
+        }
+        if item.reconciled && accounted != item.quantity_loaded {
+            issues.push(IntegrityIssue {
+                check: "truck_load_unaccounted".to_string(),
+                entity: "truck_load_item".to_string(),
+                entity_id: item.id,
+                expected: item.quantity_loaded as i64,
+                actual: accounted as i64,
+                message: format!(
+                    "load is reconciled but sold {} + returned {} + discarded {} does not equal loaded {}",
+                    item.quantity_sold, item.quantity_returned, item.quantity_discarded, item.quantity_loaded
+                ),
+                correction: None,
+                repaired: false,
+            });
+        }
+        if item.quantity_sold as i64 != item.sale_items_sold {
+            issues.push(IntegrityIssue {
+                check: "truck_load_sold".to_string(),
+                entity: "truck_load_item".to_string(),
+                entity_id: item.id,
+                expected: item.sale_items_sold,
+                actual: item.quantity_sold as i64,
+                message: format!(
+                    "quantity_sold is {} but sale items for this load and batch add up to {}",
+                    item.quantity_sold, item.sale_items_sold
+                ),
+                correction: None,
+                repaired: false,
+            });
+        }
+    }
+
+    // Finalized reconciliations: stored totals vs the sum of their truck items
+    let reconciliations = sqlx::query!(
+        r#"SELECT dr.id,
+                  dr.total_items_loaded::BIGINT as "total_loaded!",
+                  dr.total_items_sold::BIGINT as "total_sold!",
+                  dr.total_items_returned::BIGINT as "total_returned!",
+                  dr.total_items_discarded::BIGINT as "total_discarded!",
+                  COALESCE(SUM(ri.items_loaded), 0)::BIGINT as "items_loaded!",
+                  COALESCE(SUM(ri.items_sold), 0)::BIGINT as "items_sold!",
+                  COALESCE(SUM(ri.items_returned), 0)::BIGINT as "items_returned!",
+                  COALESCE(SUM(ri.items_discarded), 0)::BIGINT as "items_discarded!"
+           FROM daily_reconciliations dr
+           LEFT JOIN reconciliation_items ri ON ri.reconciliation_id = dr.id
+           WHERE dr.status = 'finalized'
+           GROUP BY dr.id
+           ORDER BY dr.id"#
+    )
+    .fetch_all(db_pool)
+    .await?;
+    let reconciliations_checked = reconciliations.len() as i64;
+
+    for r in &reconciliations {
+        for (field, stored, summed) in [
+            ("total_items_loaded", r.total_loaded, r.items_loaded),
+            ("total_items_sold", r.total_sold, r.items_sold),
+            ("total_items_returned", r.total_returned, r.items_returned),
+            ("total_items_discarded", r.total_discarded, r.items_discarded),
+        ] {
+            if stored != summed {
+                issues.push(IntegrityIssue {
+                    check: "reconciliation_totals".to_string(),
+                    entity: "daily_reconciliation".to_string(),
+                    entity_id: r.id as i64,
+                    expected: summed,
+                    actual: stored,
+                    message: format!("{field} is {stored} but the truck items add up to {summed}"),
+                    correction: None,
+                    repaired: false,
+                });
+            }
+        }
+    }
+
+    // Verified truck items: returned / discarded counts vs their per-batch lines
+    let verified_items = sqlx::query!(
+        r#"SELECT ri.id,
+                  ri.items_returned::BIGINT as "items_returned!",
+                  ri.items_discarded::BIGINT as "items_discarded!",
+                  COALESCE(SUM(l.quantity) FILTER (WHERE l.line_type = 'returned'), 0)::BIGINT as "lines_returned!",
+                  COALESCE(SUM(l.quantity) FILTER (WHERE l.line_type = 'discarded'), 0)::BIGINT as "lines_discarded!"
+           FROM reconciliation_items ri
+           LEFT JOIN reconciliation_item_lines l ON l.reconciliation_item_id = ri.id
+           WHERE ri.is_verified
+           GROUP BY ri.id
+           ORDER BY ri.id"#
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    for item in &verified_items {
+        for (field, stored, lines) in [
+            ("items_returned", item.items_returned, item.lines_returned),
+            ("items_discarded", item.items_discarded, item.lines_discarded),
+        ] {
+            if stored != lines {
+                issues.push(IntegrityIssue {
+                    check: "reconciliation_lines".to_string(),
+                    entity: "reconciliation_item".to_string(),
+                    entity_id: item.id as i64,
+                    expected: lines,
+                    actual: stored,
+                    message: format!("{field} is {stored} but the product lines add up to {lines}"),
+                    correction: None,
+                    repaired: false,
+                });
+            }
+        }
+    }
+
+    // Delivery lines: good units vs what their batch receipts put into stock
+    let delivery_items = sqlx::query!(
+        r#"SELECT di.id, di.quantity::BIGINT as "quantity!",
+                  COALESCE(SUM(br.quantity), 0)::BIGINT as "receipts!"
+           FROM delivery_items di
+           LEFT JOIN batch_receipts br ON br.delivery_item_id = di.id
+           GROUP BY di.id
+           ORDER BY di.id"#
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    for item in &delivery_items {
+        if item.quantity != item.receipts {
+            issues.push(IntegrityIssue {
+                check: "delivery_receipts".to_string(),
+                entity: "delivery_item".to_string(),
+                entity_id: item.id,
+                expected: item.receipts,
+                actual: item.quantity,
+                message: format!(
+                    "quantity is {} but the batch receipts add up to {}",
+                    item.quantity, item.receipts
+                ),
+                correction: None,
+                repaired: false,
+            });
+        }
+    }
+
+    let mut adjustments_posted = 0;
+    if !dry_run && issues.iter().any(|i| i.correction.is_some()) {
+        adjustments_posted = repair_batch_ledgers(db_pool, requested_by, &mut issues).await?;
+    }
+
+    let checked_at = sqlx::query_scalar!(r#"SELECT NOW() as "now!""#)
+        .fetch_one(db_pool)
+        .await?;
+
+    Ok(IntegrityReport {
+        checked_at,
+        dry_run,
+        batches_checked,
+        truck_load_items_checked,
+        reconciliations_checked,
+        adjustments_posted,
+        issues,
+    })
+}
+
+/// Post one adjustment per mismatched batch so its ledger equals remaining_quantity.
+/// The movement references the repaired batch under the 'integrity_repair' type.
+/// Each batch is locked and re-read first, so a movement written since the scan is
+/// taken into account.
+async fn repair_batch_ledgers(
+    db_pool: &PgPool,
+    requested_by: Option<i64>,
+    issues: &mut [IntegrityIssue],
+) -> Result<i32, AppError> {
+    let mut tx = db_pool.begin().await?;
+
+    let created_by = match requested_by {
+        Some(user_id) => user_id,
+        None => super::system_user_id(&mut tx).await?,
+    };
+
+    let mut posted = 0;
+    for issue in issues.iter_mut().filter(|i| i.correction.is_some()) {
+        let batch = sqlx::query!(
+            r#"SELECT b.id, b.product_id, b.batch_number, b.remaining_quantity,
+                      (SELECT COALESCE(SUM(stock_movement_effect(sm.movement_type, sm.reference_type, sm.quantity)), 0)
+                       FROM stock_movements sm WHERE sm.batch_id = b.id)::INT as "ledger_balance!"
+               FROM batches b
+               WHERE b.id = $1
+               FOR UPDATE OF b"#,
+            issue.entity_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let correction = batch.remaining_quantity - batch.ledger_balance;
+        issue.correction = Some(correction);
+        if correction == 0 {
+            continue;
+        }
+
+        sqlx::query!(
+            r#"INSERT INTO stock_movements
+               (batch_id, product_id, movement_type, quantity, reference_type, reference_id, notes, created_by, movement_date)
+               VALUES ($1, $2, 'adjustment', ($3)::FLOAT8::NUMERIC, 'integrity_repair', $1, $4, $5, CURRENT_DATE)"#,
+            batch.id as i32,
+            batch.product_id as i32,
+            correction as f64,
+            format!(
+                "Integrity repair - ledger {} vs remaining_quantity {} - Batch: {}",
+                batch.ledger_balance, batch.remaining_quantity, batch.batch_number
+            ),
+            created_by as i32
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        issue.repaired = true;
+        posted += 1;
+    }
+
+    tx.commit().await?;
+    Ok(posted)
+}
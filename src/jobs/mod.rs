@@ -0,0 +1,14 @@
+// Background tasks started by main alongside the HTTP server, and maintenance runs
+// shared by the API and the command line
+pub mod expiry_sweep;
+pub mod integrity_check;
+
+use crate::error::AppError;
+
+/// Id of the non-login `system` principal that owns unattended stock movements.
+pub async fn system_user_id(conn: &mut sqlx::PgConnection) -> Result<i64, AppError> {
+    sqlx::query_scalar!("SELECT id FROM users WHERE role = 'system'")
+        .fetch_optional(conn)
+        .await?
+        .ok_or_else(|| AppError::internal("System user is missing"))
+}
@@ -8,6 +8,8 @@ mod state;
 mod dtos; // expose DTO modules
 mod error;
 mod auth; // expose auth module
+mod money; // decimal money helpers
+mod jobs; // background tasks
 
 use axum::{routing::get, Router};
 use tracing_subscriber::fmt::init as tracing_init;
@@ -28,17 +30,39 @@ async fn main() {
         .expect("DATABASE_URL must be set");
     let db_pool = database::create_pool(&database_url).await
         .expect("Failed to create database pool");
-    
+
+    // `dairyx-backend integrity-check [--repair]`: print the integrity report and exit
+    // (status 2 while unrepaired issues remain) instead of starting the server
+    if std::env::args().nth(1).as_deref() == Some("integrity-check") {
+        let repair = std::env::args().any(|arg| arg == "--repair");
+        match jobs::integrity_check::run_check(&db_pool, !repair, None).await {
+            Ok(report) => {
+                println!("{}", serde_json::to_string_pretty(&report).expect("Report serializes"));
+                if report.issues.iter().any(|issue| !issue.repaired) {
+                    std::process::exit(2);
+                }
+            }
+            Err(e) => {
+                tracing::error!(error = ?e, "Integrity check failed");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // Background expiry write-offs
+    jobs::expiry_sweep::spawn(db_pool.clone());
+
     // Create application state
     let app_state = state::AppState::new(db_pool);
     
     // Build application under /DairyX base path
-    let api = routes::create_router()
+    let api = routes::create_router(app_state.clone())
         .route("/", get(|| async { "DairyX API" }))
         .route("/health", get(health_check));
 
     let app = Router::new()
-        .nest("/DairyX", api)
+        .nest(routes::API_BASE_PATH, api)
         .with_state(app_state);
     
     // Start server (axum 0.8 style) with HOST/PORT env and graceful port selection
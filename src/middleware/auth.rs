@@ -1,57 +1,150 @@
+use std::marker::PhantomData;
 use axum::{response::{Response, IntoResponse}};
-use axum::http::StatusCode;
+use axum::extract::{FromRequestParts, MatchedPath, State};
+use axum::http::{HeaderMap, Request, request::Parts};
 use axum::middleware::Next;
+use sqlx::PgPool;
 use crate::auth::jwt::verify_token;
-use serde::Serialize;
+use crate::auth::role::{Role, RoleMarker};
+use crate::error::AppError;
+use crate::routes::permissions::{self, Access};
+use crate::state::AppState;
 
 #[derive(Clone)]
 pub struct AuthContext {
     pub user_id: i64,
-    pub role: String,
+    pub role: Role,
     pub username: String,
+    pub session_id: i64,
 }
 
-#[derive(Serialize)]
-struct ErrorBody { error: String, code: &'static str }
-
-use axum::http::Request;
-
-pub async fn require_auth(mut req: Request<axum::body::Body>, next: Next) -> Response {
-    let auth_header = match req.headers()
-        .get("Authorization")
-        .and_then(|v| v.to_str().ok()) {
-        Some(h) => h,
-        None => return unauthorized("Missing Authorization header"),
+// Applied to every API route. Looks the route up in routes::permissions, authenticates
+// the caller unless the route is public, and rejects roles the table does not allow.
+// Routes missing from the table are refused so a new endpoint cannot ship unprotected.
+pub async fn authorize(
+    State(AppState { db_pool }): State<AppState>,
+    mut req: Request<axum::body::Body>,
+    next: Next,
+) -> Response {
+    let path = req.extensions().get::<MatchedPath>().map(|p| p.as_str().to_string());
+    let access = match path.as_deref().and_then(|p| permissions::access_for(req.method(), p)) {
+        Some(access) => access,
+        None => {
+            tracing::warn!(method = %req.method(), path = ?path, "No permission rule for route");
+            return AppError::forbidden("Route is not permitted").into_response();
+        }
     };
 
-    // Expect "Bearer <token>"
-    let token = match auth_header.strip_prefix("Bearer ") {
-        Some(t) => t,
-        None => return unauthorized("Invalid Authorization format"),
+    let required_role = match access {
+        Access::Public => return next.run(req).await,
+        Access::AnyRole => None,
+        Access::Role(role) => Some(*role),
     };
 
-    let secret = match std::env::var("JWT_SECRET") {
-        Ok(s) => s,
-        Err(_) => return unauthorized("Server auth misconfiguration"),
+    let auth = match authenticate(&db_pool, req.headers()).await {
+        Ok(auth) => auth,
+        Err(e) => return e.into_response(),
     };
 
-    let claims = match verify_token(token, &secret) {
-        Ok(c) => c,
-        Err(e) => return unauthorized(&format!("{e:?}")),
-    };
+    if let Some(role) = required_role {
+        if auth.role != role {
+            return AppError::forbidden(format!("This action requires the {role} role")).into_response();
+        }
+    }
 
     // Attach context
-    req.extensions_mut().insert(AuthContext {
-        user_id: claims.sub,
-        role: claims.role,
-        username: claims.username,
-        
-    });
+    req.extensions_mut().insert(auth);
 
     next.run(req).await
 }
 
-fn unauthorized(msg: &str) -> Response {
-    let body = axum::Json(ErrorBody { error: msg.to_string(), code: "unauthorized" });
-    (StatusCode::UNAUTHORIZED, body).into_response()
-}
\ No newline at end of file
+async fn authenticate(db_pool: &PgPool, headers: &HeaderMap) -> Result<AuthContext, AppError> {
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| AppError::unauthorized("Missing Authorization header"))?;
+
+    // Expect "Bearer <token>"
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| AppError::unauthorized("Invalid Authorization format"))?;
+
+    let secret = std::env::var("JWT_SECRET")
+        .map_err(|_| AppError::unauthorized("Server auth misconfiguration"))?;
+
+    let claims = verify_token(token, &secret)
+        .map_err(|e| AppError::unauthorized(format!("{e:?}")))?;
+
+    // The token alone is not enough: the session must still be open and the user active.
+    // Role and username come from the DB so role changes apply without re-login.
+    let user = sqlx::query!(
+        r#"SELECT u.role, u.username, u.is_active
+        FROM user_sessions s
+        JOIN users u ON u.id = s.user_id
+        WHERE s.id = $1 AND s.user_id = $2 AND s.revoked_at IS NULL"#,
+        claims.sid,
+        claims.sub
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(AppError::db)?
+    .ok_or_else(|| AppError::unauthorized("Session has been revoked"))?;
+
+    if !user.is_active {
+        return Err(AppError::unauthorized("User inactive"));
+    }
+
+    let role = user.role.parse::<Role>()
+        .map_err(|_| AppError::unauthorized("Unknown role"))?;
+
+    Ok(AuthContext {
+        user_id: claims.sub,
+        role,
+        username: user.username,
+        session_id: claims.sid,
+    })
+}
+
+fn auth_context(parts: &Parts) -> Result<AuthContext, AppError> {
+    parts
+        .extensions
+        .get::<AuthContext>()
+        .cloned()
+        .ok_or_else(|| AppError::unauthorized("Authentication required"))
+}
+
+/// Handler argument that only extracts for callers with role `R`, e.g. `RequireRole<Manager>`.
+pub struct RequireRole<R: RoleMarker> {
+    pub auth: AuthContext,
+    _role: PhantomData<R>,
+}
+
+impl<S, R> FromRequestParts<S> for RequireRole<R>
+where
+    S: Send + Sync,
+    R: RoleMarker,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let auth = auth_context(parts)?;
+        if auth.role != R::ROLE {
+            return Err(AppError::forbidden(format!("This action requires the {} role", R::ROLE)));
+        }
+        Ok(RequireRole { auth, _role: PhantomData })
+    }
+}
+
+/// Handler argument for any authenticated user; the handler decides per role.
+pub struct RequireAnyRole(pub AuthContext);
+
+impl<S> FromRequestParts<S> for RequireAnyRole
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        auth_context(parts).map(RequireAnyRole)
+    }
+}
@@ -1,12 +1,26 @@
-use serde::{Deserialize, Serialize};
+use rust_decimal::Decimal;
 use sqlx::FromRow;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 
 #[derive(Debug, FromRow)]
 pub struct Product {
     pub id: i64,
     pub name: String,
-    pub current_wholesale_price: f64,
-    pub commission_per_unit: f64,
+    pub current_wholesale_price: Decimal,
+    pub commission_per_unit: Decimal,
+    pub reorder_point: i32,
+    pub safety_stock: i32,
+    pub lead_time_days: i32,
+    pub supplier_id: Option<i64>,
     pub created_at: Option<DateTime<Utc>>,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, FromRow)]
+pub struct ProductPrice {
+    pub effective_from: NaiveDate,
+    pub effective_to: Option<NaiveDate>,
+    pub wholesale_price: Decimal,
+    pub commission_per_unit: Decimal,
+    pub changed_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,62 @@
+// src/money.rs
+// All monetary values (prices, commissions, allowances, sale and reconciliation totals)
+// are carried as rust_decimal::Decimal and map to Postgres NUMERIC(…, 2) without going
+// through FLOAT8. DTO fields use `#[serde(with = "crate::money")]` so the wire format is
+// a fixed-scale string ("220.00"); requests accept JSON numbers or strings.
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// Scale used for every rupee amount stored in the database.
+pub const MONEY_SCALE: u32 = 2;
+
+/// Round to cents (half away from zero) and force a fixed scale of 2,
+/// so "220.5" and 220.499 both become "220.50".
+pub fn normalize(amount: Decimal) -> Decimal {
+    let mut rounded = amount.round_dp_with_strategy(MONEY_SCALE, RoundingStrategy::MidpointAwayFromZero);
+    rounded.rescale(MONEY_SCALE);
+    rounded
+}
+
+/// Money amount for `quantity` units at `unit_amount` each.
+pub fn line_amount(quantity: i32, unit_amount: Decimal) -> Decimal {
+    normalize(Decimal::from(quantity) * unit_amount)
+}
+
+/// Cost per unit of `value` spread over `quantity` units, zero when there are none.
+pub fn average_cost(value: Decimal, quantity: i32) -> Decimal {
+    if quantity == 0 {
+        return zero();
+    }
+    normalize(value / Decimal::from(quantity))
+}
+
+/// Zero with the fixed money scale ("0.00").
+pub fn zero() -> Decimal {
+    normalize(Decimal::ZERO)
+}
+
+pub fn serialize<S: Serializer>(amount: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&normalize(*amount).to_string())
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+    <Decimal as Deserialize>::deserialize(deserializer).map(normalize)
+}
+
+/// Optional amounts (e.g. a sale line without an explicit unit price); `null` when absent.
+pub mod option {
+    use super::normalize;
+    use rust_decimal::Decimal;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(amount: &Option<Decimal>, serializer: S) -> Result<S::Ok, S::Error> {
+        match amount {
+            Some(amount) => super::serialize(amount, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Decimal>, D::Error> {
+        Option::<Decimal>::deserialize(deserializer).map(|a| a.map(normalize))
+    }
+}
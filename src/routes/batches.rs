@@ -5,6 +5,7 @@ use axum::{
 use crate::state::AppState;
 use crate::handlers::batch::{list_batches, get_batch};
 
+// Access rules: routes::permissions
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/batches", get(list_batches))
@@ -1,21 +1,22 @@
-use axum::{Router, routing::{get, post, put, delete}, middleware};
+use axum::{Router, routing::{get, patch, post, put}};
 use crate::state::AppState;
 use crate::handlers::delivery::{
     create_delivery, get_delivery, list_deliveries, update_delivery, delete_delivery,
+    add_delivery_item, update_delivery_item, delete_delivery_item, add_delivery_batch,
+    correct_delivery_batch,
 };
-use crate::middleware::auth::require_auth;
+use crate::handlers::delivery_reversal::{preview_delivery_reversal, reverse_delivery};
 
+// Access rules: routes::permissions
 pub fn routes() -> Router<AppState> {
-    // Public endpoints: list + get
-    let open = Router::new()
-        .route("/deliveries", get(list_deliveries))
-        .route("/deliveries/{id}", get(get_delivery));
-
-    // Protected endpoints: create/update/delete (JWT required)
-    let protected = Router::new()
-        .route("/deliveries", post(create_delivery))
+    Router::new()
+        .route("/deliveries", get(list_deliveries).post(create_delivery))
+        .route("/deliveries/{id}", get(get_delivery))
         .route("/deliveries/{id}", put(update_delivery).delete(delete_delivery))
-        .layer(middleware::from_fn(require_auth));
-
-    open.merge(protected)
+        // Line-level corrections
+        .route("/deliveries/{id}/items", post(add_delivery_item))
+        .route("/deliveries/{id}/items/{item_id}", patch(update_delivery_item).delete(delete_delivery_item))
+        .route("/deliveries/{id}/items/{item_id}/batches", post(add_delivery_batch))
+        .route("/deliveries/{id}/items/{item_id}/batches/{batch_id}", patch(correct_delivery_batch))
+        .route("/deliveries/{id}/reversal", get(preview_delivery_reversal).post(reverse_delivery))
 }
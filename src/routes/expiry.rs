@@ -0,0 +1,13 @@
+use axum::{
+    routing::get,
+    Router,
+};
+use crate::state::AppState;
+use crate::handlers::expiry;
+
+// Access rules: routes::permissions
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/expiry-sweeps", get(expiry::list_expiry_sweeps).post(expiry::run_expiry_sweep))
+        .route("/expiry-sweeps/{id}", get(expiry::get_expiry_sweep))
+}
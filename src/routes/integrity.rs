@@ -0,0 +1,13 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use crate::state::AppState;
+use crate::handlers::integrity;
+
+// Access rules: routes::permissions
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/integrity", get(integrity::check_integrity))
+        .route("/integrity/repair", post(integrity::repair_integrity))
+}
@@ -0,0 +1,13 @@
+use axum::{
+    routing::get,
+    Router,
+};
+use crate::state::AppState;
+use crate::handlers::inventory;
+
+// Access rules: routes::permissions
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/inventory/on-hand", get(inventory::get_on_hand))
+        .route("/inventory/valuation", get(inventory::get_valuation))
+}
@@ -9,11 +9,23 @@ pub mod allowances;
 pub mod reconciliations;
 pub mod stock_movements;
 pub mod batches;
+pub mod expiry;
+pub mod reports;
+pub mod inventory;
+pub mod integrity;
+pub mod stocktakes;
+pub mod purchase_orders;
+pub mod suppliers;
+pub mod supplier_claims;
+pub mod permissions;
 
-use axum::Router;
+use axum::{Router, middleware};
+use crate::middleware::auth::authorize;
 use crate::state::AppState;
 
-pub fn create_router() -> Router<AppState> {
+pub const API_BASE_PATH: &str = "/DairyX";
+
+pub fn create_router(state: AppState) -> Router<AppState> {
     Router::new()
         .merge(products::routes())
         .merge(users::routes())
@@ -26,4 +38,14 @@ pub fn create_router() -> Router<AppState> {
         .merge(reconciliations::routes())
         .merge(stock_movements::routes())
         .merge(batches::routes())
-}
\ No newline at end of file
+        .merge(expiry::routes())
+        .merge(reports::routes())
+        .merge(inventory::routes())
+        .merge(integrity::routes())
+        .merge(stocktakes::routes())
+        .merge(purchase_orders::routes())
+        .merge(suppliers::routes())
+        .merge(supplier_claims::routes())
+        // Every route above is checked against routes::permissions
+        .route_layer(middleware::from_fn_with_state(state, authorize))
+}
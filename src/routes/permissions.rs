@@ -0,0 +1,147 @@
+// Who may call each route. Enforced by middleware::auth::authorize for every route
+// registered in create_router; a route without an entry here is refused.
+// Handlers still declare their role with RequireRole<Manager> / RequireAnyRole.
+use axum::http::Method;
+use crate::auth::role::Role;
+use super::API_BASE_PATH;
+
+pub enum Access {
+    Public,
+    AnyRole,
+    Role(Role),
+}
+
+const MANAGER: Access = Access::Role(Role::Manager);
+
+pub static ROUTE_PERMISSIONS: &[(Method, &str, Access)] = &[
+    // Users
+    (Method::POST, "/users/login", Access::Public),
+    (Method::POST, "/users/refresh", Access::Public),
+    (Method::POST, "/users/logout", Access::AnyRole),
+    (Method::GET, "/users/me", Access::AnyRole),
+    (Method::PUT, "/users/me/password", Access::AnyRole),
+    (Method::POST, "/users/register", MANAGER),
+    (Method::GET, "/users", MANAGER),
+    (Method::GET, "/users/{id}", Access::AnyRole), // drivers: own account only
+    (Method::PATCH, "/users/{id}/role", MANAGER),
+    (Method::PATCH, "/users/{id}/deactivate", MANAGER),
+    (Method::PATCH, "/users/{id}/reactivate", MANAGER),
+    (Method::POST, "/users/{id}/reset-password", MANAGER),
+    // Products
+    (Method::GET, "/products", Access::AnyRole),
+    (Method::GET, "/products/{id}", Access::AnyRole),
+    (Method::GET, "/products/{id}/price-history", Access::AnyRole),
+    (Method::POST, "/products", MANAGER),
+    (Method::PUT, "/products/{id}", MANAGER),
+    (Method::DELETE, "/products/{id}", MANAGER),
+    // Suppliers (carry cost prices)
+    (Method::GET, "/suppliers", MANAGER),
+    (Method::POST, "/suppliers", MANAGER),
+    (Method::GET, "/suppliers/{id}", MANAGER),
+    (Method::PUT, "/suppliers/{id}", MANAGER),
+    (Method::PUT, "/suppliers/{id}/products/{product_id}", MANAGER),
+    (Method::DELETE, "/suppliers/{id}/products/{product_id}", MANAGER),
+    // Deliveries (supplier side, carries purchase prices)
+    (Method::GET, "/deliveries", MANAGER),
+    (Method::GET, "/deliveries/{id}", MANAGER),
+    (Method::POST, "/deliveries", MANAGER),
+    (Method::PUT, "/deliveries/{id}", MANAGER),
+    (Method::DELETE, "/deliveries/{id}", MANAGER),
+    (Method::POST, "/deliveries/{id}/items", MANAGER),
+    (Method::PATCH, "/deliveries/{id}/items/{item_id}", MANAGER),
+    (Method::DELETE, "/deliveries/{id}/items/{item_id}", MANAGER),
+    (Method::POST, "/deliveries/{id}/items/{item_id}/batches", MANAGER),
+    (Method::PATCH, "/deliveries/{id}/items/{item_id}/batches/{batch_id}", MANAGER),
+    (Method::GET, "/deliveries/{id}/reversal", MANAGER),
+    (Method::POST, "/deliveries/{id}/reversal", MANAGER),
+    // Supplier claims (damaged/short deliveries)
+    (Method::GET, "/supplier-claims", MANAGER),
+    (Method::GET, "/supplier-claims/{id}", MANAGER),
+    (Method::POST, "/supplier-claims/{id}/resolve", MANAGER),
+    // Purchase orders
+    (Method::GET, "/purchase-orders", MANAGER),
+    (Method::POST, "/purchase-orders", MANAGER),
+    (Method::GET, "/purchase-orders/{id}", MANAGER),
+    (Method::GET, "/purchase-orders/{id}/comparison", MANAGER),
+    (Method::POST, "/purchase-orders/{id}/close", MANAGER),
+    (Method::POST, "/purchase-orders/{id}/cancel", MANAGER),
+    // Batches
+    (Method::GET, "/batches", Access::AnyRole),
+    (Method::GET, "/batches/{id}", Access::AnyRole),
+    // Trucks
+    (Method::GET, "/trucks", Access::AnyRole),
+    (Method::GET, "/trucks/{id}", Access::AnyRole),
+    (Method::POST, "/trucks", MANAGER),
+    (Method::PUT, "/trucks/{id}", MANAGER),
+    (Method::DELETE, "/trucks/{id}", MANAGER),
+    (Method::PATCH, "/trucks/{id}/max-limit", MANAGER),
+    // Truck loads
+    (Method::GET, "/truck-loads", Access::AnyRole),
+    (Method::GET, "/truck-loads/{id}", Access::AnyRole),
+    (Method::POST, "/truck-loads", MANAGER),
+    (Method::PUT, "/truck-loads/{id}/reconcile", MANAGER),
+    (Method::DELETE, "/truck-loads/{id}", MANAGER),
+    // Shops
+    (Method::GET, "/shops", Access::AnyRole),
+    (Method::GET, "/shops/{id}", Access::AnyRole),
+    (Method::POST, "/shops", MANAGER),
+    (Method::PUT, "/shops/{id}", MANAGER),
+    (Method::DELETE, "/shops/{id}", MANAGER),
+    // Sales (drivers create sales and record payments only for their own truck)
+    (Method::GET, "/sales", Access::AnyRole),
+    (Method::POST, "/sales", Access::AnyRole),
+    (Method::GET, "/sales/{id}", Access::AnyRole),
+    (Method::PATCH, "/sales/{id}/payment", Access::AnyRole),
+    // Allowances
+    (Method::GET, "/allowances", MANAGER),
+    (Method::POST, "/allowances", MANAGER),
+    (Method::GET, "/allowances/{id}", MANAGER),
+    (Method::DELETE, "/allowances/{id}", MANAGER),
+    (Method::POST, "/allowances/{id}/allocate", MANAGER),
+    (Method::PATCH, "/allowances/{id}/trucks/{truck_id}", MANAGER),
+    (Method::POST, "/allowances/{id}/finalize", MANAGER),
+    // Reconciliations
+    (Method::POST, "/reconciliations/start", MANAGER),
+    (Method::GET, "/reconciliations", MANAGER),
+    (Method::GET, "/reconciliations/{date}", MANAGER),
+    (Method::POST, "/reconciliations/{date}/trucks/{truck_id}/verify", MANAGER),
+    (Method::POST, "/reconciliations/{date}/finalize", MANAGER),
+    // Stock movements
+    (Method::GET, "/stock-movements/batches/{batch_id}", Access::AnyRole),
+    (Method::GET, "/stock-movements/daily/{date}", Access::AnyRole),
+    (Method::GET, "/stock-movements/products/{product_id}", Access::AnyRole),
+    (Method::GET, "/stock-movements/balance", Access::AnyRole),
+    (Method::GET, "/stock-movements/balance-check", MANAGER),
+    (Method::POST, "/stock-movements/adjust", MANAGER),
+    // Stocktakes (any user can record counts)
+    (Method::GET, "/stocktakes", Access::AnyRole),
+    (Method::POST, "/stocktakes", MANAGER),
+    (Method::GET, "/stocktakes/{id}", Access::AnyRole),
+    (Method::PUT, "/stocktakes/{id}/counts", Access::AnyRole),
+    (Method::POST, "/stocktakes/{id}/post", MANAGER),
+    (Method::POST, "/stocktakes/{id}/cancel", MANAGER),
+    // Expiry write-offs
+    (Method::GET, "/expiry-sweeps", MANAGER),
+    (Method::POST, "/expiry-sweeps", MANAGER),
+    (Method::GET, "/expiry-sweeps/{id}", MANAGER),
+    // Reports (valued at delivery cost)
+    (Method::GET, "/reports/expiring", MANAGER),
+    (Method::GET, "/reports/reorder", MANAGER),
+    // Inventory
+    (Method::GET, "/inventory/on-hand", Access::AnyRole),
+    (Method::GET, "/inventory/valuation", MANAGER),
+    // Integrity checks
+    (Method::GET, "/integrity", MANAGER),
+    (Method::POST, "/integrity/repair", MANAGER),
+];
+
+/// Access rule for a matched route path (with or without the API base path).
+pub fn access_for(method: &Method, matched_path: &str) -> Option<&'static Access> {
+    let path = matched_path.strip_prefix(API_BASE_PATH).unwrap_or(matched_path);
+    // axum answers HEAD with the GET handler
+    let method = if method == Method::HEAD { &Method::GET } else { method };
+    ROUTE_PERMISSIONS
+        .iter()
+        .find(|(m, p, _)| m == method && *p == path)
+        .map(|(_, _, access)| access)
+}
@@ -1,14 +1,16 @@
 use axum::{
-    routing::{get, post, put, delete},
+    routing::get,
     Router,
 };
 use crate::handlers::product::{
-    get_products, get_product, create_product, update_product, delete_product
+    get_products, get_product, create_product, update_product, delete_product, get_price_history
 };
 use crate::state::AppState;
 
+// Access rules: routes::permissions
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/products", get(get_products).post(create_product))
-    .route("/products/{id}", get(get_product).put(update_product).delete(delete_product))
-}
\ No newline at end of file
+        .route("/products/{id}", get(get_product).put(update_product).delete(delete_product))
+        .route("/products/{id}/price-history", get(get_price_history))
+}
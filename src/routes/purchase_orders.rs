@@ -0,0 +1,19 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use crate::state::AppState;
+use crate::handlers::purchase_order;
+
+// Access rules: routes::permissions
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/purchase-orders",
+            get(purchase_order::list_purchase_orders).post(purchase_order::create_purchase_order),
+        )
+        .route("/purchase-orders/{id}", get(purchase_order::get_purchase_order))
+        .route("/purchase-orders/{id}/comparison", get(purchase_order::get_purchase_order_comparison))
+        .route("/purchase-orders/{id}/close", post(purchase_order::close_purchase_order))
+        .route("/purchase-orders/{id}/cancel", post(purchase_order::cancel_purchase_order))
+}
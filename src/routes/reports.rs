@@ -0,0 +1,13 @@
+use axum::{
+    routing::get,
+    Router,
+};
+use crate::state::AppState;
+use crate::handlers::report;
+
+// Access rules: routes::permissions
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/reports/expiring", get(report::get_expiring_report))
+        .route("/reports/reorder", get(report::get_reorder_report))
+}
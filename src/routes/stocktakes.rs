@@ -0,0 +1,16 @@
+use axum::{
+    routing::{get, post, put},
+    Router,
+};
+use crate::state::AppState;
+use crate::handlers::stocktake;
+
+// Access rules: routes::permissions
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/stocktakes", get(stocktake::list_stocktakes).post(stocktake::create_stocktake))
+        .route("/stocktakes/{id}", get(stocktake::get_stocktake))
+        .route("/stocktakes/{id}/counts", put(stocktake::record_counts))
+        .route("/stocktakes/{id}/post", post(stocktake::post_stocktake))
+        .route("/stocktakes/{id}/cancel", post(stocktake::cancel_stocktake))
+}
@@ -0,0 +1,14 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use crate::state::AppState;
+use crate::handlers::supplier_claim;
+
+// Access rules: routes::permissions
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/supplier-claims", get(supplier_claim::list_supplier_claims))
+        .route("/supplier-claims/{id}", get(supplier_claim::get_supplier_claim))
+        .route("/supplier-claims/{id}/resolve", post(supplier_claim::resolve_supplier_claim))
+}
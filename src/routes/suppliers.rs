@@ -0,0 +1,17 @@
+use axum::{
+    routing::{get, put},
+    Router,
+};
+use crate::state::AppState;
+use crate::handlers::supplier;
+
+// Access rules: routes::permissions
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/suppliers", get(supplier::list_suppliers).post(supplier::create_supplier))
+        .route("/suppliers/{id}", get(supplier::get_supplier).put(supplier::update_supplier))
+        .route(
+            "/suppliers/{id}/products/{product_id}",
+            put(supplier::set_supplier_price).delete(supplier::remove_supplier_price),
+        )
+}
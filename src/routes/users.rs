@@ -1,16 +1,23 @@
-use axum::{Router, routing::{post, get}, middleware};
+use axum::{Router, routing::{post, get, patch, put}};
 use crate::state::AppState;
-use crate::handlers::user::{register_user, login_user, get_me};
-use crate::middleware::auth::require_auth;
+use crate::handlers::user::{
+    register_user, login_user, refresh_token, logout_user, get_me, list_users, get_user, update_user_role,
+    deactivate_user, reactivate_user, reset_user_password, change_my_password,
+};
 
+// Access rules: routes::permissions
 pub fn routes() -> Router<AppState> {
-    let open = Router::new()
+    Router::new()
+        .route("/users/login", post(login_user))
+        .route("/users/refresh", post(refresh_token))
         .route("/users/register", post(register_user))
-        .route("/users/login", post(login_user));
-
-    let protected = Router::new()
+        .route("/users/logout", post(logout_user))
         .route("/users/me", get(get_me))
-        .layer(middleware::from_fn(require_auth));
-
-    open.merge(protected)
-}
\ No newline at end of file
+        .route("/users/me/password", put(change_my_password))
+        .route("/users", get(list_users))
+        .route("/users/{id}", get(get_user))
+        .route("/users/{id}/role", patch(update_user_role))
+        .route("/users/{id}/deactivate", patch(deactivate_user))
+        .route("/users/{id}/reactivate", patch(reactivate_user))
+        .route("/users/{id}/reset-password", post(reset_user_password))
+}